@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
@@ -7,12 +7,22 @@ use crate::{
 	typ::{InfoTypeError, TypeError},
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Span<'a> {
 	pub file: Cow<'a, str>,
 	pub index: usize,
 }
 
+impl Span<'_> {
+	/// Detaches the span from the source it was parsed from, so it can be stored in IR
+	pub fn into_owned(self) -> Span<'static> {
+		Span {
+			file: Cow::Owned(self.file.into_owned()),
+			index: self.index,
+		}
+	}
+}
+
 #[derive(Debug)]
 pub struct InfoError<'a> {
 	pub span: Span<'a>,
@@ -25,6 +35,15 @@ pub enum Error<'a> {
 	TypeError(TypeError),
 }
 
+impl Display for Error<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Error::ParseError(error) => write!(f, "{error}"),
+			Error::TypeError(error) => write!(f, "{error}"),
+		}
+	}
+}
+
 impl<'a> From<InfoParseError<'a>> for InfoError<'a> {
 	fn from(value: InfoParseError<'a>) -> Self {
 		Self {
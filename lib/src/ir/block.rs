@@ -3,6 +3,7 @@ use crate::ir::Operation;
 use crate::ir::Statement;
 use crate::ir::to_ir;
 use crate::parser::expression::InfoExpr;
+use crate::typ::InfoTypeError;
 use crate::typ::type_id;
 use crate::value::Value;
 use crate::value::primitive::EmptyTuple;
@@ -14,14 +15,14 @@ pub fn compile_block<'a>(
 	store: Option<usize>,
 	tail: bool,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let mut i = 0;
 	let len = statements.len();
 	for statement in statements {
 		if i != len - 1 || !returns {
-			to_ir(block, statement, None, false, context);
+			to_ir(block, statement, None, false, context)?;
 		} else {
-			to_ir(block, statement, store, tail, context);
+			to_ir(block, statement, store, tail, context)?;
 		}
 		i += 1;
 	}
@@ -32,4 +33,6 @@ pub fn compile_block<'a>(
 			operation: Operation::LoadLiteral(Value::new(EmptyTuple {}, type_id::empty_tuple)),
 		});
 	}
+
+	Ok(())
 }
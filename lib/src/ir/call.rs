@@ -1,42 +1,92 @@
+use crate::error::Span;
 use crate::ir::Callable;
 use crate::ir::IRContext;
 use crate::ir::Operation;
 use crate::ir::Statement;
 use crate::ir::Terminal;
 use crate::ir::to_ir;
-use crate::parser::expression::InfoExpr;
+use crate::parser::expression::{Expr, InfoExpr};
+use crate::parser::typ::InfoTypeExpr;
+use crate::typ::{InfoTypeError, TypeExpr};
 
 pub fn call<'a>(
 	callee: Box<InfoExpr<'a>>,
 	args: Vec<InfoExpr<'a>>,
+	span: Span<'a>,
 	block: &mut usize,
 	store: Option<usize>,
 	tail: bool,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let callee = *callee;
 
+	let inferred = match &callee.expr {
+		Expr::Name(name) if !is_local(name, context) => {
+			if context.ins.needs_inference(name, context.generics)? {
+				let mut arg_types = Vec::new();
+				for arg in &args {
+					arg_types.push(context.infer_type(arg)?);
+				}
+				Some(
+					context
+						.ins
+						.instantiate_call(name, context.generics, &args, &arg_types)?,
+				)
+			} else {
+				None
+			}
+		}
+		_ => None,
+	};
+
 	let mut arg_indexes = Vec::new();
 	for arg in args {
 		let i = context.var();
-		to_ir(block, arg, Some(i), false, context);
+		to_ir(block, arg, Some(i), false, context)?;
 		arg_indexes.push(i);
 	}
 
 	let fn_var = context.var();
-	to_ir(block, callee, Some(fn_var), false, context);
+	if let Some(function) = inferred {
+		context.blocks[*block].statements.push(Statement {
+			store: Some(fn_var),
+			operation: Operation::LoadFunction(function),
+		});
+	} else {
+		to_ir(block, callee, Some(fn_var), false, context)?;
+	}
+
+	emit_call(fn_var, arg_indexes, span, block, store, tail, context);
 
+	Ok(())
+}
+
+fn is_local(name: &InfoTypeExpr<'_>, context: &IRContext<'_, '_>) -> bool {
+	matches!(&name.expr, TypeExpr::Name(name, params) if params.is_empty() && context.locals.contains_key(name))
+}
+
+pub fn emit_call(
+	fn_var: usize,
+	args: Vec<usize>,
+	span: Span<'_>,
+	block: &mut usize,
+	store: Option<usize>,
+	tail: bool,
+	context: &mut IRContext<'_, '_>,
+) {
 	if tail {
 		context.blocks[*block].terminal = Terminal::TailCall {
 			function: Callable::Var(fn_var),
-			args: arg_indexes,
+			args,
+			span: span.into_owned(),
 		}
 	} else {
 		context.blocks[*block].statements.push(Statement {
 			store,
 			operation: Operation::Call {
 				function: Callable::Var(fn_var),
-				args: arg_indexes,
+				args,
+				span: span.into_owned(),
 			},
 		});
 	}
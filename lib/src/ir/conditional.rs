@@ -1,4 +1,5 @@
 use crate::ir::IRContext;
+use crate::typ::InfoTypeError;
 use crate::typ::type_id;
 use crate::value::Value;
 use crate::value::primitive::EmptyTuple;
@@ -16,9 +17,9 @@ pub fn conditional<'a>(
 	store: Option<usize>,
 	tail: bool,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let cond_var = context.var();
-	to_ir(block, *cond, Some(cond_var), false, context);
+	to_ir(block, *cond, Some(cond_var), false, context)?;
 
 	let then_block_n = context.blocks.len();
 	let mut then_block_n_mut = context.blocks.len();
@@ -33,7 +34,7 @@ pub fn conditional<'a>(
 		Some(then_block_var),
 		tail,
 		context,
-	);
+	)?;
 
 	let else_block = if let Some(els) = els {
 		let else_block_n = context.blocks.len();
@@ -49,7 +50,7 @@ pub fn conditional<'a>(
 			Some(else_block_var),
 			tail,
 			context,
-		);
+		)?;
 		Some((else_block_n, else_block_var))
 	} else {
 		None
@@ -85,4 +86,6 @@ pub fn conditional<'a>(
 			});
 		}
 	}
+
+	Ok(())
 }
@@ -1,4 +1,5 @@
 use crate::ir::{IRContext, Operation, Statement};
+use crate::typ::InfoTypeError;
 use crate::{
 	ir::{Block, Terminal, to_ir},
 	parser::expression::InfoExpr,
@@ -11,9 +12,9 @@ pub fn guard<'a>(
 	store: Option<usize>,
 	tail: bool,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let dep_var = context.var();
-	to_ir(block, *dependency, Some(dep_var), false, context);
+	to_ir(block, *dependency, Some(dep_var), false, context)?;
 
 	let body_block = context.blocks.len();
 	let mut body_block_mut = body_block;
@@ -31,7 +32,7 @@ pub fn guard<'a>(
 		terminal: old_terminal,
 	});
 
-	to_ir(&mut body_block_mut, *body, store, tail, context);
+	to_ir(&mut body_block_mut, *body, store, tail, context)?;
 
 	context.blocks[*block].terminal = Terminal::Guard {
 		dependency: dep_var,
@@ -49,4 +50,6 @@ pub fn guard<'a>(
 			},
 		});
 	}
+
+	Ok(())
 }
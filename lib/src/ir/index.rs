@@ -1,4 +1,5 @@
 use crate::ir::{IRContext, to_ir};
+use crate::typ::InfoTypeError;
 use crate::{
 	ir::{Operation, Statement},
 	parser::expression::InfoExpr,
@@ -9,14 +10,16 @@ pub fn index<'a>(
 	block: &mut usize,
 	store: Option<usize>,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let left_var = context.var();
-	to_ir(block, *left, Some(left_var), false, context);
+	to_ir(block, *left, Some(left_var), false, context)?;
 	let right_var = context.var();
-	to_ir(block, *right, Some(right_var), false, context);
+	to_ir(block, *right, Some(right_var), false, context)?;
 
 	context.blocks[*block].statements.push(Statement {
 		store,
 		operation: Operation::Index(left_var, right_var),
 	});
+
+	Ok(())
 }
@@ -1,34 +1,85 @@
 use std::collections::HashMap;
 
 use crate::{
-	ir::{IRContext, Operation, Statement, to_ir},
+	error::Span,
+	ir::{IRContext, Operation, Statement, call::emit_call, to_ir},
 	parser::{expression::InfoExpr, typ::InfoTypeExpr},
+	typ::{ConcreteType, InfoTypeError, Type, TypeError},
 };
 
 pub fn initialize_struct<'a>(
 	typ: InfoTypeExpr<'a>,
 	fields: HashMap<String, InfoExpr<'a>>,
+	base: Option<Box<InfoExpr<'a>>>,
+	span: Span<'a>,
 	block: &mut usize,
 	store: Option<usize>,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	if let Some(store) = store {
+		let struct_type = context.ins.instantiate(&typ, context.generics)?;
+
 		let mut field_vars: HashMap<String, usize> = HashMap::new();
 		for (field_name, field_expr) in fields {
 			let field_var = context.var();
 			field_vars.insert(field_name, field_var);
 
-			to_ir(block, field_expr, Some(field_var), false, context);
+			to_ir(block, field_expr, Some(field_var), false, context)?;
+		}
+
+		// Fields that weren't given come from the base struct, or otherwise their defaults
+		let missing: Vec<String> = match context.ins.get_type(struct_type) {
+			Some(Type::Concrete(ConcreteType::Struct(struct_fields))) => struct_fields
+				.keys()
+				.filter(|name| !field_vars.contains_key(*name))
+				.cloned()
+				.collect(),
+			_ => Vec::new(),
+		};
+
+		if let Some(base) = base {
+			let base_var = context.var();
+			to_ir(block, *base, Some(base_var), false, context)?;
+			for field_name in missing {
+				let field_var = context.var();
+				context.blocks[*block].statements.push(Statement {
+					store: Some(field_var),
+					operation: Operation::Access(base_var, field_name.clone()),
+				});
+				field_vars.insert(field_name, field_var);
+			}
+		} else {
+			for field_name in missing {
+				let Some(default) = context.ins.field_default(struct_type, &field_name)? else {
+					return Err(InfoTypeError {
+						span,
+						error: TypeError::MissingField(field_name),
+					});
+				};
+				let fn_var = context.var();
+				context.blocks[*block].statements.push(Statement {
+					store: Some(fn_var),
+					operation: Operation::LoadFunction(default),
+				});
+				let field_var = context.var();
+				emit_call(
+					fn_var,
+					Vec::new(),
+					span.clone(),
+					block,
+					Some(field_var),
+					false,
+					context,
+				);
+				field_vars.insert(field_name, field_var);
+			}
 		}
+
 		context.blocks[*block].statements.push(Statement {
 			store: Some(store),
-			operation: Operation::InitializeStruct(
-				context
-					.ins
-					.instantiate(&typ, context.generics)
-					.expect("Pass type error up properly"),
-				field_vars,
-			),
+			operation: Operation::InitializeStruct(struct_type, field_vars),
 		});
 	}
+
+	Ok(())
 }
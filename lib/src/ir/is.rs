@@ -2,7 +2,7 @@ use crate::{
 	error::Span,
 	ir::{IRContext, Operation, Statement, variable::variable},
 	parser::typ::InfoTypeExpr,
-	typ::TypeExpr,
+	typ::{InfoTypeError, TypeExpr},
 };
 
 pub fn is<'a>(
@@ -12,7 +12,7 @@ pub fn is<'a>(
 	block: &mut usize,
 	store: Option<usize>,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let checked_var = context.var();
 
 	variable(
@@ -23,18 +23,17 @@ pub fn is<'a>(
 		block,
 		Some(checked_var),
 		context,
-	);
+	)?;
 
 	if let Some(store) = store {
 		context.blocks[*block].statements.push(Statement {
 			store: Some(store),
 			operation: Operation::Is {
 				value: checked_var,
-				typ: context
-					.ins
-					.instantiate(&typ, context.generics)
-					.expect("Pass type errors up as IRErrors"),
+				typ: context.ins.instantiate(&typ, context.generics)?,
 			},
 		});
 	}
+
+	Ok(())
 }
@@ -2,15 +2,16 @@ use crate::ir::Block;
 use crate::ir::Operation;
 use crate::ir::Statement;
 use crate::tokeniser::Literal;
+use crate::typ::InfoTypeError;
 use crate::typ::type_id;
 use crate::value::Value;
 
 pub fn literal<'a>(
 	lit: Literal,
-	function: &mut Vec<Block>,
+	function: &mut [Block],
 	block: &mut usize,
 	store: Option<usize>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	if let Some(store) = store {
 		function[*block].statements.push(Statement {
 			store: Some(store),
@@ -22,4 +23,6 @@ pub fn literal<'a>(
 			}),
 		});
 	}
+
+	Ok(())
 }
@@ -0,0 +1,46 @@
+use crate::error::Span;
+use crate::ir::IRContext;
+use crate::ir::Operation;
+use crate::ir::Statement;
+use crate::ir::call::emit_call;
+use crate::ir::to_ir;
+use crate::parser::expression::InfoExpr;
+use crate::typ::InfoTypeError;
+
+#[allow(clippy::too_many_arguments)]
+pub fn method_call<'a>(
+	receiver: Box<InfoExpr<'a>>,
+	method: String,
+	args: Vec<InfoExpr<'a>>,
+	span: Span<'a>,
+	block: &mut usize,
+	store: Option<usize>,
+	tail: bool,
+	context: &mut IRContext<'_, 'a>,
+) -> Result<(), InfoTypeError<'a>> {
+	let receiver_type = context.infer_type(&receiver)?;
+	let function =
+		context
+			.ins
+			.instantiate_method(receiver_type, &method, &receiver.idx, context.generics)?;
+
+	let receiver_var = context.var();
+	to_ir(block, *receiver, Some(receiver_var), false, context)?;
+
+	let mut arg_indexes = vec![receiver_var];
+	for arg in args {
+		let i = context.var();
+		to_ir(block, arg, Some(i), false, context)?;
+		arg_indexes.push(i);
+	}
+
+	let fn_var = context.var();
+	context.blocks[*block].statements.push(Statement {
+		store: Some(fn_var),
+		operation: Operation::LoadFunction(function),
+	});
+
+	emit_call(fn_var, arg_indexes, span, block, store, tail, context);
+
+	Ok(())
+}
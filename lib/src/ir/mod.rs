@@ -7,18 +7,27 @@ mod index;
 mod initialize_struct;
 mod is;
 mod literal;
+mod method_call;
+mod parse;
+mod print;
 mod returns;
 mod to_ir;
+mod type_literal;
 mod variable;
 mod variable_declaration;
+mod verify;
 
+pub use parse::*;
+pub use print::*;
 pub use to_ir::*;
+pub use verify::*;
 
 use std::{collections::HashMap, fmt::Debug};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
+	error::Span,
 	typ::Type,
 	value::{PrevalValue, Value, runtime_type::TypeDeserializer},
 	vm::{RunResult, evaluate},
@@ -93,6 +102,7 @@ pub enum Operation {
 	Call {
 		function: Callable,
 		args: Vec<usize>,
+		span: Span<'static>,
 	},
 	LoadLiteral(Value),
 	LoadLocal {
@@ -113,6 +123,9 @@ pub enum Operation {
 		value: usize,
 		typ: usize,
 	},
+	StructType(HashMap<String, usize>),
+	UnionType(usize, usize),
+	TupleType(Vec<usize>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -148,6 +161,7 @@ pub enum Terminal {
 	TailCall {
 		function: Callable,
 		args: Vec<usize>,
+		span: Span<'static>,
 	},
 }
 
@@ -0,0 +1,808 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display, iter::Peekable, str::Chars};
+
+use crate::{
+	error::Span,
+	ir::{Block, Callable, Function, Operation, Partial, Statement, Terminal},
+	typ::{ConcreteType, Implementation, IntegerSize, Type},
+	value::{
+		Value,
+		builtin::Builtin,
+		list::List,
+		native::NativeFunction,
+		primitive::{EmptyTuple, IO, TypeValue},
+		reflection::{Reflect, Reflection},
+		runtime_type::Poison,
+		structure::Struct,
+	},
+	vm::{EvalError, RunResult},
+};
+
+/// Where text given to [`parse_program`] or [`parse_blocks`] stopped making sense
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrParseError {
+	pub line: usize,
+	pub column: usize,
+	pub message: String,
+}
+
+impl Display for IrParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}:{}: {}", self.line, self.column, self.message)
+	}
+}
+
+/// Parses a program written by [`print_program`](super::print_program)
+pub fn parse_program(text: &str) -> Result<(RunResult, Vec<Type>), IrParseError> {
+	let mut parser = Parser::new(text)?;
+	let mut types = Vec::new();
+	while parser.peek_id('t') {
+		let id = parser.id('t')?;
+		if id != types.len() {
+			return Err(parser.error(format!("expected t{} to be defined next", types.len())));
+		}
+		parser.punct('=')?;
+		types.push(parser.typ()?);
+	}
+	parser.keyword("program")?;
+	parser.punct('=')?;
+	let program = parser.result()?;
+	parser.end()?;
+	Ok((program, types))
+}
+
+/// Parses blocks written by [`print_blocks`](super::print_blocks), which is handy for
+/// writing IR by hand
+pub fn parse_blocks(text: &str) -> Result<Vec<Block>, IrParseError> {
+	let mut parser = Parser::new(text)?;
+	let blocks = parser.body()?;
+	parser.end()?;
+	Ok(blocks)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+	Ident(String),
+	Number(usize),
+	String(String),
+	Punct(char),
+	Arrow,
+}
+
+impl Display for Token {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Token::Ident(name) => write!(f, "`{name}`"),
+			Token::Number(number) => write!(f, "`{number}`"),
+			Token::String(string) => write!(f, "{string:?}"),
+			Token::Punct(c) => write!(f, "`{c}`"),
+			Token::Arrow => write!(f, "`->`"),
+		}
+	}
+}
+
+/// Reads the characters of the text, keeping track of where it is
+struct Cursor<'t> {
+	chars: Peekable<Chars<'t>>,
+	line: usize,
+	column: usize,
+}
+
+impl Cursor<'_> {
+	fn peek(&mut self) -> Option<char> {
+		self.chars.peek().copied()
+	}
+
+	fn next(&mut self) -> Option<char> {
+		let c = self.chars.next();
+		match c {
+			Some('\n') => (self.line, self.column) = (self.line + 1, 1),
+			Some(_) => self.column += 1,
+			None => {}
+		}
+		c
+	}
+
+	fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+		let mut taken = String::new();
+		while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+			taken.push(c);
+			self.next();
+		}
+		taken
+	}
+
+	/// The rest of a string after its opening quote, with escapes written like Rust's
+	fn string(&mut self) -> Result<String, &'static str> {
+		let mut string = String::new();
+		loop {
+			let c = match self.next().ok_or("unterminated string")? {
+				'"' => return Ok(string),
+				'\\' => match self.next() {
+					Some('n') => '\n',
+					Some('t') => '\t',
+					Some('r') => '\r',
+					Some('0') => '\0',
+					Some(c @ ('\\' | '"' | '\'')) => c,
+					Some('u') if self.next() == Some('{') => {
+						let code = self.take_while(|c| c != '}' && c != '"');
+						self.next();
+						u32::from_str_radix(&code, 16)
+							.ok()
+							.and_then(char::from_u32)
+							.ok_or("invalid escape")?
+					}
+					_ => return Err("invalid escape"),
+				},
+				c => c,
+			};
+			string.push(c);
+		}
+	}
+}
+
+struct Parser {
+	/// Tokens along with their line and column
+	tokens: Vec<(Token, usize, usize)>,
+	pos: usize,
+	/// Where the text ends, for errors about it ending early
+	end: (usize, usize),
+}
+
+impl Parser {
+	fn new(text: &str) -> Result<Self, IrParseError> {
+		let mut cursor = Cursor {
+			chars: text.chars().peekable(),
+			line: 1,
+			column: 1,
+		};
+		let mut tokens = Vec::new();
+
+		while let Some(c) = cursor.peek() {
+			let (line, column) = (cursor.line, cursor.column);
+			let error = |message: &str| IrParseError {
+				line,
+				column,
+				message: message.to_string(),
+			};
+
+			let token = match c {
+				_ if c.is_whitespace() => {
+					cursor.next();
+					continue;
+				}
+				'/' => {
+					cursor.next();
+					if cursor.next() != Some('/') {
+						return Err(error("expected a comment"));
+					}
+					while !matches!(cursor.next(), Some('\n') | None) {}
+					continue;
+				}
+				'-' => {
+					cursor.next();
+					if cursor.next() != Some('>') {
+						return Err(error("expected `->`"));
+					}
+					Token::Arrow
+				}
+				'0'..='9' => {
+					let digits = cursor.take_while(|c| c.is_ascii_digit());
+					Token::Number(digits.parse().map_err(|_| error("number is too large"))?)
+				}
+				'"' => {
+					cursor.next();
+					Token::String(cursor.string().map_err(error)?)
+				}
+				_ if c.is_ascii_alphabetic() || c == '_' => {
+					Token::Ident(cursor.take_while(|c| c.is_ascii_alphanumeric() || c == '_'))
+				}
+				'{' | '}' | '(' | ')' | '[' | ']' | '<' | '>' | ':' | ',' | '=' | '.' | '|'
+				| '@' => {
+					cursor.next();
+					Token::Punct(c)
+				}
+				_ => return Err(error(&format!("unexpected `{c}`"))),
+			};
+			tokens.push((token, line, column));
+		}
+
+		Ok(Parser {
+			tokens,
+			pos: 0,
+			end: (cursor.line, cursor.column),
+		})
+	}
+
+	fn error(&self, message: String) -> IrParseError {
+		let (line, column) = match self.tokens.get(self.pos) {
+			Some((_, line, column)) => (*line, *column),
+			None => self.end,
+		};
+		IrParseError {
+			line,
+			column,
+			message,
+		}
+	}
+
+	fn expected(&self, what: &str) -> IrParseError {
+		match self.peek() {
+			Some(token) => self.error(format!("expected {what}, found {token}")),
+			None => self.error(format!("expected {what}, found the end")),
+		}
+	}
+
+	fn peek(&self) -> Option<&Token> {
+		self.nth(0)
+	}
+
+	fn nth(&self, n: usize) -> Option<&Token> {
+		self.tokens.get(self.pos + n).map(|(token, _, _)| token)
+	}
+
+	fn end(&self) -> Result<(), IrParseError> {
+		match self.peek() {
+			None => Ok(()),
+			Some(_) => Err(self.expected("the end")),
+		}
+	}
+
+	fn eat_punct(&mut self, c: char) -> bool {
+		let found = self.peek() == Some(&Token::Punct(c));
+		if found {
+			self.pos += 1;
+		}
+		found
+	}
+
+	fn punct(&mut self, c: char) -> Result<(), IrParseError> {
+		match self.eat_punct(c) {
+			true => Ok(()),
+			false => Err(self.expected(&format!("`{c}`"))),
+		}
+	}
+
+	fn peek_keyword(&self, keyword: &str) -> bool {
+		matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword)
+	}
+
+	fn eat_keyword(&mut self, keyword: &str) -> bool {
+		let found = self.peek_keyword(keyword);
+		if found {
+			self.pos += 1;
+		}
+		found
+	}
+
+	fn keyword(&mut self, keyword: &str) -> Result<(), IrParseError> {
+		match self.eat_keyword(keyword) {
+			true => Ok(()),
+			false => Err(self.expected(&format!("`{keyword}`"))),
+		}
+	}
+
+	fn ident(&mut self) -> Result<String, IrParseError> {
+		match self.peek() {
+			Some(Token::Ident(ident)) => {
+				let ident = ident.clone();
+				self.pos += 1;
+				Ok(ident)
+			}
+			_ => Err(self.expected("a name")),
+		}
+	}
+
+	/// A field or member name, which is quoted if it isn't an identifier
+	fn name(&mut self) -> Result<String, IrParseError> {
+		match self.peek() {
+			Some(Token::String(_)) => self.string(),
+			_ => self.ident(),
+		}
+	}
+
+	fn string(&mut self) -> Result<String, IrParseError> {
+		match self.peek() {
+			Some(Token::String(string)) => {
+				let string = string.clone();
+				self.pos += 1;
+				Ok(string)
+			}
+			_ => Err(self.expected("a string")),
+		}
+	}
+
+	fn number(&mut self) -> Result<usize, IrParseError> {
+		match self.peek() {
+			Some(Token::Number(number)) => {
+				let number = *number;
+				self.pos += 1;
+				Ok(number)
+			}
+			_ => Err(self.expected("a number")),
+		}
+	}
+
+	/// Parses `open item, item, ... close`, allowing a trailing comma
+	fn list<T>(
+		&mut self,
+		open: char,
+		close: char,
+		mut item: impl FnMut(&mut Self) -> Result<T, IrParseError>,
+	) -> Result<Vec<T>, IrParseError> {
+		self.punct(open)?;
+		let mut items = Vec::new();
+		while !self.eat_punct(close) {
+			items.push(item(self)?);
+			if !self.eat_punct(',') {
+				self.punct(close)?;
+				break;
+			}
+		}
+		Ok(items)
+	}
+
+	/// Whether the next token is a `v`, `b` or `t` id
+	fn peek_id(&self, prefix: char) -> bool {
+		self.nth_is_id(0, prefix)
+	}
+
+	fn nth_is_id(&self, n: usize, prefix: char) -> bool {
+		matches!(self.nth(n), Some(Token::Ident(ident)) if Self::split_id(ident, prefix).is_some())
+	}
+
+	fn split_id(ident: &str, prefix: char) -> Option<usize> {
+		let digits = ident.strip_prefix(prefix)?;
+		match digits.chars().all(|c| c.is_ascii_digit()) {
+			true => digits.parse().ok(),
+			false => None,
+		}
+	}
+
+	fn id(&mut self, prefix: char) -> Result<usize, IrParseError> {
+		let what = match prefix {
+			'v' => "a variable",
+			'b' => "a block",
+			_ => "a type",
+		};
+		match self.peek() {
+			Some(Token::Ident(ident)) => match Self::split_id(ident, prefix) {
+				Some(id) => {
+					self.pos += 1;
+					Ok(id)
+				}
+				None => Err(self.expected(what)),
+			},
+			_ => Err(self.expected(what)),
+		}
+	}
+
+	fn var(&mut self) -> Result<usize, IrParseError> {
+		self.id('v')
+	}
+
+	fn block(&mut self) -> Result<usize, IrParseError> {
+		self.id('b')
+	}
+
+	fn type_id(&mut self) -> Result<usize, IrParseError> {
+		self.id('t')
+	}
+
+	fn generics(&mut self) -> Result<Vec<usize>, IrParseError> {
+		match self.peek() == Some(&Token::Punct('<')) {
+			true => self.list('<', '>', Self::type_id),
+			false => Ok(Vec::new()),
+		}
+	}
+
+	fn span(&mut self) -> Result<Span<'static>, IrParseError> {
+		self.punct('@')?;
+		let file = self.string()?;
+		self.punct(':')?;
+		Ok(Span {
+			file: Cow::Owned(file),
+			index: self.number()?,
+		})
+	}
+
+	fn typ(&mut self) -> Result<Type, IrParseError> {
+		if self.peek_id('t') {
+			let a = self.type_id()?;
+			self.punct('|')?;
+			return Ok(Type::Union(a, self.type_id()?));
+		}
+		if self.peek() == Some(&Token::Punct('(')) {
+			let elems = self.list('(', ')', Self::type_id)?;
+			return Ok(Type::Concrete(ConcreteType::Tuple(elems)));
+		}
+
+		let ident = self.ident()?;
+		let integer = |signed, size: &str| {
+			let size = match size {
+				"size" => IntegerSize::Size,
+				bits => IntegerSize::Number(bits.parse().ok()?),
+			};
+			Some(Type::Concrete(ConcreteType::Integer { size, signed }))
+		};
+		if let Some(typ) = ident
+			.strip_prefix('u')
+			.and_then(|size| integer(false, size))
+			.or_else(|| ident.strip_prefix('i').and_then(|size| integer(true, size)))
+		{
+			return Ok(typ);
+		}
+		if let Some(size) = ident.strip_prefix('f').and_then(|size| size.parse().ok()) {
+			return Ok(Type::Concrete(ConcreteType::Float { size }));
+		}
+
+		Ok(match ident.as_str() {
+			"bool" => Type::Concrete(ConcreteType::Bool),
+			"String" => Type::Concrete(ConcreteType::String),
+			"IO" => Type::Concrete(ConcreteType::IO),
+			"type" => Type::Concrete(ConcreteType::Type),
+			"struct" => {
+				let fields = self.list('{', '}', |p| {
+					let name = p.name()?;
+					p.punct(':')?;
+					Ok((name, p.type_id()?))
+				})?;
+				Type::Concrete(ConcreteType::Struct(fields.into_iter().collect()))
+			}
+			"fn" => {
+				let args = self.list('(', ')', Self::type_id)?;
+				if self.peek() != Some(&Token::Arrow) {
+					return Err(self.expected("`->`"));
+				}
+				self.pos += 1;
+				let ret = self.type_id()?;
+				let implementation = match self.peek() {
+					Some(Token::Punct('{')) => Implementation::Normal(self.blocks()?),
+					_ => match self.ident()?.as_str() {
+						"native" => Implementation::Native(self.native()?),
+						"builtin" => Implementation::Builtin(self.builtin()?),
+						"reflect" => Implementation::Reflect(self.reflection()?),
+						_ => {
+							self.pos -= 1;
+							return Err(self.expected("a function implementation"));
+						}
+					},
+				};
+				Type::Concrete(ConcreteType::Function(args, ret, implementation))
+			}
+			"list" => Type::Concrete(ConcreteType::List(self.type_id()?)),
+			"module" => Type::Concrete(ConcreteType::Module(self.list('[', ']', Self::name)?)),
+			"early_return" => Type::EarlyReturn,
+			"placeholder" => Type::Placeholder(self.number()?),
+			"unification" => Type::UnificationVar(self.number()?),
+			_ => {
+				self.pos -= 1;
+				return Err(self.expected("a type"));
+			}
+		})
+	}
+
+	fn native(&mut self) -> Result<NativeFunction, IrParseError> {
+		Ok(NativeFunction::new(self.string()?, self.string()?))
+	}
+
+	fn builtin(&mut self) -> Result<Builtin, IrParseError> {
+		let name = self.ident()?;
+		let Some(builtin) = Builtin::named(&name) else {
+			self.pos -= 1;
+			return Err(self.error(format!("unknown builtin `{name}`")));
+		};
+		Ok(match builtin {
+			Builtin::ListNew(_) | Builtin::Args(_) => {
+				self.punct('(')?;
+				let typ = self.type_id()?;
+				self.punct(')')?;
+				builtin.returning(typ)
+			}
+			builtin => builtin,
+		})
+	}
+
+	fn reflection(&mut self) -> Result<Reflection, IrParseError> {
+		let name = self.ident()?;
+		let Some(reflect) = Reflect::ALL.iter().find(|reflect| reflect.name() == name) else {
+			self.pos -= 1;
+			return Err(self.error(format!("unknown reflection `{name}`")));
+		};
+		let type_name = self.string()?;
+		self.keyword("fields")?;
+		let fields = self.list('[', ']', |p| {
+			let name = p.name()?;
+			p.punct(':')?;
+			Ok((name, p.string()?))
+		})?;
+		self.keyword("members")?;
+		Ok(Reflection {
+			reflect: *reflect,
+			type_name,
+			fields,
+			members: self.list('[', ']', Self::string)?,
+		})
+	}
+
+	fn value(&mut self) -> Result<Value, IrParseError> {
+		let typ = self.type_id()?;
+		match self.peek() {
+			Some(Token::Number(number)) => {
+				let number = *number;
+				self.pos += 1;
+				return Ok(Value::new(number, typ));
+			}
+			Some(Token::String(_)) => return Ok(Value::new(self.string()?, typ)),
+			Some(Token::Punct('(')) => {
+				self.pos += 1;
+				self.punct(')')?;
+				return Ok(Value::new(EmptyTuple, typ));
+			}
+			Some(Token::Punct('{')) => {
+				let fields = self.list('{', '}', |p| {
+					let name = p.name()?;
+					p.punct(':')?;
+					match p.eat_keyword("_") {
+						true => Ok((name, None)),
+						false => Ok((name, Some(p.value()?))),
+					}
+				})?;
+				let fields = fields.into_iter().collect();
+				return Ok(Value::new(Struct { fields }, typ));
+			}
+			Some(Token::Punct('[')) => {
+				return Ok(Value::new(List(self.list('[', ']', Self::value)?), typ));
+			}
+			_ => {}
+		}
+
+		Ok(match self.ident()?.as_str() {
+			"true" => Value::new(true, typ),
+			"false" => Value::new(false, typ),
+			"io" => Value::new(IO, typ),
+			"function" => Value::new(self.function()?, typ),
+			"partial" => Value::new(self.partial()?, typ),
+			"native" => Value::new(self.native()?, typ),
+			"builtin" => Value::new(self.builtin()?, typ),
+			"reflect" => Value::new(self.reflection()?, typ),
+			"type" => Value::new(TypeValue(self.type_id()?), typ),
+			"poison" => Value::new(Poison, typ),
+			_ => {
+				self.pos -= 1;
+				return Err(self.expected("a value"));
+			}
+		})
+	}
+
+	/// A function value, after `function`
+	fn function(&mut self) -> Result<Function, IrParseError> {
+		let generics = self.generics()?;
+		let exported = self.eat_keyword("exported");
+		Ok(Function {
+			ir: self.blocks()?,
+			exported,
+			generics,
+		})
+	}
+
+	/// A partial, after `partial`
+	fn partial(&mut self) -> Result<Partial, IrParseError> {
+		let generics = self.generics()?;
+		self.keyword("start")?;
+		let start_block = self.block()?;
+		Ok(Partial {
+			blocks: self.blocks()?,
+			start_block,
+			generics,
+		})
+	}
+
+	fn result(&mut self) -> Result<RunResult, IrParseError> {
+		if self.eat_keyword("partial") {
+			return Ok(RunResult::Partial(self.partial()?));
+		}
+		if self.eat_keyword("residualise") {
+			return Ok(RunResult::Residualise);
+		}
+		if self.eat_keyword("error") {
+			let message = self.string()?;
+			let mut trace = Vec::new();
+			while self.peek() == Some(&Token::Punct('@')) {
+				trace.push(self.span()?);
+			}
+			return Ok(RunResult::Error(EvalError {
+				message,
+				trace,
+				check: false,
+			}));
+		}
+		match self.peek_id('t') {
+			true => Ok(RunResult::Concrete(self.value()?)),
+			false => Err(self.expected("a result")),
+		}
+	}
+
+	fn blocks(&mut self) -> Result<Vec<Block>, IrParseError> {
+		self.punct('{')?;
+		let blocks = self.body()?;
+		self.punct('}')?;
+		Ok(blocks)
+	}
+
+	fn body(&mut self) -> Result<Vec<Block>, IrParseError> {
+		let mut blocks = Vec::new();
+		while self.peek_id('b') {
+			let id = self.block()?;
+			if id != blocks.len() {
+				self.pos -= 1;
+				return Err(self.error(format!("expected b{} to come next", blocks.len())));
+			}
+			self.punct(':')?;
+
+			let mut statements = Vec::new();
+			let terminal = loop {
+				if let Some(terminal) = self.terminal()? {
+					break terminal;
+				}
+				statements.push(self.statement()?);
+			};
+			blocks.push(Block {
+				statements,
+				terminal,
+			});
+		}
+		Ok(blocks)
+	}
+
+	fn statement(&mut self) -> Result<Statement, IrParseError> {
+		let store = match self.peek_id('v') && self.nth(1) == Some(&Token::Punct('=')) {
+			true => {
+				let store = self.var()?;
+				self.pos += 1;
+				Some(store)
+			}
+			false => None,
+		};
+
+		if self.peek_id('v') {
+			let value = self.var()?;
+			let operation = if self.eat_punct('[') {
+				let index = self.var()?;
+				self.punct(']')?;
+				Operation::Index(value, index)
+			} else if self.eat_punct('.') {
+				Operation::Access(value, self.name()?)
+			} else if self.eat_keyword("is") {
+				Operation::Is {
+					value,
+					typ: self.type_id()?,
+				}
+			} else {
+				Operation::LoadLocal { src: value }
+			};
+			return Ok(Statement { store, operation });
+		}
+
+		let operation = match self.ident()?.as_str() {
+			"call" => {
+				let (function, args, span) = self.call()?;
+				Operation::Call {
+					function,
+					args,
+					span,
+				}
+			}
+			"const" => Operation::LoadLiteral(self.value()?),
+			"phi" => {
+				let sources = self.list('[', ']', |p| {
+					let block = p.block()?;
+					p.punct(':')?;
+					Ok((block, p.var()?))
+				})?;
+				Operation::Phi {
+					block_to_var: sources.into_iter().collect(),
+				}
+			}
+			"guard_phi" => {
+				let block = self.block()?;
+				self.punct(':')?;
+				Operation::GuardPhi {
+					block,
+					var: self.var()?,
+				}
+			}
+			"struct" => Operation::InitializeStruct(self.type_id()?, self.fields()?),
+			"fn" => Operation::LoadFunction(self.type_id()?),
+			"struct_type" => Operation::StructType(self.fields()?),
+			"union_type" => {
+				let a = self.var()?;
+				self.punct('|')?;
+				Operation::UnionType(a, self.var()?)
+			}
+			"tuple_type" => Operation::TupleType(self.list('(', ')', Self::var)?),
+			_ => {
+				self.pos -= 1;
+				return Err(self.expected("a statement"));
+			}
+		};
+		Ok(Statement { store, operation })
+	}
+
+	fn fields(&mut self) -> Result<HashMap<String, usize>, IrParseError> {
+		let fields = self.list('{', '}', |p| {
+			let name = p.name()?;
+			p.punct(':')?;
+			Ok((name, p.var()?))
+		})?;
+		Ok(fields.into_iter().collect())
+	}
+
+	/// The function, arguments and span of a call, after `call`
+	fn call(&mut self) -> Result<(Callable, Vec<usize>, Span<'static>), IrParseError> {
+		let function = match self.eat_keyword("partial") {
+			true => Callable::Partial(self.partial()?),
+			false => Callable::Var(self.var()?),
+		};
+		let args = self.list('(', ')', Self::var)?;
+		Ok((function, args, self.span()?))
+	}
+
+	/// Parses the terminal of a block, or returns `None` if a statement comes next
+	fn terminal(&mut self) -> Result<Option<Terminal>, IrParseError> {
+		let Some(Token::Ident(keyword)) = self.peek() else {
+			return Err(self.expected("a statement or terminal"));
+		};
+		let keyword = keyword.clone();
+		if !["return", "jump", "if", "branch", "guard", "tail"].contains(&keyword.as_str()) {
+			return Ok(None);
+		}
+		self.pos += 1;
+
+		Ok(Some(match keyword.as_str() {
+			"return" => Terminal::Return(self.var()?),
+			"jump" => Terminal::Jump(self.block()?),
+			"if" => {
+				let cond = self.var()?;
+				self.keyword("then")?;
+				let then = self.block()?;
+				self.keyword("else")?;
+				Terminal::CondJump {
+					cond,
+					then,
+					els: self.block()?,
+				}
+			}
+			"branch" => {
+				let cond = self.var()?;
+				self.keyword("then")?;
+				let then = self.result()?;
+				self.keyword("else")?;
+				Terminal::Branch {
+					cond,
+					then,
+					els: self.result()?,
+				}
+			}
+			"guard" => {
+				let dependency = self.var()?;
+				self.keyword("then")?;
+				let body = self.block()?;
+				self.keyword("continue")?;
+				Terminal::Guard {
+					dependency,
+					body,
+					continuation: self.block()?,
+				}
+			}
+			_ => {
+				self.keyword("call")?;
+				let (function, args, span) = self.call()?;
+				Terminal::TailCall {
+					function,
+					args,
+					span,
+				}
+			}
+		}))
+	}
+}
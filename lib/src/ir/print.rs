@@ -0,0 +1,400 @@
+//! A textual form of IR, which is easier to read than the IR's debug output and can
+//! be parsed back with [`parse_program`](super::parse_program):
+//!
+//! ```text
+//! t0 = usize
+//! t4 = fn(t3) -> t0 builtin string_length
+//!
+//! program = partial start b0 {
+//!     b0:
+//!         v2 = fn t4
+//!         v3 = call v2(v1) @ "./main.pv":42
+//!         if v3 then b1 else b2
+//!     ...
+//! }
+//! ```
+//!
+//! Variables are written `v3`, blocks `b3` and types `t3`. Literals are written after
+//! their type, like `t0 5`, and results of branches are either literals, partials,
+//! `residualise` or `error "message"`.
+
+use crate::{
+	error::Span,
+	ir::{Block, Callable, Function, Operation, Partial, Statement, Terminal},
+	typ::{ConcreteType, Implementation, IntegerSize, Type},
+	value::{
+		Value, builtin::Builtin, list::List, native::NativeFunction, primitive::TypeValue,
+		reflection::Reflection, runtime_type::TypeDeserializer, structure::Struct,
+	},
+	vm::RunResult,
+};
+
+/// Prints a compiled program along with the types it uses
+pub fn print_program(program: &RunResult, types: &[Type]) -> String {
+	let mut printer = Printer::default();
+	for (id, typ) in types.iter().enumerate() {
+		printer.text(&format!("t{id} = "));
+		printer.typ(typ);
+		printer.line();
+	}
+	if !types.is_empty() {
+		printer.line();
+	}
+	printer.text("program = ");
+	printer.result(program);
+	printer.line();
+	printer.out
+}
+
+/// Prints the blocks of a function, starting with `b0:`
+pub fn print_blocks(blocks: &[Block]) -> String {
+	let mut printer = Printer::default();
+	printer.body(blocks);
+	printer.line();
+	printer.out.trim_start().to_string()
+}
+
+#[derive(Default)]
+struct Printer {
+	out: String,
+	indent: usize,
+}
+
+impl Printer {
+	fn text(&mut self, text: &str) {
+		self.out += text;
+	}
+
+	fn line(&mut self) {
+		self.out.push('\n');
+		for _ in 0..self.indent {
+			self.out.push('\t');
+		}
+	}
+
+	/// Separates items with commas, which `item` prints
+	fn list<T>(&mut self, items: impl IntoIterator<Item = T>, mut item: impl FnMut(&mut Self, T)) {
+		for (i, value) in items.into_iter().enumerate() {
+			if i > 0 {
+				self.text(", ");
+			}
+			item(self, value);
+		}
+	}
+
+	/// Field and member names, which are quoted unless they're identifiers
+	fn name(&mut self, name: &str) {
+		let mut chars = name.chars();
+		let identifier = chars
+			.next()
+			.is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+			&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+		match identifier {
+			true => self.text(name),
+			false => self.text(&format!("{name:?}")),
+		}
+	}
+
+	fn generics(&mut self, generics: &[usize]) {
+		if !generics.is_empty() {
+			self.text("<");
+			self.list(generics, |p, typ| p.text(&format!("t{typ}")));
+			self.text(">");
+		}
+	}
+
+	fn span(&mut self, span: &Span) {
+		self.text(&format!(" @ {:?}:{}", span.file, span.index));
+	}
+
+	fn typ(&mut self, typ: &Type) {
+		match typ {
+			Type::Concrete(ConcreteType::Integer { size, signed }) => {
+				let sign = if *signed { "i" } else { "u" };
+				match size {
+					IntegerSize::Size => self.text(&format!("{sign}size")),
+					IntegerSize::Number(bits) => self.text(&format!("{sign}{bits}")),
+				}
+			}
+			Type::Concrete(ConcreteType::Float { size }) => self.text(&format!("f{size}")),
+			Type::Concrete(ConcreteType::Bool) => self.text("bool"),
+			Type::Concrete(ConcreteType::String) => self.text("String"),
+			Type::Concrete(ConcreteType::IO) => self.text("IO"),
+			Type::Concrete(ConcreteType::Type) => self.text("type"),
+			Type::Concrete(ConcreteType::Struct(fields)) => {
+				let mut fields = fields.iter().collect::<Vec<_>>();
+				fields.sort();
+				self.text("struct {");
+				self.list(fields, |p, (name, typ)| {
+					p.name(name);
+					p.text(&format!(": t{typ}"));
+				});
+				self.text("}");
+			}
+			Type::Concrete(ConcreteType::Function(args, ret, implementation)) => {
+				self.text("fn(");
+				self.list(args, |p, arg| p.text(&format!("t{arg}")));
+				self.text(&format!(") -> t{ret} "));
+				match implementation {
+					Implementation::Native(native) => self.native(native),
+					Implementation::Builtin(builtin) => self.builtin(builtin),
+					Implementation::Reflect(reflection) => self.reflection(reflection),
+					Implementation::Normal(blocks) => self.blocks(blocks),
+				}
+			}
+			Type::Concrete(ConcreteType::Tuple(elems)) => {
+				self.text("(");
+				self.list(elems, |p, elem| p.text(&format!("t{elem}")));
+				self.text(")");
+			}
+			Type::Concrete(ConcreteType::List(elem)) => self.text(&format!("list t{elem}")),
+			Type::Concrete(ConcreteType::Module(path)) => {
+				self.text("module [");
+				self.list(path, |p, name| p.name(name));
+				self.text("]");
+			}
+			Type::Union(a, b) => self.text(&format!("t{a} | t{b}")),
+			Type::EarlyReturn => self.text("early_return"),
+			Type::Placeholder(id) => self.text(&format!("placeholder {id}")),
+			Type::UnificationVar(id) => self.text(&format!("unification {id}")),
+		}
+	}
+
+	fn native(&mut self, native: &NativeFunction) {
+		self.text(&format!(
+			"native {:?} {:?}",
+			native.lib_name, native.func_name
+		));
+	}
+
+	fn builtin(&mut self, builtin: &Builtin) {
+		self.text(&format!("builtin {}", builtin.name()));
+		if let Builtin::ListNew(typ) | Builtin::Args(typ) = builtin {
+			self.text(&format!("(t{typ})"));
+		}
+	}
+
+	fn reflection(&mut self, reflection: &Reflection) {
+		self.text(&format!(
+			"reflect {} {:?} fields [",
+			reflection.reflect.name(),
+			reflection.type_name
+		));
+		self.list(&reflection.fields, |p, (name, typ)| {
+			p.name(name);
+			p.text(&format!(": {typ:?}"));
+		});
+		self.text("] members [");
+		self.list(&reflection.members, |p, member| {
+			p.text(&format!("{member:?}"))
+		});
+		self.text("]");
+	}
+
+	fn value(&mut self, value: &Value) {
+		self.text(&format!("t{} ", value.typ));
+		match &value.deserializer {
+			TypeDeserializer::USize => self.text(&data::<usize>(value).to_string()),
+			TypeDeserializer::Bool => self.text(&data::<bool>(value).to_string()),
+			TypeDeserializer::String => self.text(&format!("{:?}", data::<String>(value))),
+			TypeDeserializer::Tuple(_) => self.text("()"),
+			TypeDeserializer::IO => self.text("io"),
+			TypeDeserializer::Struct => {
+				let mut fields = data::<Struct>(value).fields.iter().collect::<Vec<_>>();
+				fields.sort_by(|a, b| a.0.cmp(b.0));
+				self.text("{");
+				self.list(fields, |p, (name, field)| {
+					p.name(name);
+					p.text(": ");
+					match field {
+						Some(field) => p.value(field),
+						None => p.text("_"),
+					}
+				});
+				self.text("}");
+			}
+			TypeDeserializer::List => {
+				self.text("[");
+				self.list(&data::<List>(value).0, Self::value);
+				self.text("]");
+			}
+			TypeDeserializer::Function => self.function(data::<Function>(value)),
+			TypeDeserializer::Partial => self.partial(data::<Partial>(value)),
+			TypeDeserializer::NativeFunction => self.native(data::<NativeFunction>(value)),
+			TypeDeserializer::Builtin => self.builtin(data::<Builtin>(value)),
+			TypeDeserializer::Reflection => self.reflection(data::<Reflection>(value)),
+			TypeDeserializer::Type => self.text(&format!("type t{}", data::<TypeValue>(value).0)),
+			TypeDeserializer::Poison => self.text("poison"),
+		}
+	}
+
+	fn function(&mut self, function: &Function) {
+		self.text("function");
+		self.generics(&function.generics);
+		if function.exported {
+			self.text(" exported");
+		}
+		self.text(" ");
+		self.blocks(&function.ir);
+	}
+
+	fn partial(&mut self, partial: &Partial) {
+		self.text("partial");
+		self.generics(&partial.generics);
+		self.text(&format!(" start b{} ", partial.start_block));
+		self.blocks(&partial.blocks);
+	}
+
+	fn result(&mut self, result: &RunResult) {
+		match result {
+			RunResult::Concrete(value) => self.value(value),
+			RunResult::Partial(partial) => self.partial(partial),
+			RunResult::Residualise => self.text("residualise"),
+			RunResult::Error(error) => {
+				self.text(&format!("error {:?}", error.message));
+				for span in &error.trace {
+					self.span(span);
+				}
+			}
+		}
+	}
+
+	fn blocks(&mut self, blocks: &[Block]) {
+		self.text("{");
+		self.indent += 1;
+		self.body(blocks);
+		self.indent -= 1;
+		self.line();
+		self.text("}");
+	}
+
+	fn body(&mut self, blocks: &[Block]) {
+		for (id, block) in blocks.iter().enumerate() {
+			self.line();
+			self.text(&format!("b{id}:"));
+			self.indent += 1;
+			for statement in &block.statements {
+				self.line();
+				self.statement(statement);
+			}
+			self.line();
+			self.terminal(&block.terminal);
+			self.indent -= 1;
+		}
+	}
+
+	fn statement(&mut self, statement: &Statement) {
+		if let Some(store) = statement.store {
+			self.text(&format!("v{store} = "));
+		}
+		match &statement.operation {
+			Operation::Call {
+				function,
+				args,
+				span,
+			} => {
+				self.text("call ");
+				self.call(function, args, span);
+			}
+			Operation::LoadLiteral(value) => {
+				self.text("const ");
+				self.value(value);
+			}
+			Operation::LoadLocal { src } => self.text(&format!("v{src}")),
+			Operation::Phi { block_to_var } => {
+				let mut sources = block_to_var.iter().collect::<Vec<_>>();
+				sources.sort();
+				self.text("phi [");
+				self.list(sources, |p, (block, var)| {
+					p.text(&format!("b{block}: v{var}"))
+				});
+				self.text("]");
+			}
+			Operation::GuardPhi { block, var } => self.text(&format!("guard_phi b{block}: v{var}")),
+			Operation::Index(value, index) => self.text(&format!("v{value}[v{index}]")),
+			Operation::Access(value, field) => {
+				self.text(&format!("v{value}."));
+				self.name(field);
+			}
+			Operation::InitializeStruct(typ, fields) => {
+				self.text(&format!("struct t{typ} "));
+				self.fields(fields.iter());
+			}
+			Operation::LoadFunction(typ) => self.text(&format!("fn t{typ}")),
+			Operation::Is { value, typ } => self.text(&format!("v{value} is t{typ}")),
+			Operation::StructType(fields) => {
+				self.text("struct_type ");
+				self.fields(fields.iter());
+			}
+			Operation::UnionType(a, b) => self.text(&format!("union_type v{a} | v{b}")),
+			Operation::TupleType(elems) => {
+				self.text("tuple_type (");
+				self.list(elems, |p, elem| p.text(&format!("v{elem}")));
+				self.text(")");
+			}
+		}
+	}
+
+	/// Struct fields holding variables, sorted by name
+	fn fields<'f>(&mut self, fields: impl Iterator<Item = (&'f String, &'f usize)>) {
+		let mut fields = fields.collect::<Vec<_>>();
+		fields.sort();
+		self.text("{");
+		self.list(fields, |p, (name, var)| {
+			p.name(name);
+			p.text(&format!(": v{var}"));
+		});
+		self.text("}");
+	}
+
+	fn call(&mut self, function: &Callable, args: &[usize], span: &Span) {
+		match function {
+			Callable::Var(var) => self.text(&format!("v{var}")),
+			Callable::Partial(partial) => self.partial(partial),
+		}
+		self.text("(");
+		self.list(args, |p, arg| p.text(&format!("v{arg}")));
+		self.text(")");
+		self.span(span);
+	}
+
+	fn terminal(&mut self, terminal: &Terminal) {
+		match terminal {
+			Terminal::Return(var) => self.text(&format!("return v{var}")),
+			Terminal::Jump(block) => self.text(&format!("jump b{block}")),
+			Terminal::CondJump { cond, then, els } => {
+				self.text(&format!("if v{cond} then b{then} else b{els}"))
+			}
+			Terminal::Branch { cond, then, els } => {
+				self.text(&format!("branch v{cond} then "));
+				self.result(then);
+				self.text(" else ");
+				self.result(els);
+			}
+			Terminal::Guard {
+				dependency,
+				body,
+				continuation,
+			} => self.text(&format!(
+				"guard v{dependency} then b{body} continue b{continuation}"
+			)),
+			Terminal::TailCall {
+				function,
+				args,
+				span,
+			} => {
+				self.text("tail call ");
+				self.call(function, args, span);
+			}
+		}
+	}
+}
+
+/// The data of a value, which its deserializer says the type of
+fn data<T: 'static>(value: &Value) -> &T {
+	value
+		.data
+		.as_any()
+		.downcast_ref()
+		.expect("value doesn't hold the data its kind says")
+}
@@ -4,6 +4,7 @@ use crate::ir::Statement;
 use crate::ir::Terminal;
 use crate::ir::to_ir;
 use crate::parser::expression::InfoExpr;
+use crate::typ::InfoTypeError;
 use crate::typ::type_id;
 use crate::value::Value;
 use crate::value::primitive::EmptyTuple;
@@ -12,16 +13,19 @@ pub fn returns<'a>(
 	value_expr: Option<Box<InfoExpr<'a>>>,
 	block: &mut usize,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	let return_var = context.var();
-	context.blocks[*block].terminal = Terminal::Return(if let Some(value_expr) = value_expr {
-		to_ir(block, *value_expr, Some(return_var), true, context);
-		return_var
-	} else {
-		context.blocks[*block].statements.push(Statement {
+	match value_expr {
+		Some(value_expr) => to_ir(block, *value_expr, Some(return_var), true, context)?,
+		None => context.blocks[*block].statements.push(Statement {
 			store: Some(return_var),
 			operation: Operation::LoadLiteral(Value::new(EmptyTuple, type_id::empty_tuple)),
-		});
-		return_var
-	});
+		}),
+	}
+	// Returning a call has already ended the block with a tail call
+	if !matches!(context.blocks[*block].terminal, Terminal::TailCall { .. }) {
+		context.blocks[*block].terminal = Terminal::Return(return_var);
+	}
+
+	Ok(())
 }
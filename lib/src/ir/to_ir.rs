@@ -1,14 +1,22 @@
 use std::collections::HashMap;
 
 use crate::{
-	ir::{Block, access::access, guard::guard, initialize_struct::initialize_struct, is::is},
-	parser::expression::{Expr, InfoExpr},
-	typ::Instantiator,
+	ir::{
+		Block, access::access, guard::guard, initialize_struct::initialize_struct, is::is,
+		method_call::method_call,
+	},
+	parser::{
+		expression::{Expr, InfoExpr},
+		typ::InfoTypeExpr,
+	},
+	passes::type_check_expr::{Scope, infer_expr_type},
+	typ::{InfoTypeError, Instantiator, TypeExpr},
 };
 
 use crate::ir::{
 	block::compile_block, call::call, conditional::conditional, index::index, literal::literal,
-	returns::returns, variable::variable, variable_declaration::variable_declaration,
+	returns::returns, type_literal::type_literal, variable::variable,
+	variable_declaration::variable_declaration,
 };
 
 pub struct IRContext<'a, 'typ> {
@@ -17,6 +25,12 @@ pub struct IRContext<'a, 'typ> {
 	pub blocks: &'a mut Vec<Block>,
 	pub locals: &'a mut HashMap<String, usize>,
 	pub next_var: &'a mut usize,
+	/// Static types of locals, or why they couldn't be typed, only needed to resolve
+	/// method calls
+	pub local_types: &'a mut HashMap<String, Result<usize, InfoTypeError<'typ>>>,
+	/// `let` bindings whose types haven't been inferred yet
+	pub pending_locals: &'a mut Vec<(String, InfoExpr<'typ>)>,
+	pub return_type: usize,
 }
 
 impl<'a, 'typ> IRContext<'a, 'typ> {
@@ -24,6 +38,98 @@ impl<'a, 'typ> IRContext<'a, 'typ> {
 		*self.next_var += 1;
 		*self.next_var
 	}
+
+	/// Infers the static type of `expr` in the current function.
+	/// Locals are only typed on demand because most functions never need it, and each
+	/// is typed once.
+	pub fn infer_type(&mut self, expr: &InfoExpr<'typ>) -> Result<usize, InfoTypeError<'typ>> {
+		for (name, value) in std::mem::take(self.pending_locals) {
+			let typ = self.infer_with_locals(&value);
+			self.local_types.insert(name, typ);
+		}
+		self.infer_with_locals(expr)
+	}
+
+	fn infer_with_locals(&mut self, expr: &InfoExpr<'typ>) -> Result<usize, InfoTypeError<'typ>> {
+		// A local that couldn't be typed would otherwise be looked up as a type
+		if let Some(error) = untyped_local(expr, self.local_types, &mut Vec::new()) {
+			return Err(error.clone());
+		}
+
+		let mut scope = Scope::new();
+		for (name, typ) in self.local_types.iter() {
+			if let Ok(typ) = typ {
+				scope.insert(name.clone(), *typ);
+			}
+		}
+		infer_expr_type(
+			expr.clone(),
+			self.ins,
+			&mut scope,
+			self.return_type,
+			self.generics,
+			&[],
+		)
+		.map(|typed| typed.typ)
+	}
+}
+
+/// Why a local that `expr` uses couldn't be typed, ignoring names `expr` binds itself
+fn untyped_local<'e, 'typ>(
+	expr: &InfoExpr<'typ>,
+	local_types: &'e HashMap<String, Result<usize, InfoTypeError<'typ>>>,
+	bound: &mut Vec<String>,
+) -> Option<&'e InfoTypeError<'typ>> {
+	let local = match &expr.expr {
+		Expr::Name(InfoTypeExpr {
+			expr: TypeExpr::Name(name, params),
+			..
+		}) if params.is_empty() => Some(name),
+		Expr::Is { name, .. } => Some(name),
+		_ => None,
+	};
+	if let Some(name) = local {
+		if bound.contains(name) {
+			return None;
+		}
+		return local_types.get(name)?.as_ref().err();
+	}
+
+	let mut find = |expr: &InfoExpr<'typ>| untyped_local(expr, local_types, bound);
+	match &expr.expr {
+		Expr::Block(statements, _) => {
+			let outer = bound.len();
+			let mut found = None;
+			for statement in statements {
+				found = untyped_local(statement, local_types, bound);
+				if found.is_some() {
+					break;
+				}
+				if let Expr::Let(name, _) = &statement.expr {
+					bound.push(name.clone());
+				}
+			}
+			bound.truncate(outer);
+			found
+		}
+		Expr::Let(_, value) | Expr::Access(value, _) => find(value),
+		Expr::Index(left, right) => find(left).or_else(|| find(right)),
+		Expr::Call(callee, args) => find(callee).or_else(|| args.iter().find_map(find)),
+		Expr::MethodCall { receiver, args, .. } => {
+			find(receiver).or_else(|| args.iter().find_map(find))
+		}
+		Expr::Return(value) => value.as_deref().and_then(find),
+		Expr::If { cond, then, els } => find(cond)
+			.or_else(|| find(then))
+			.or_else(|| els.as_deref().and_then(find)),
+		Expr::InitializeStruct(_, fields, base) => fields
+			.values()
+			.find_map(&mut find)
+			.or_else(|| base.as_deref().and_then(find)),
+		Expr::Guard { dependency, body } => find(dependency).or_else(|| find(body)),
+		Expr::Name(_) | Expr::Is { .. } | Expr::Literal(_) | Expr::TypeLiteral(_) => None,
+		Expr::Local(never) => match *never {},
+	}
 }
 
 pub fn to_ir<'typ>(
@@ -32,7 +138,7 @@ pub fn to_ir<'typ>(
 	store: Option<usize>,
 	tail: bool,
 	context: &mut IRContext<'_, 'typ>,
-) {
+) -> Result<(), InfoTypeError<'typ>> {
 	match expr.expr {
 		Expr::Literal(lit) => literal(lit, context.blocks, block, store),
 		Expr::Access(left, right) => access(left, right, block, store, context),
@@ -42,15 +148,23 @@ pub fn to_ir<'typ>(
 		Expr::Block(statements, returns) => {
 			compile_block(statements, returns, block, store, tail, context)
 		}
-		Expr::InitializeStruct(name, fields) => {
-			initialize_struct(name, fields, block, store, context)
+		Expr::InitializeStruct(name, fields, base) => {
+			initialize_struct(name, fields, base, expr.idx, block, store, context)
 		}
 		Expr::Return(value_expr) => returns(value_expr, block, context),
-		Expr::Call(callee, args) => call(callee, args, block, store, tail, context),
+		Expr::Call(callee, args) => call(callee, args, expr.idx, block, store, tail, context),
+		Expr::MethodCall {
+			receiver,
+			method,
+			args,
+		} => method_call(
+			receiver, method, args, expr.idx, block, store, tail, context,
+		),
 		Expr::Name(name) => variable(name, block, store, context),
 		Expr::If { cond, then, els } => conditional(cond, then, els, block, store, tail, context),
 		Expr::Guard { dependency, body } => guard(dependency, body, block, store, tail, context),
 		Expr::Index(left, right) => index(left, right, block, store, context),
 		Expr::Is { name, typ } => is(name, typ, expr.idx, block, store, context),
+		Expr::TypeLiteral(typ) => type_literal(typ, block, store, context),
 	}
 }
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::{
+	ir::{IRContext, Operation, Statement},
+	parser::typ::InfoTypeExpr,
+	typ::{InfoTypeError, TypeExpr, type_id},
+	value::{Value, primitive::TypeValue},
+};
+
+pub fn type_literal<'a>(
+	typ: InfoTypeExpr<'a>,
+	block: &mut usize,
+	store: Option<usize>,
+	context: &mut IRContext<'_, 'a>,
+) -> Result<(), InfoTypeError<'a>> {
+	if let Some(store) = store {
+		construct_type(typ, block, store, context)?;
+	}
+
+	Ok(())
+}
+
+/// Types that mention locals are built while evaluating, everything else is instantiated now
+fn construct_type<'a>(
+	typ: InfoTypeExpr<'a>,
+	block: &mut usize,
+	store: usize,
+	context: &mut IRContext<'_, 'a>,
+) -> Result<(), InfoTypeError<'a>> {
+	let operation = match typ.expr {
+		TypeExpr::Name(name, params) if params.is_empty() && context.locals.contains_key(&name) => {
+			Operation::LoadLocal {
+				src: context.locals[&name],
+			}
+		}
+		TypeExpr::Struct(fields) => {
+			let mut field_vars = HashMap::new();
+			for (name, field) in fields {
+				let var = context.var();
+				construct_type(field, block, var, context)?;
+				field_vars.insert(name, var);
+			}
+			Operation::StructType(field_vars)
+		}
+		TypeExpr::Union(a, b) => {
+			let a_var = context.var();
+			construct_type(*a, block, a_var, context)?;
+			let b_var = context.var();
+			construct_type(*b, block, b_var, context)?;
+			Operation::UnionType(a_var, b_var)
+		}
+		TypeExpr::Tuple(elems) => {
+			let mut elem_vars = Vec::new();
+			for elem in elems {
+				let var = context.var();
+				construct_type(elem, block, var, context)?;
+				elem_vars.push(var);
+			}
+			Operation::TupleType(elem_vars)
+		}
+		expr => {
+			let typ = context
+				.ins
+				.instantiate(&InfoTypeExpr { expr, idx: typ.idx }, context.generics)?;
+			Operation::LoadLiteral(Value::new(TypeValue(typ), type_id::Type))
+		}
+	};
+
+	context.blocks[*block].statements.push(Statement {
+		store: Some(store),
+		operation,
+	});
+
+	Ok(())
+}
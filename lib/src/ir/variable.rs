@@ -1,14 +1,15 @@
 use crate::ir::IRContext;
 use crate::ir::{Operation, Statement};
 use crate::parser::typ::InfoTypeExpr;
-use crate::typ::TypeExpr;
+use crate::typ::{ConcreteType, InfoTypeError, Type, TypeExpr, type_id};
+use crate::value::{Value, primitive::TypeValue};
 
 pub fn variable<'a>(
 	name: InfoTypeExpr<'a>,
 	block: &mut usize,
 	store: Option<usize>,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
 	if let Some(store) = store {
 		match name.expr {
 			TypeExpr::Name(name, generics)
@@ -24,16 +25,21 @@ pub fn variable<'a>(
 				}
 			}
 			_ => {
+				let typ = context.ins.instantiate(&name, context.generics)?;
+				let operation = match context.ins.get_type(typ) {
+					Some(Type::Concrete(ConcreteType::Function(..) | ConcreteType::Module(_))) => {
+						Operation::LoadFunction(typ)
+					}
+					// Any other name refers to a type, used as a value
+					_ => Operation::LoadLiteral(Value::new(TypeValue(typ), type_id::Type)),
+				};
 				context.blocks[*block].statements.push(Statement {
 					store: Some(store),
-					operation: Operation::LoadFunction(
-						context
-							.ins
-							.instantiate(&name, context.generics)
-							.expect("pass errors in types to IRError"),
-					),
+					operation,
 				});
 			}
 		}
 	}
+
+	Ok(())
 }
@@ -3,6 +3,7 @@ use crate::ir::Operation;
 use crate::ir::Statement;
 use crate::ir::to_ir;
 use crate::parser::expression::InfoExpr;
+use crate::typ::InfoTypeError;
 
 pub fn variable_declaration<'a>(
 	name: String,
@@ -10,9 +11,13 @@ pub fn variable_declaration<'a>(
 	block: &mut usize,
 	store: Option<usize>,
 	context: &mut IRContext<'_, 'a>,
-) {
+) -> Result<(), InfoTypeError<'a>> {
+	context
+		.pending_locals
+		.push((name.clone(), (*value_expr).clone()));
+
 	let new_var = context.var();
-	to_ir(block, *value_expr, Some(new_var), false, context);
+	to_ir(block, *value_expr, Some(new_var), false, context)?;
 	context.locals.insert(name, new_var);
 	if let Some(store) = store {
 		context.blocks[*block].statements.push(Statement {
@@ -20,4 +25,6 @@ pub fn variable_declaration<'a>(
 			operation: Operation::LoadLocal { src: new_var },
 		});
 	}
+
+	Ok(())
 }
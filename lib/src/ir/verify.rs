@@ -0,0 +1,418 @@
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::Display,
+};
+
+use crate::{
+	ir::{Block, Callable, Function, Operation, Partial, Terminal, print_blocks, print_program},
+	typ::{ConcreteType, Type},
+	value::{Value, builtin::Builtin, list::List, primitive::TypeValue, structure::Struct},
+	vm::RunResult,
+};
+
+/// Why blocks of IR can't be run, and which block it's in. Problems inside a partial
+/// or function in a block are reported in that block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrError {
+	pub block: usize,
+	pub kind: IrErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrErrorKind {
+	UnknownType(usize),
+	UnknownBlock(usize),
+	/// A variable that isn't defined on every path to where it's used
+	UndefinedVariable(usize),
+	/// A phi without a variable for a block that jumps to it
+	MissingPhiSource(usize),
+	/// A guard phi whose block isn't the body of a guard before it, or once the guard
+	/// is evaluated, the only block jumping to the phi
+	NotAGuardBody(usize),
+}
+
+impl Display for IrErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			IrErrorKind::UnknownType(typ) => write!(f, "type {typ} isn't in the type table"),
+			IrErrorKind::UnknownBlock(block) => write!(f, "block {block} doesn't exist"),
+			IrErrorKind::UndefinedVariable(var) => {
+				write!(f, "variable {var} isn't defined on every path to its use")
+			}
+			IrErrorKind::MissingPhiSource(block) => {
+				write!(
+					f,
+					"phi has no variable for block {block}, which jumps to it"
+				)
+			}
+			IrErrorKind::NotAGuardBody(block) => write!(f, "block {block} isn't a guard's body"),
+		}
+	}
+}
+
+impl Display for IrError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} in block {}", self.kind, self.block)
+	}
+}
+
+/// Checks that the blocks of a function taking `params` arguments only jump to blocks
+/// that exist and only use variables once they're defined, so evaluating them can't
+/// panic. Blocks that can't be reached from `start` aren't checked.
+pub fn verify(
+	blocks: &[Block],
+	start: usize,
+	params: usize,
+	types: &[Type],
+) -> Result<(), IrError> {
+	let mut verifier = Verifier {
+		types,
+		block: start,
+		nested: false,
+	};
+	verifier
+		.blocks(blocks, start, (0..params).collect())
+		.map_err(|kind| verifier.error(kind))
+}
+
+/// Checks the result of evaluating a function taking `params` arguments
+pub fn verify_result(result: &RunResult, params: usize, types: &[Type]) -> Result<(), IrError> {
+	let mut verifier = Verifier {
+		types,
+		block: 0,
+		// Functions in a concrete result aren't blocks of the result
+		nested: !matches!(result, RunResult::Partial(_)),
+	};
+	verifier
+		.result(result, &(0..params).collect())
+		.map_err(|kind| verifier.error(kind))
+}
+
+/// Verifies blocks in debug builds, panicking with them printed if they're invalid so
+/// bugs in lowering and passes show up where they're introduced
+pub fn debug_verify_blocks(after: &str, blocks: &[Block], params: usize, types: &[Type]) {
+	if cfg!(debug_assertions)
+		&& let Err(error) = verify(blocks, 0, params, types)
+	{
+		panic!(
+			"invalid IR after {after}: {error}\n{}",
+			print_blocks(blocks)
+		);
+	}
+}
+
+/// Verifies the result of a pass in debug builds, like [`debug_verify_blocks`]
+pub fn debug_verify(after: &str, result: &RunResult, params: usize, types: &[Type]) {
+	if cfg!(debug_assertions)
+		&& let Err(error) = verify_result(result, params, types)
+	{
+		panic!(
+			"invalid IR after {after}: {error}\n{}",
+			print_program(result, &[])
+		);
+	}
+}
+
+struct Verifier<'t> {
+	types: &'t [Type],
+	/// The block being checked in the outermost blocks, which errors are reported in
+	block: usize,
+	/// Whether blocks being checked are inside another block, so don't move `block`
+	nested: bool,
+}
+
+/// The blocks that jump to each block, and the variables defined on every path to
+/// the start and end of each
+struct Flow {
+	predecessors: HashMap<usize, Vec<usize>>,
+	defined_before: HashMap<usize, HashSet<usize>>,
+	defined_after: HashMap<usize, HashSet<usize>>,
+	/// Bodies of the guards in the function, which guard phis take their variable from
+	guard_bodies: HashSet<usize>,
+}
+
+impl Verifier<'_> {
+	fn error(&self, kind: IrErrorKind) -> IrError {
+		IrError {
+			block: self.block,
+			kind,
+		}
+	}
+
+	fn type_id(&self, id: usize) -> Result<(), IrErrorKind> {
+		match id < self.types.len() {
+			true => Ok(()),
+			false => Err(IrErrorKind::UnknownType(id)),
+		}
+	}
+
+	fn builtin(&self, builtin: &Builtin) -> Result<(), IrErrorKind> {
+		match builtin {
+			Builtin::ListNew(typ) | Builtin::Args(typ) => self.type_id(*typ),
+			_ => Ok(()),
+		}
+	}
+
+	/// How many arguments a function value of type `typ` takes, if it's known
+	fn params(&self, typ: usize) -> usize {
+		match self.types.get(typ) {
+			Some(Type::Concrete(ConcreteType::Function(args, _, _))) => args.len(),
+			_ => 0,
+		}
+	}
+
+	fn value(&mut self, value: &Value) -> Result<(), IrErrorKind> {
+		self.type_id(value.typ)?;
+		let data = value.data.as_any();
+		if let Some(data) = data.downcast_ref::<Struct>() {
+			data.fields
+				.values()
+				.flatten()
+				.try_for_each(|field| self.value(field))
+		} else if let Some(data) = data.downcast_ref::<List>() {
+			data.0.iter().try_for_each(|elem| self.value(elem))
+		} else if let Some(data) = data.downcast_ref::<TypeValue>() {
+			self.type_id(data.0)
+		} else if let Some(data) = data.downcast_ref::<Builtin>() {
+			self.builtin(data)
+		} else if let Some(data) = data.downcast_ref::<Function>() {
+			self.generics(&data.generics)?;
+			let params = self.params(value.typ);
+			self.blocks(&data.ir, 0, (0..params).collect())
+		} else if let Some(data) = data.downcast_ref::<Partial>() {
+			self.partial(data, (0..self.params(value.typ)).collect())
+		} else {
+			Ok(())
+		}
+	}
+
+	fn generics(&self, generics: &[usize]) -> Result<(), IrErrorKind> {
+		generics.iter().try_for_each(|typ| self.type_id(*typ))
+	}
+
+	fn partial(&mut self, partial: &Partial, entry: HashSet<usize>) -> Result<(), IrErrorKind> {
+		self.generics(&partial.generics)?;
+		self.blocks(&partial.blocks, partial.start_block, entry)
+	}
+
+	fn result(&mut self, result: &RunResult, entry: &HashSet<usize>) -> Result<(), IrErrorKind> {
+		match result {
+			RunResult::Concrete(value) => self.value(value),
+			RunResult::Partial(partial) => self.partial(partial, entry.clone()),
+			RunResult::Residualise | RunResult::Error(_) => Ok(()),
+		}
+	}
+
+	/// Checks blocks run from `start` with the variables in `entry` defined, which are
+	/// a function's arguments, or everything defined before a branch for its partials
+	fn blocks(
+		&mut self,
+		blocks: &[Block],
+		start: usize,
+		entry: HashSet<usize>,
+	) -> Result<(), IrErrorKind> {
+		if blocks.get(start).is_none() {
+			return Err(IrErrorKind::UnknownBlock(start));
+		}
+
+		let flow = self.flow(blocks, start, entry)?;
+		let mut reached = flow.defined_after.keys().copied().collect::<Vec<_>>();
+		reached.sort();
+
+		let was_nested = self.nested;
+		for idx in reached {
+			if !was_nested {
+				self.block = idx;
+			}
+			self.nested = true;
+			let result = self.block(idx, &blocks[idx], &flow);
+			self.nested = was_nested;
+			result?;
+		}
+		Ok(())
+	}
+
+	/// Works out which blocks can be reached from `start`, and which variables are
+	/// defined on every path to the end of each
+	fn flow(
+		&self,
+		blocks: &[Block],
+		start: usize,
+		entry: HashSet<usize>,
+	) -> Result<Flow, IrErrorKind> {
+		let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+		let mut order = vec![start];
+		let mut pending = vec![start];
+		let mut seen = HashSet::from([start]);
+		while let Some(idx) = pending.pop() {
+			for next in successors(&blocks[idx].terminal) {
+				if blocks.get(next).is_none() {
+					return Err(IrErrorKind::UnknownBlock(next));
+				}
+				predecessors.entry(next).or_default().push(idx);
+				if seen.insert(next) {
+					order.push(next);
+					pending.push(next);
+				}
+			}
+		}
+
+		// Blocks only jump forwards, so this settles after a pass or two
+		let mut defined_before: HashMap<usize, HashSet<usize>> = HashMap::new();
+		let mut defined_after: HashMap<usize, HashSet<usize>> = HashMap::new();
+		let mut changed = true;
+		while changed {
+			changed = false;
+			for &idx in &order {
+				let sources = predecessors
+					.get(&idx)
+					.into_iter()
+					.flatten()
+					.filter_map(|pred| defined_after.get(pred));
+				let mut defined = match idx == start {
+					true => Some(entry.clone()),
+					false => None,
+				};
+				for source in sources {
+					defined = Some(match defined {
+						Some(defined) => defined.intersection(source).copied().collect(),
+						None => source.clone(),
+					});
+				}
+				let Some(before) = defined else {
+					continue;
+				};
+				let mut after = before.clone();
+				after.extend(blocks[idx].statements.iter().filter_map(|s| s.store));
+				if defined_after.get(&idx) != Some(&after) {
+					defined_before.insert(idx, before);
+					defined_after.insert(idx, after);
+					changed = true;
+				}
+			}
+		}
+
+		let guard_bodies = blocks
+			.iter()
+			.filter_map(|block| match block.terminal {
+				Terminal::Guard { body, .. } => Some(body),
+				_ => None,
+			})
+			.collect();
+		Ok(Flow {
+			predecessors,
+			defined_before,
+			defined_after,
+			guard_bodies,
+		})
+	}
+
+	fn block(&mut self, idx: usize, block: &Block, flow: &Flow) -> Result<(), IrErrorKind> {
+		let predecessors = flow.predecessors.get(&idx).map_or(&[][..], Vec::as_slice);
+		let mut defined = flow.defined_before[&idx].clone();
+		let var = |defined: &HashSet<usize>, var: usize| match defined.contains(&var) {
+			true => Ok(()),
+			false => Err(IrErrorKind::UndefinedVariable(var)),
+		};
+
+		for statement in &block.statements {
+			match &statement.operation {
+				Operation::Call { function, args, .. } => self.call(function, args, &defined)?,
+				Operation::LoadLiteral(value) => self.value(value)?,
+				Operation::LoadLocal { src } => var(&defined, *src)?,
+				Operation::Phi { block_to_var } => {
+					for pred in predecessors {
+						let Some(source) = block_to_var.get(pred) else {
+							return Err(IrErrorKind::MissingPhiSource(*pred));
+						};
+						var(&flow.defined_after[pred], *source)?;
+					}
+				}
+				Operation::GuardPhi { block, var: source } => {
+					// Evaluating a guard turns it into a jump through its body, so then the
+					// body is the only way into the phi's block
+					let resolved = predecessors == [*block];
+					if !flow.guard_bodies.contains(block) && !resolved {
+						return Err(IrErrorKind::NotAGuardBody(*block));
+					}
+					// The variable is only read when coming from the body
+					if let Some(body) = flow.defined_after.get(block) {
+						var(body, *source)?;
+					}
+				}
+				Operation::Index(value, index) => {
+					var(&defined, *value)?;
+					var(&defined, *index)?;
+				}
+				Operation::Access(value, _) => var(&defined, *value)?,
+				Operation::InitializeStruct(typ, fields) => {
+					self.type_id(*typ)?;
+					fields
+						.values()
+						.try_for_each(|field| var(&defined, *field))?;
+				}
+				Operation::LoadFunction(typ) => self.type_id(*typ)?,
+				Operation::Is { value, typ } => {
+					var(&defined, *value)?;
+					self.type_id(*typ)?;
+				}
+				Operation::StructType(fields) => fields
+					.values()
+					.try_for_each(|field| var(&defined, *field))?,
+				Operation::UnionType(a, b) => {
+					var(&defined, *a)?;
+					var(&defined, *b)?;
+				}
+				Operation::TupleType(elems) => {
+					elems.iter().try_for_each(|elem| var(&defined, *elem))?
+				}
+			}
+			if let Some(store) = statement.store {
+				defined.insert(store);
+			}
+		}
+
+		match &block.terminal {
+			Terminal::Return(ret) => var(&defined, *ret),
+			Terminal::Jump(_) => Ok(()),
+			Terminal::CondJump { cond, .. } => var(&defined, *cond),
+			Terminal::Branch { cond, then, els } => {
+				var(&defined, *cond)?;
+				self.result(then, &defined)?;
+				self.result(els, &defined)
+			}
+			Terminal::Guard { dependency, .. } => var(&defined, *dependency),
+			Terminal::TailCall { function, args, .. } => self.call(function, args, &defined),
+		}
+	}
+
+	fn call(
+		&mut self,
+		function: &Callable,
+		args: &[usize],
+		defined: &HashSet<usize>,
+	) -> Result<(), IrErrorKind> {
+		for arg in args {
+			if !defined.contains(arg) {
+				return Err(IrErrorKind::UndefinedVariable(*arg));
+			}
+		}
+		match function {
+			Callable::Var(var) if defined.contains(var) => Ok(()),
+			Callable::Var(var) => Err(IrErrorKind::UndefinedVariable(*var)),
+			Callable::Partial(partial) => self.partial(partial, (0..args.len()).collect()),
+		}
+	}
+}
+
+/// The blocks a terminal can go to next
+fn successors(terminal: &Terminal) -> Vec<usize> {
+	match terminal {
+		Terminal::Jump(block) => vec![*block],
+		Terminal::CondJump { then, els, .. } => vec![*then, *els],
+		Terminal::Guard {
+			body, continuation, ..
+		} => vec![*body, *continuation],
+		Terminal::Return(_) | Terminal::Branch { .. } | Terminal::TailCall { .. } => Vec::new(),
+	}
+}
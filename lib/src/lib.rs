@@ -2,6 +2,9 @@ pub mod error;
 pub mod ir;
 pub mod parser;
 pub mod passes;
+pub mod project;
+pub mod pvc;
+pub mod stdlib;
 pub mod tokeniser;
 pub mod typ;
 pub mod value;
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::convert::Infallible;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
 use crate::error::Span;
 use crate::parser::typ::{InfoTypeExpr, parse_type};
@@ -8,6 +8,7 @@ use crate::parser::utility::read_punctuated;
 use crate::tokeniser::Literal;
 use crate::tokeniser::{InfoToken, Keyword, Token};
 use crate::typ::TypeExpr;
+use crate::value::builtin::Builtin;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum InfixOp {
@@ -30,6 +31,11 @@ pub enum Expr<Expression: Clone + Debug, Type: Clone + Debug, LocalType: Clone +
 	Name(Type),
 	Literal(Literal),
 	Call(Box<Expression>, Vec<Expression>),
+	MethodCall {
+		receiver: Box<Expression>,
+		method: String,
+		args: Vec<Expression>,
+	},
 	Return(Option<Box<Expression>>),
 	Block(Vec<Expression>, bool),
 	Let(String, Box<Expression>),
@@ -38,7 +44,8 @@ pub enum Expr<Expression: Clone + Debug, Type: Clone + Debug, LocalType: Clone +
 		then: Box<Expression>,
 		els: Option<Box<Expression>>,
 	},
-	InitializeStruct(Type, HashMap<String, Expression>),
+	/// Type, fields, and a struct to take the remaining fields from (`..base`)
+	InitializeStruct(Type, HashMap<String, Expression>, Option<Box<Expression>>),
 	Access(Box<Expression>, String),
 	Guard {
 		dependency: Box<Expression>,
@@ -48,6 +55,8 @@ pub enum Expr<Expression: Clone + Debug, Type: Clone + Debug, LocalType: Clone +
 		name: String,
 		typ: Type,
 	},
+	/// A type used as a value, like `struct { a: T }` where `T` is a local
+	TypeLiteral(Type),
 	Local(LocalType),
 }
 
@@ -74,6 +83,40 @@ pub enum ParseError<'a> {
 	DuplicateName,
 	TypeUndefined(Vec<InfoToken<'a>>),
 	UnclosedAngleBrackets,
+	/// A `builtin` declaration names something the VM doesn't implement
+	UnknownBuiltin(String),
+	/// A `builtin` declaration's types differ from what the VM implements
+	BuiltinSignature(Builtin),
+	/// Only `self` in an `impl` or capability can leave out its type
+	UntypedArgument,
+}
+
+impl Display for ParseError<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ParseError::ExpectedName => write!(f, "expected a name"),
+			ParseError::ExpectedExpression(_) => write!(f, "expected an expression"),
+			ParseError::ExpectedString(_) => write!(f, "expected a string"),
+			ParseError::ExpectedTopLevel => write!(f, "expected a declaration"),
+			ParseError::ExpectedFunctionSignature(_) => write!(f, "expected a function signature"),
+			ParseError::ExpectedSemicolon(_) => write!(f, "expected `;`"),
+			ParseError::ExpectedAssign => write!(f, "expected `=`"),
+			ParseError::DuplicateName => write!(f, "this name is already declared"),
+			ParseError::TypeUndefined(_) => write!(f, "`impl` of a type that isn't declared here"),
+			ParseError::UnclosedAngleBrackets => write!(f, "unclosed `<`"),
+			ParseError::UnknownBuiltin(name) => write!(f, "no builtin is called `{name}`"),
+			ParseError::BuiltinSignature(builtin) => write!(
+				f,
+				"`builtin \"{}\"` needs declaring like `{}`",
+				builtin.name(),
+				builtin.signature().trim_end_matches(';')
+			),
+			ParseError::UntypedArgument => write!(
+				f,
+				"arguments need a type like `x: usize`, except `self` in an `impl` or capability"
+			),
+		}
+	}
 }
 
 pub fn parse_expression<'a>(
@@ -104,6 +147,10 @@ pub fn parse_expression<'a>(
 		return Ok(expr);
 	}
 
+	if let Some(expr) = try_parse_type_literal(tokens, generics)? {
+		return Ok(expr);
+	}
+
 	if let Some(expr) = try_parse_infix_op(tokens, generics, InfixOp::Plus)? {
 		return Ok(expr);
 	}
@@ -179,6 +226,34 @@ fn try_parse_infix_op<'a>(
 	}))
 }
 
+fn try_parse_type_literal<'a>(
+	tokens: &[InfoToken<'a>],
+	generics: &[String],
+) -> Result<Option<InfoExpr<'a>>, InfoParseError<'a>> {
+	let is_type = match tokens {
+		[
+			InfoToken {
+				token: Token::Keyword(Keyword::Struct),
+				span: _,
+			},
+			InfoToken {
+				token: Token::Braces(_),
+				span: _,
+			},
+		] => true,
+		_ => tokens.iter().any(|token| token.token == Token::Union),
+	};
+
+	if !is_type {
+		return Ok(None);
+	}
+
+	Ok(Some(InfoExpr {
+		idx: tokens[0].span.clone(),
+		expr: Expr::TypeLiteral(parse_type(tokens, generics)?),
+	}))
+}
+
 fn try_parse_let<'a>(
 	tokens: &[InfoToken<'a>],
 	generics: &[String],
@@ -324,8 +399,22 @@ fn try_parse_struct<'a>(
 	] = tokens
 	{
 		let mut fields = HashMap::new();
+		let mut base = None;
 		for name_colon_value in read_punctuated(contents, Token::Comma)? {
 			if let [
+				InfoToken {
+					token: Token::Dot,
+					span: _,
+				},
+				InfoToken {
+					token: Token::Dot,
+					span: _,
+				},
+				value @ ..,
+			] = &name_colon_value[..]
+			{
+				base = Some(Box::new(parse_expression(value, generics)?));
+			} else if let [
 				InfoToken {
 					token: Token::Name(name),
 					span: _name_idx,
@@ -345,7 +434,7 @@ fn try_parse_struct<'a>(
 		let type_expr = parse_type(type_tokens, &generics)?;
 
 		Ok(Some(InfoExpr {
-			expr: Expr::InitializeStruct(type_expr, fields),
+			expr: Expr::InitializeStruct(type_expr, fields, base),
 			idx: type_tokens[0].span.clone(),
 		}))
 	} else {
@@ -455,14 +544,35 @@ fn try_parse_call<'a>(
 		},
 	] = tokens
 	{
+		let mut args = Vec::new();
+		for tokens in read_punctuated(contents, Token::Comma)? {
+			args.push(parse_expression(&tokens, generics)?);
+		}
+
+		if let [
+			receiver @ ..,
+			InfoToken {
+				token: Token::Dot,
+				span: _,
+			},
+			InfoToken {
+				token: Token::Name(method),
+				span: _,
+			},
+		] = left && !receiver.is_empty()
+		{
+			return Ok(Some(InfoExpr {
+				expr: Expr::MethodCall {
+					receiver: Box::new(parse_expression(receiver, generics)?),
+					method: method.clone(),
+					args,
+				},
+				idx: idx.clone(),
+			}));
+		}
+
 		return Ok(Some(InfoExpr {
-			expr: Expr::Call(Box::new(parse_expression(left, generics)?), {
-				let mut out = Vec::new();
-				for tokens in read_punctuated(contents, Token::Comma)? {
-					out.push(parse_expression(&tokens, generics)?);
-				}
-				out
-			}),
+			expr: Expr::Call(Box::new(parse_expression(left, generics)?), args),
 			idx: idx.clone(),
 		}));
 	}
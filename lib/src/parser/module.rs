@@ -1,15 +1,15 @@
-use std::{collections::HashMap, usize};
+use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
 	error::{InfoError, Span},
 	parser::{
-		expression::{InfoExpr, InfoParseError, ParseError, parse_expression},
-		typ::{InfoTypeExpr, parse_type},
+		expression::{InfixOp, InfoExpr, InfoParseError, ParseError, parse_expression},
+		typ::{InfoTypeExpr, parse_struct_fields, parse_type},
 		utility::read_punctuated,
 	},
-	tokeniser::{InfoToken, Keyword, Literal, Token},
+	tokeniser::{InfoToken, Keyword, Literal, Token, tokenise},
 	typ::{GenericImplementation, Template, TypeExpr},
-	value::native::NativeFunction,
+	value::{builtin::Builtin, native::NativeFunction},
 };
 
 pub fn add_prefix(prefix: &[String], name: String) -> Vec<String> {
@@ -18,6 +18,9 @@ pub fn add_prefix(prefix: &[String], name: String) -> Vec<String> {
 	v
 }
 
+/// Modules imported with `use path::*`, by the path of the module importing them
+pub type Globs<'a> = HashMap<Vec<String>, Vec<InfoTypeExpr<'a>>>;
+
 #[derive(Debug)]
 pub enum Symbol<'a> {
 	Fn(Signature<'a>, InfoExpr<'a>),
@@ -28,82 +31,199 @@ pub enum Symbol<'a> {
 
 #[derive(Clone, Debug)]
 pub struct Signature<'a> {
-	name: String,
-	name_idx: Span<'a>,
-	generics: Vec<String>,
-	args: Vec<String>,
-	arg_types: Vec<InfoTypeExpr<'a>>,
-	return_type: InfoTypeExpr<'a>,
+	pub name: String,
+	pub name_idx: Span<'a>,
+	pub generics: Vec<String>,
+	pub bounds: Bounds<'a>,
+	pub args: Vec<String>,
+	pub arg_types: Vec<InfoTypeExpr<'a>>,
+	pub return_type: InfoTypeExpr<'a>,
 }
 
+/// Adds the declarations in `tokens` to `module`, which lives at `path`
 pub fn declaration_pass<'a>(
 	tokens: &[InfoToken<'a>],
 	module: &mut HashMap<String, Template<'a>>,
+	globs: &mut Globs<'a>,
+	path: &[String],
 ) -> Result<(), InfoError<'a>> {
 	let mut i = 0;
 
 	while i < tokens.len() {
+		// Declarations are private to their module unless marked `pub`
+		let public = tokens[i].token == Token::Keyword(Keyword::Pub);
+		if public {
+			i += 1;
+			if i == tokens.len() {
+				return Err(InfoParseError {
+					span: tokens[i - 1].span.clone(),
+					error: ParseError::ExpectedTopLevel,
+				}
+				.into());
+			}
+		}
+
 		match tokens[i].token.clone() {
 			Token::Keyword(Keyword::Use) => {
 				i += 1;
 				let start = i;
-				while tokens[i].token != Token::Semicolon {
+				while let Some(token) = tokens.get(i)
+					&& token.token != Token::Semicolon
+				{
 					i += 1;
 				}
-				let end = i;
-				let items = read_punctuated(&tokens[start..end], Token::DoubleColon)?;
+				if i == tokens.len() {
+					return Err(InfoParseError {
+						span: tokens[start - 1].span.clone(),
+						error: ParseError::ExpectedSemicolon(tokens[i - 1].clone()),
+					}
+					.into());
+				}
+				if start == i {
+					return Err(InfoParseError {
+						span: tokens[start - 1].span.clone(),
+						error: ParseError::ExpectedName,
+					}
+					.into());
+				}
+				use_tree(&[], &tokens[start..i], public, module, globs, path)?;
 				i += 1;
-
-				let mut path = Vec::new();
-
-				for item in items {
-					if let [
+			}
+			Token::Keyword(Keyword::Mod) => {
+				let idx = i;
+				i += 1;
+				let (name, body) = if let Some(
+					[
 						InfoToken {
 							token: Token::Name(name),
 							span: _,
 						},
-					] = &item[..]
-					{
-						path.push(name.clone());
-					} else {
-						return Err(InfoParseError {
-							error: ParseError::ExpectedName,
-							span: item[0].span.clone(),
-						}
-						.into());
+						InfoToken {
+							token: Token::Braces(body),
+							span: _,
+						},
+					],
+				) = tokens.get(i..i + 2)
+				{
+					(name.clone(), body)
+				} else {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedName,
 					}
-				}
+					.into());
+				};
+				i += 2;
+
+				let child_path = add_prefix(path, name.clone());
+				let mut child = HashMap::new();
+				declaration_pass(body, &mut child, globs, &child_path)?;
 
 				module.insert(
-					path.last().unwrap().clone(),
+					name,
 					Template {
-						expr: parse_type(&tokens[start..end], &vec![])?,
+						expr: InfoTypeExpr {
+							expr: TypeExpr::Module(child, child_path),
+							idx: tokens[idx].span.clone(),
+						},
 						parameters: 0,
+						members: HashMap::new(),
+						bounds: Vec::new(),
+						defaults: HashMap::new(),
+						public,
 					},
 				);
 			}
 			Token::Keyword(Keyword::Fn) => {
 				i += 1;
-				let signature = expect_function_signature(&tokens, &mut i)?;
+				let signature = expect_function_signature(tokens, &mut i, None)?;
 
-				let body = expect_block_or_expr(&tokens, &mut i, &signature.generics)?;
+				let body = expect_block_or_expr(tokens, &mut i, &signature.generics)?;
 
 				module.insert(
-					signature.name,
-					Template {
-						expr: InfoTypeExpr {
-							expr: TypeExpr::Function(
-								signature.arg_types,
-								Box::new(signature.return_type.clone()),
-								Some(GenericImplementation::Normal(Box::new(body.clone()))),
-								signature.args,
-							),
-							idx: signature.name_idx,
-						},
-						parameters: signature.generics.len(),
-					},
+					signature.name.clone(),
+					function_template(signature, body, public),
 				);
 			}
+			Token::Keyword(Keyword::Impl) if !public => {
+				i += 1;
+				let start = i;
+				while let Some(token) = tokens.get(i)
+					&& !matches!(token.token, Token::Braces(_))
+				{
+					i += 1;
+				}
+				let self_type = parse_type(&tokens[start..i], &[])?;
+				let name = if let TypeExpr::Name(name, params) = &self_type.expr
+					&& params.is_empty()
+				{
+					name.clone()
+				} else {
+					return Err(InfoParseError {
+						span: self_type.idx,
+						error: ParseError::ExpectedName,
+					}
+					.into());
+				};
+				let body = if let Some(InfoToken {
+					token: Token::Braces(body),
+					span: _,
+				}) = tokens.get(i)
+				{
+					body
+				} else {
+					return Err(InfoParseError {
+						span: tokens[start].span.clone(),
+						error: ParseError::ExpectedExpression(tokens[start..].to_vec()),
+					}
+					.into());
+				};
+				i += 1;
+
+				let mut members = HashMap::new();
+				let mut j = 0;
+				while j < body.len() {
+					let public = body[j].token == Token::Keyword(Keyword::Pub);
+					if public {
+						j += 1;
+					}
+					if body.get(j).map(|t| &t.token) != Some(&Token::Keyword(Keyword::Fn)) {
+						return Err(InfoParseError {
+							span: body[j.min(body.len() - 1)].span.clone(),
+							error: ParseError::ExpectedTopLevel,
+						}
+						.into());
+					}
+					j += 1;
+					let name_idx = body[j].span.clone();
+					let signature = expect_function_signature(body, &mut j, Some(&self_type))?;
+					let method_body = expect_block_or_expr(body, &mut j, &signature.generics)?;
+					if members
+						.insert(
+							signature.name.clone(),
+							function_template(signature, method_body, public),
+						)
+						.is_some()
+					{
+						return Err(InfoParseError {
+							span: name_idx,
+							error: ParseError::DuplicateName,
+						}
+						.into());
+					}
+				}
+
+				match module.get_mut(&name) {
+					Some(template) => template.members.extend(members),
+					None => {
+						return Err(InfoParseError {
+							span: self_type.idx,
+							error: ParseError::TypeUndefined(tokens[start..i - 1].to_vec()),
+						}
+						.into());
+					}
+				}
+			}
 			Token::Keyword(Keyword::Struct) => {
 				let idx = i;
 				i += 1;
@@ -116,45 +236,7 @@ pub fn declaration_pass<'a>(
 					})
 				}?;
 				i += 1;
-				let generics_tokens = if let Token::LessThan = &tokens[i].token {
-					i += 1;
-					let start = i;
-					loop {
-						if let Some(InfoToken {
-							token: Token::GreaterThan,
-							span: _,
-						}) = tokens.iter().nth(i)
-						{
-							break;
-						}
-						i += 1;
-					}
-					i += 1;
-					Some(&tokens[start..i - 1])
-				} else {
-					None
-				};
-				let generics = if let Some(generics_tokens) = generics_tokens {
-					let generics = read_punctuated(generics_tokens, Token::Comma)?;
-					generics
-						.iter()
-						.map(|param_tokens| {
-							if let [
-								InfoToken {
-									token: Token::Name(name),
-									span: _,
-								},
-							] = &param_tokens[..]
-							{
-								name.clone()
-							} else {
-								panic!("Non name tokens in generic {param_tokens:?}")
-							}
-						})
-						.collect()
-				} else {
-					Vec::new()
-				};
+				let (generics, bounds) = expect_generics(tokens, &mut i)?;
 				let block = if let Token::Braces(block) = &tokens[i].token {
 					Ok(block)
 				} else {
@@ -165,22 +247,24 @@ pub fn declaration_pass<'a>(
 				}?;
 
 				let mut fields = HashMap::new();
-
-				for field_colon_type in read_punctuated(block, Token::Comma)? {
-					if let [
-						InfoToken {
-							token: Token::Name(name),
-							span: _name_idx,
-						},
-						InfoToken {
-							token: Token::Colon,
-							span: _colon_idx,
-						},
-						typ @ ..,
-					] = field_colon_type.as_slice()
+				let mut defaults = HashMap::new();
+				for field_tokens in read_punctuated(block, Token::Comma)? {
+					// `name: Type = default`
+					let (field_tokens, default) = match field_tokens
+						.iter()
+						.position(|token| token.token == Token::Assignment)
 					{
-						fields.insert(name.clone(), parse_type(typ, &generics)?);
+						Some(idx) => (
+							&field_tokens[..idx],
+							Some(parse_expression(&field_tokens[idx + 1..], &generics)?),
+						),
+						None => (&field_tokens[..], None),
+					};
+					let field = parse_struct_fields(field_tokens, &generics)?;
+					if let (Some(default), Some(name)) = (default, field.keys().next()) {
+						defaults.insert(name.clone(), default);
 					}
+					fields.extend(field);
 				}
 				i += 1;
 
@@ -192,17 +276,174 @@ pub fn declaration_pass<'a>(
 							idx: tokens[idx].span.clone(),
 						},
 						parameters: generics.len(),
+						members: HashMap::new(),
+						bounds,
+						defaults,
+						public,
+					},
+				);
+			}
+			Token::Keyword(Keyword::Type) => {
+				let idx = i;
+				i += 1;
+				let name = if let Some(InfoToken {
+					token: Token::Name(name),
+					span: _,
+				}) = tokens.get(i)
+				{
+					name.clone()
+				} else {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedName,
+					}
+					.into());
+				};
+				i += 1;
+				let (generics, bounds) = expect_generics(tokens, &mut i)?;
+
+				if tokens.get(i).map(|t| &t.token) != Some(&Token::Assignment) {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedAssign,
+					}
+					.into());
+				}
+				i += 1;
+
+				let start = i;
+				while let Some(token) = tokens.get(i)
+					&& token.token != Token::Semicolon
+				{
+					i += 1;
+				}
+				if i == tokens.len() {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedSemicolon(tokens[i - 1].clone()),
+					}
+					.into());
+				}
+				if start == i {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedExpression(Vec::new()),
+					}
+					.into());
+				}
+				let expr = parse_type(&tokens[start..i], &generics)?;
+				i += 1;
+
+				module.insert(
+					name,
+					Template {
+						expr,
+						parameters: generics.len(),
+						members: HashMap::new(),
+						bounds,
+						defaults: HashMap::new(),
+						public,
 					},
 				);
 			}
-			Token::Keyword(Keyword::Dylib) => {
+			Token::Keyword(Keyword::Capability) => {
+				let idx = i;
 				i += 1;
-				let lib_name = if let InfoToken {
+				let name = if let Some(InfoToken {
+					token: Token::Name(name),
 					span: _,
+				}) = tokens.get(i)
+				{
+					name.clone()
+				} else {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedName,
+					}
+					.into());
+				};
+				i += 1;
+				let body = if let Some(InfoToken {
+					token: Token::Braces(body),
+					span: _,
+				}) = tokens.get(i)
+				{
+					body
+				} else {
+					return Err(InfoParseError {
+						span: tokens[idx].span.clone(),
+						error: ParseError::ExpectedExpression(tokens[i..].to_vec()),
+					}
+					.into());
+				};
+				i += 1;
+
+				// Required functions are written as `fn name(self, other: Self): Self;`
+				let self_type = InfoTypeExpr {
+					expr: TypeExpr::Name("Self".to_string(), Vec::new()),
+					idx: tokens[idx].span.clone(),
+				};
+				let mut methods = HashMap::new();
+				let mut j = 0;
+				while j < body.len() {
+					if body[j].token != Token::Keyword(Keyword::Fn) {
+						return Err(InfoParseError {
+							span: body[j].span.clone(),
+							error: ParseError::ExpectedTopLevel,
+						}
+						.into());
+					}
+					j += 1;
+					let signature = expect_function_signature(body, &mut j, Some(&self_type))?;
+					if body.get(j).map(|t| &t.token) != Some(&Token::Semicolon) {
+						return Err(InfoParseError {
+							span: signature.name_idx,
+							error: ParseError::ExpectedSemicolon(
+								body[j.min(body.len() - 1)].clone(),
+							),
+						}
+						.into());
+					}
+					j += 1;
+					methods.insert(
+						signature.name,
+						InfoTypeExpr {
+							expr: TypeExpr::Function(
+								signature.arg_types,
+								Box::new(signature.return_type),
+								None,
+								signature.args,
+							),
+							idx: signature.name_idx,
+						},
+					);
+				}
+
+				module.insert(
+					name.clone(),
+					Template {
+						expr: InfoTypeExpr {
+							expr: TypeExpr::Capability(name, methods),
+							idx: tokens[idx].span.clone(),
+						},
+						parameters: 0,
+						members: HashMap::new(),
+						bounds: Vec::new(),
+						defaults: HashMap::new(),
+						public,
+					},
+				);
+			}
+			// `dylib "lib" fn name(...);` is implemented by a native library, and
+			// `builtin "name" fn name(...);` by the VM
+			Token::Keyword(keyword @ (Keyword::Dylib | Keyword::Builtin)) => {
+				i += 1;
+				let (lib_name, name_idx) = if let InfoToken {
+					span,
 					token: Token::Literal(Literal::String(s)),
 				} = &tokens[i]
 				{
-					s.clone()
+					(s.clone(), span.clone())
 				} else {
 					return Err(InfoParseError {
 						span: tokens[i].span.clone(),
@@ -214,7 +455,7 @@ pub fn declaration_pass<'a>(
 				i += 1;
 				i += 1;
 
-				let signature = expect_function_signature(&tokens, &mut i)?;
+				let signature = expect_function_signature(tokens, &mut i, None)?;
 
 				if tokens[i].token != Token::Semicolon {
 					return Err(InfoParseError {
@@ -224,6 +465,22 @@ pub fn declaration_pass<'a>(
 					.into());
 				}
 				i += 1;
+				let implementation = if keyword == Keyword::Builtin {
+					let Some(builtin) = Builtin::named(&lib_name) else {
+						return Err(InfoParseError {
+							span: name_idx,
+							error: ParseError::UnknownBuiltin(lib_name),
+						}
+						.into());
+					};
+					check_builtin(builtin, &signature, &name_idx)?;
+					GenericImplementation::Builtin(builtin)
+				} else {
+					GenericImplementation::Native(NativeFunction::new(
+						lib_name,
+						signature.name.clone(),
+					))
+				};
 				module.insert(
 					signature.name.clone(),
 					Template {
@@ -231,15 +488,16 @@ pub fn declaration_pass<'a>(
 							expr: TypeExpr::Function(
 								signature.arg_types,
 								Box::new(signature.return_type),
-								Some(GenericImplementation::Native(NativeFunction {
-									lib_name,
-									func_name: signature.name,
-								})),
+								Some(implementation),
 								signature.args,
 							),
 							idx: signature.name_idx,
 						},
 						parameters: signature.generics.len(),
+						members: HashMap::new(),
+						bounds: signature.bounds,
+						defaults: HashMap::new(),
+						public,
 					},
 				);
 			}
@@ -256,6 +514,135 @@ pub fn declaration_pass<'a>(
 	Ok(())
 }
 
+/// Adds the names brought in by a `use` tree like `a::b`, `a::b as c`,
+/// `a::{b, c::d}` or `a::*` to `module`, with paths relative to `prefix`
+fn use_tree<'a>(
+	prefix: &[InfoToken<'a>],
+	tree: &[InfoToken<'a>],
+	public: bool,
+	module: &mut HashMap<String, Template<'a>>,
+	globs: &mut Globs<'a>,
+	module_path: &[String],
+) -> Result<(), InfoParseError<'a>> {
+	let first = &tree[0];
+	let join = |path: &[InfoToken<'a>]| {
+		let mut joined = prefix.to_vec();
+		if !prefix.is_empty() {
+			joined.push(InfoToken {
+				token: Token::DoubleColon,
+				span: first.span.clone(),
+			});
+		}
+		joined.extend_from_slice(path);
+		joined
+	};
+
+	let (path, name) = match tree {
+		[
+			path @ ..,
+			InfoToken {
+				token: Token::DoubleColon,
+				span: _,
+			},
+			InfoToken {
+				token: Token::Braces(items),
+				span: _,
+			},
+		] => {
+			let path = join(path);
+			for item in read_punctuated(items, Token::Comma)? {
+				use_tree(&path, &item, public, module, globs, module_path)?;
+			}
+			return Ok(());
+		}
+		[
+			path @ ..,
+			InfoToken {
+				token: Token::DoubleColon,
+				span: _,
+			},
+			InfoToken {
+				token: Token::Star,
+				span: _,
+			},
+		] => {
+			// Globs are searched when a name isn't found in the module
+			let path = join(path);
+			use_path(&path, &first.span)?;
+			globs
+				.entry(module_path.to_vec())
+				.or_default()
+				.push(parse_type(&path, &[])?);
+			return Ok(());
+		}
+		[
+			path @ ..,
+			InfoToken {
+				token: Token::Keyword(Keyword::As),
+				span: _,
+			},
+			InfoToken {
+				token: Token::Name(alias),
+				span: _,
+			},
+		] => {
+			let path = join(path);
+			use_path(&path, &first.span)?;
+			(path, alias.clone())
+		}
+		path => {
+			let path = join(path);
+			let name = use_path(&path, &first.span)?.pop().unwrap();
+			(path, name)
+		}
+	};
+
+	module.insert(
+		name,
+		Template {
+			expr: parse_type(&path, &[])?,
+			parameters: 0,
+			members: HashMap::new(),
+			bounds: Vec::new(),
+			defaults: HashMap::new(),
+			public,
+		},
+	);
+
+	Ok(())
+}
+
+/// Checks that `path` looks like `a::b::c` and returns its names
+fn use_path<'a>(
+	path: &[InfoToken<'a>],
+	span: &Span<'a>,
+) -> Result<Vec<String>, InfoParseError<'a>> {
+	let mut names = Vec::new();
+	for item in read_punctuated(path, Token::DoubleColon)? {
+		if let [
+			InfoToken {
+				token: Token::Name(name),
+				span: _,
+			},
+		] = &item[..]
+		{
+			names.push(name.clone());
+		} else {
+			return Err(InfoParseError {
+				error: ParseError::ExpectedName,
+				span: item[0].span.clone(),
+			});
+		}
+	}
+	if names.is_empty() {
+		return Err(InfoParseError {
+			error: ParseError::ExpectedName,
+			span: span.clone(),
+		});
+	}
+	Ok(names)
+}
+
 // pub fn implementation_pass<'a>(
 // 	mut instantiator: &mut Instantiator<'a>,
 // ) -> Result<(), InfoError<'a>> {
@@ -345,54 +732,208 @@ pub fn declaration_pass<'a>(
 // 	Ok(())
 // }
 
+/// Capabilities required of generic parameters, by parameter index
+type Bounds<'a> = Vec<(usize, InfoTypeExpr<'a>)>;
+
+/// Reads an optional `<A, B: Capability + Other>` list of generic parameter
+/// names, along with the capabilities each parameter is bounded by
+fn expect_generics<'a>(
+	tokens: &[InfoToken<'a>],
+	i: &mut usize,
+) -> Result<(Vec<String>, Bounds<'a>), InfoParseError<'a>> {
+	let mut generics = Vec::new();
+	let mut bounds = Vec::new();
+
+	if let Some(InfoToken {
+		token: Token::LessThan,
+		span,
+	}) = tokens.get(*i)
+	{
+		*i += 1;
+		let start = *i;
+		loop {
+			match tokens.get(*i) {
+				Some(InfoToken {
+					token: Token::GreaterThan,
+					span: _,
+				}) => break,
+				Some(_) => *i += 1,
+				None => {
+					return Err(InfoParseError {
+						span: span.clone(),
+						error: ParseError::UnclosedAngleBrackets,
+					});
+				}
+			}
+		}
+		*i += 1;
+
+		for param_tokens in read_punctuated(&tokens[start..*i - 1], Token::Comma)? {
+			if let [
+				InfoToken {
+					token: Token::Name(name),
+					span: _,
+				},
+			] = &param_tokens[..]
+			{
+				generics.push(name.clone());
+			} else if let [
+				InfoToken {
+					token: Token::Name(name),
+					span: _,
+				},
+				InfoToken {
+					token: Token::Colon,
+					span: _,
+				},
+				capabilities @ ..,
+			] = &param_tokens[..]
+			{
+				for capability in read_punctuated(capabilities, Token::InfixOp(InfixOp::Plus))? {
+					bounds.push((generics.len(), parse_type(&capability, &[])?));
+				}
+				generics.push(name.clone());
+			} else {
+				return Err(InfoParseError {
+					span: param_tokens[0].span.clone(),
+					error: ParseError::ExpectedName,
+				});
+			}
+		}
+	}
+
+	Ok((generics, bounds))
+}
+
+fn function_template<'a>(
+	signature: Signature<'a>,
+	body: InfoExpr<'a>,
+	public: bool,
+) -> Template<'a> {
+	Template {
+		expr: InfoTypeExpr {
+			expr: TypeExpr::Function(
+				signature.arg_types,
+				Box::new(signature.return_type),
+				Some(GenericImplementation::Normal(Box::new(body))),
+				signature.args,
+			),
+			idx: signature.name_idx,
+		},
+		parameters: signature.generics.len(),
+		members: HashMap::new(),
+		bounds: signature.bounds,
+		defaults: HashMap::new(),
+		public,
+	}
+}
+
+/// Parses bodiless declarations like `fn name(arg: T): U;`, which native libraries
+/// describe their functions with
+pub fn parse_signatures<'a>(
+	tokens: &[InfoToken<'a>],
+) -> Result<Vec<Signature<'a>>, InfoParseError<'a>> {
+	let mut signatures = Vec::new();
+	let mut i = 0;
+	while i < tokens.len() {
+		if tokens[i].token != Token::Keyword(Keyword::Fn) {
+			return Err(InfoParseError {
+				span: tokens[i].span.clone(),
+				error: ParseError::ExpectedFunctionSignature(tokens[i].clone()),
+			});
+		}
+		// Signatures are only read up to their `;`, so one missing it can't run off the end
+		if !tokens[i..]
+			.iter()
+			.any(|token| token.token == Token::Semicolon)
+		{
+			return Err(InfoParseError {
+				span: tokens[i].span.clone(),
+				error: ParseError::ExpectedSemicolon(tokens[tokens.len() - 1].clone()),
+			});
+		}
+		i += 1;
+		let signature = expect_function_signature(tokens, &mut i, None)?;
+		if tokens.get(i).map(|t| &t.token) != Some(&Token::Semicolon) {
+			return Err(InfoParseError {
+				span: signature.name_idx,
+				error: ParseError::ExpectedSemicolon(tokens[i.min(tokens.len() - 1)].clone()),
+			});
+		}
+		i += 1;
+		signatures.push(signature);
+	}
+	Ok(signatures)
+}
+
+/// Checks that a `builtin` declaration has the types the VM implements it with
+fn check_builtin<'a>(
+	builtin: Builtin,
+	signature: &Signature<'a>,
+	span: &Span<'a>,
+) -> Result<(), InfoParseError<'a>> {
+	let tokens = tokenise(builtin.signature(), 0, Cow::Borrowed("<builtin>"))
+		.expect("builtin signatures should tokenise");
+	let Ok(mut expected) = parse_signatures(&tokens) else {
+		unreachable!("builtin signatures should parse")
+	};
+	let expected = expected.remove(0);
+
+	let types = |signature: &Signature<'a>| {
+		let mut types = signature.arg_types.clone();
+		types.push(signature.return_type.clone());
+		types
+	};
+	if expected.generics.len() == signature.generics.len()
+		&& expected.arg_types.len() == signature.arg_types.len()
+		&& types(&expected)
+			.iter()
+			.zip(&types(signature))
+			.all(|(expected, declared)| same_shape(&expected.expr, &declared.expr))
+	{
+		return Ok(());
+	}
+	Err(InfoParseError {
+		span: span.clone(),
+		error: ParseError::BuiltinSignature(builtin),
+	})
+}
+
+/// Whether two types are written alike, ignoring the paths to named types
+fn same_shape(a: &TypeExpr, b: &TypeExpr) -> bool {
+	let all = |a: &[InfoTypeExpr], b: &[InfoTypeExpr]| {
+		a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_shape(&a.expr, &b.expr))
+	};
+	match (a, b) {
+		(
+			TypeExpr::Name(a, a_params) | TypeExpr::Subtype(_, a, a_params),
+			TypeExpr::Name(b, b_params) | TypeExpr::Subtype(_, b, b_params),
+		) => {
+			a == b
+				&& a_params.len() == b_params.len()
+				&& a_params.iter().zip(b_params).all(|params| match params {
+					(Some(a), Some(b)) => same_shape(&a.expr, &b.expr),
+					_ => false,
+				})
+		}
+		(TypeExpr::Parameter(a), TypeExpr::Parameter(b)) => a == b,
+		(TypeExpr::Tuple(a), TypeExpr::Tuple(b)) => all(a, b),
+		(TypeExpr::List(a), TypeExpr::List(b)) => same_shape(&a.expr, &b.expr),
+		_ => false,
+	}
+}
+
 fn expect_function_signature<'a>(
 	tokens: &[InfoToken<'a>],
 	i: &mut usize,
+	self_type: Option<&InfoTypeExpr<'a>>,
 ) -> Result<Signature<'a>, InfoParseError<'a>> {
 	if let Token::Name(name) = &tokens[*i].token {
 		let name_idx = tokens[*i].span.clone();
 		*i += 1;
 
 		let mut args = Vec::new();
-		let generics_tokens = if let Token::LessThan = &tokens[*i].token {
-			*i += 1;
-			let start = *i;
-			loop {
-				if let Some(InfoToken {
-					token: Token::GreaterThan,
-					span: _,
-				}) = tokens.get(*i)
-				{
-					break;
-				}
-				*i += 1;
-			}
-			*i += 1;
-			Some(&tokens[start..*i - 1])
-		} else {
-			None
-		};
-		let generics = if let Some(generics_tokens) = generics_tokens {
-			let generics = read_punctuated(generics_tokens, Token::Comma)?;
-			generics
-				.iter()
-				.map(|param_tokens| {
-					if let [
-						InfoToken {
-							token: Token::Name(name),
-							span: _,
-						},
-					] = &param_tokens[..]
-					{
-						name.clone()
-					} else {
-						panic!("Non name tokens in generic {param_tokens:?}")
-					}
-				})
-				.collect()
-		} else {
-			Vec::new()
-		};
+		let (generics, bounds) = expect_generics(tokens, i)?;
 
 		if let Token::Parens(contents) = &tokens[*i].token {
 			for arg_colon_type in read_punctuated(contents, Token::Comma)? {
@@ -403,18 +944,42 @@ fn expect_function_signature<'a>(
 					},
 					InfoToken {
 						token: Token::Colon,
-						span: _colon_idx,
+						span: colon_idx,
 					},
 					typ @ ..,
 				] = &arg_colon_type[..]
 				{
+					if typ.is_empty() {
+						return Err(InfoParseError {
+							span: colon_idx.clone(),
+							error: ParseError::ExpectedExpression(Vec::new()),
+						});
+					}
 					let typ = parse_type(typ, &generics)?;
 					args.push((name.clone(), typ));
+				} else if let [
+					InfoToken {
+						token: Token::Name(name),
+						span: _,
+					},
+				] = &arg_colon_type[..]
+					&& name == "self"
+					&& let Some(self_type) = self_type
+				{
+					args.push((name.clone(), self_type.clone()));
+				} else {
+					return Err(InfoParseError {
+						span: arg_colon_type[0].span.clone(),
+						error: ParseError::UntypedArgument,
+					});
 				}
 			}
 			*i += 1;
 		} else {
-			panic!("Missing function parameters, got {:?}", tokens[*i]);
+			return Err(InfoParseError {
+				span: tokens[*i].span.clone(),
+				error: ParseError::ExpectedFunctionSignature(tokens[*i].clone()),
+			});
 		}
 		let returns = if let Token::Colon = &tokens[*i].token {
 			*i += 1;
@@ -429,6 +994,12 @@ fn expect_function_signature<'a>(
 				}
 				*i += 1;
 			}
+			if start == *i {
+				return Err(InfoParseError {
+					span: tokens[*i].span.clone(),
+					error: ParseError::ExpectedExpression(Vec::new()),
+				});
+			}
 
 			parse_type(&tokens[start..*i], &generics)?
 		} else {
@@ -441,6 +1012,7 @@ fn expect_function_signature<'a>(
 			name: name.clone(),
 			name_idx,
 			generics,
+			bounds,
 			args: args.iter().map(|arg| arg.0.clone()).collect(),
 			arg_types: args.iter().map(|arg| arg.1.clone()).collect(),
 			return_type: returns,
@@ -1,10 +1,12 @@
+use std::collections::HashMap;
+
 use crate::{
 	error::Span,
 	parser::{
 		expression::{InfoParseError, ParseError},
 		utility::read_punctuated,
 	},
-	tokeniser::{InfoToken, Token},
+	tokeniser::{InfoToken, Keyword, Token},
 	typ::TypeExpr,
 };
 
@@ -20,6 +22,12 @@ pub fn parse_type<'a>(
 ) -> Result<InfoTypeExpr<'a>, InfoParseError<'a>> {
 	let expr = if let Some(expr) = try_parse_union(tokens, generics)? {
 		expr
+	} else if let Some(expr) = try_parse_tuple(tokens, generics)? {
+		expr
+	} else if let Some(expr) = try_parse_struct(tokens, generics)? {
+		expr
+	} else if let Some(expr) = try_parse_call(tokens, generics)? {
+		expr
 	} else if let Some(expr) = try_parse_subtype(tokens, generics)? {
 		expr
 	} else if let Some(expr) = try_parse_generics(tokens, generics)? {
@@ -36,6 +44,108 @@ pub fn parse_type<'a>(
 	Ok(expr)
 }
 
+fn try_parse_tuple<'a>(
+	tokens: &[InfoToken<'a>],
+	generics: &[String],
+) -> Result<Option<InfoTypeExpr<'a>>, InfoParseError<'a>> {
+	if let [
+		InfoToken {
+			token: Token::Parens(contents),
+			span,
+		},
+	] = tokens
+	{
+		let mut elems = Vec::new();
+		for elem_tokens in read_punctuated(contents, Token::Comma)? {
+			elems.push(parse_type(&elem_tokens, generics)?);
+		}
+		return Ok(Some(InfoTypeExpr {
+			expr: TypeExpr::Tuple(elems),
+			idx: span.clone(),
+		}));
+	}
+	Ok(None)
+}
+
+fn try_parse_struct<'a>(
+	tokens: &[InfoToken<'a>],
+	generics: &[String],
+) -> Result<Option<InfoTypeExpr<'a>>, InfoParseError<'a>> {
+	if let [
+		InfoToken {
+			token: Token::Keyword(Keyword::Struct),
+			span,
+		},
+		InfoToken {
+			token: Token::Braces(contents),
+			span: _,
+		},
+	] = tokens
+	{
+		return Ok(Some(InfoTypeExpr {
+			expr: TypeExpr::Struct(parse_struct_fields(contents, generics)?),
+			idx: span.clone(),
+		}));
+	}
+	Ok(None)
+}
+
+/// Parses the inside of the braces in `struct { a: A, b: B }`
+pub fn parse_struct_fields<'a>(
+	contents: &[InfoToken<'a>],
+	generics: &[String],
+) -> Result<HashMap<String, InfoTypeExpr<'a>>, InfoParseError<'a>> {
+	let mut fields = HashMap::new();
+
+	for field_colon_type in read_punctuated(contents, Token::Comma)? {
+		if let [
+			InfoToken {
+				token: Token::Name(name),
+				span: _name_idx,
+			},
+			InfoToken {
+				token: Token::Colon,
+				span: _colon_idx,
+			},
+			typ @ ..,
+		] = field_colon_type.as_slice()
+		{
+			fields.insert(name.clone(), parse_type(typ, generics)?);
+		}
+	}
+
+	Ok(fields)
+}
+
+fn try_parse_call<'a>(
+	tokens: &[InfoToken<'a>],
+	generics: &[String],
+) -> Result<Option<InfoTypeExpr<'a>>, InfoParseError<'a>> {
+	if let [
+		callee @ ..,
+		InfoToken {
+			token: Token::Parens(contents),
+			span: _,
+		},
+	] = tokens
+	{
+		if callee.is_empty() {
+			return Ok(None);
+		}
+
+		let mut args = Vec::new();
+		for arg_tokens in read_punctuated(contents, Token::Comma)? {
+			args.push(parse_type(&arg_tokens, generics)?);
+		}
+
+		return Ok(Some(InfoTypeExpr {
+			expr: TypeExpr::Call(Box::new(parse_type(callee, generics)?), args),
+			idx: callee[0].span.clone(),
+		}));
+	}
+	Ok(None)
+}
+
 fn try_parse_name<'a>(
 	tokens: &[InfoToken<'a>],
 	generics: &[String],
@@ -79,6 +189,12 @@ fn try_parse_generics<'a>(
 	let mut inside = 0;
 	let mut i = open_idx;
 	loop {
+		if i == tokens.len() {
+			return Err(InfoParseError {
+				span: tokens[open_idx].span.clone(),
+				error: ParseError::UnclosedAngleBrackets,
+			});
+		}
 		if let Token::LessThan = tokens[i].token {
 			inside += 1;
 		} else if let Token::GreaterThan = tokens[i].token {
@@ -98,10 +214,6 @@ fn try_parse_generics<'a>(
 
 	let contents = &tokens[open_idx + 1..i];
 
-	let generics_tokens = read_punctuated(contents, Token::Comma)?;
-
-	let mut param_exprs = Vec::new();
-
 	let name = match parse_type(&tokens[..open_idx], generics)? {
 		InfoTypeExpr {
 			expr: TypeExpr::Name(name, _),
@@ -115,7 +227,20 @@ fn try_parse_generics<'a>(
 		}
 	};
 
-	for generic_param_tokens in generics_tokens {
+	Ok(Some(InfoTypeExpr {
+		expr: TypeExpr::Name(name, parse_generic_args(contents, generics)?),
+		idx: tokens[open_idx].span.clone(),
+	}))
+}
+
+/// Parses the inside of `<A, _, B>`, where `_` leaves a parameter to be inferred
+fn parse_generic_args<'a>(
+	contents: &[InfoToken<'a>],
+	generics: &[String],
+) -> Result<Vec<Option<InfoTypeExpr<'a>>>, InfoParseError<'a>> {
+	let mut param_exprs = Vec::new();
+
+	for generic_param_tokens in read_punctuated(contents, Token::Comma)? {
 		if generic_param_tokens.len() == 1 {
 			if let Token::Name(n) = &generic_param_tokens[0].token {
 				if n == "_" {
@@ -127,28 +252,27 @@ fn try_parse_generics<'a>(
 		param_exprs.push(Some(parse_type(&generic_param_tokens, generics)?));
 	}
 
-	Ok(Some(InfoTypeExpr {
-		expr: TypeExpr::Name(name, param_exprs),
-		idx: tokens[open_idx].span.clone(),
-	}))
+	Ok(param_exprs)
 }
 
 fn try_parse_subtype<'a>(
 	tokens: &[InfoToken<'a>],
 	generics: &[String],
 ) -> Result<Option<InfoTypeExpr<'a>>, InfoParseError<'a>> {
-	let (left, right, span) = if let Some((
-		idx,
-		InfoToken {
-			token: Token::DoubleColon,
-			span,
-		},
-	)) = tokens
-		.iter()
-		.enumerate()
-		.rfind(|t| t.1.token == Token::DoubleColon)
-	{
-		(&tokens[0..idx], &tokens[idx + 1..], span)
+	// The last `::` that isn't inside generic arguments
+	let mut depth = 0usize;
+	let mut separator = None;
+	for (idx, token) in tokens.iter().enumerate() {
+		match token.token {
+			Token::LessThan => depth += 1,
+			Token::GreaterThan => depth = depth.saturating_sub(1),
+			Token::DoubleColon if depth == 0 => separator = Some(idx),
+			_ => {}
+		}
+	}
+
+	let (left, right, span) = if let Some(idx) = separator {
+		(&tokens[0..idx], &tokens[idx + 1..], &tokens[idx].span)
 	} else {
 		return Ok(None);
 	};
@@ -157,6 +281,49 @@ fn try_parse_subtype<'a>(
 		panic!(":: with no preceding type {tokens:?}");
 	}
 
+	// Turbofish: `name::<T>` passes type arguments to `name`
+	if let Some(InfoToken {
+		token: Token::LessThan,
+		span: open_span,
+	}) = right.first()
+	{
+		let contents = match right {
+			[
+				_,
+				contents @ ..,
+				InfoToken {
+					token: Token::GreaterThan,
+					span: _,
+				},
+			] => contents,
+			_ => {
+				return Err(InfoParseError {
+					span: open_span.clone(),
+					error: ParseError::UnclosedAngleBrackets,
+				});
+			}
+		};
+		let params = parse_generic_args(contents, generics)?;
+
+		let left = parse_type(left, generics)?;
+		return match left.expr {
+			TypeExpr::Name(name, existing) if existing.is_empty() => Ok(Some(InfoTypeExpr {
+				expr: TypeExpr::Name(name, params),
+				idx: left.idx,
+			})),
+			TypeExpr::Subtype(parent, name, existing) if existing.is_empty() => {
+				Ok(Some(InfoTypeExpr {
+					expr: TypeExpr::Subtype(parent, name, params),
+					idx: left.idx,
+				}))
+			}
+			_ => Err(InfoParseError {
+				span: left.idx,
+				error: ParseError::ExpectedName,
+			}),
+		};
+	}
+
 	let left = parse_type(left, generics)?;
 	let right = parse_type(right, generics)?;
 
@@ -7,8 +7,15 @@ pub fn read_punctuated<'a>(
 ) -> Result<Vec<Vec<InfoToken<'a>>>, InfoParseError<'a>> {
 	let mut result = Vec::new();
 	let mut current = Vec::new();
+	// Separators inside generic arguments like `Either<A, B>` don't split
+	let mut angle_depth = 0usize;
 	for token in tokens {
-		if token.token == separator {
+		match token.token {
+			Token::LessThan => angle_depth += 1,
+			Token::GreaterThan => angle_depth = angle_depth.saturating_sub(1),
+			_ => {}
+		}
+		if token.token == separator && angle_depth == 0 {
 			if !current.is_empty() {
 				result.push(current);
 				current = Vec::new();
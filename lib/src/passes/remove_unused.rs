@@ -1,7 +1,9 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
+	error::Span,
 	ir::{Block, Callable, Operation, Partial, Statement, Terminal},
+	typ::{ConcreteType, InfoTypeError, Type, TypeError},
 	value::{Value, structure::Struct},
 	vm::RunResult,
 };
@@ -12,12 +14,46 @@ pub enum Usage {
 	Fields(HashMap<String, Usage>),
 }
 
+fn get_poison(v: &Value) -> Option<Usage> {
+	if v.data.should_poison() {
+		Some(Usage::Value)
+	} else if let Some(struc) = v.data.as_any().downcast_ref::<Struct>() {
+		let mut poison_fields = HashMap::new();
+		for (field_name, value) in &struc.fields {
+			if let Some(value) = value {
+				let poison = get_poison(value);
+				if let Some(poison) = poison {
+					poison_fields.insert(field_name.clone(), poison);
+				}
+			} else {
+				panic!("all values in literal residualised struct should be known")
+			}
+		}
+		Some(Usage::Fields(poison_fields))
+	} else {
+		None
+	}
+}
+
+fn compile_time_only(span: &Span<'static>) -> InfoTypeError<'static> {
+	InfoTypeError {
+		span: span.clone(),
+		error: TypeError::CompileTimeOnly,
+	}
+}
+
+/// Removes code that's never run, and rejects compile time values that are still needed at run time.
+/// `span` is where errors in these blocks are reported if they aren't inside a call.
 pub fn remove_unused(
 	blocks: &Vec<Block>,
 	start_block: usize,
 	mut poison_vars: HashMap<usize, Usage>,
-) -> Vec<Block> {
+	types: &[Type],
+	span: &Span<'static>,
+) -> Result<Vec<Block>, InfoTypeError<'static>> {
 	let mut used_vars = HashSet::new();
+	// Types of the functions called by their static type, to catch calls that return types
+	let mut function_types = HashMap::new();
 	let mut used_blocks = HashSet::new();
 
 	let mut block_queue = Vec::new();
@@ -70,9 +106,13 @@ pub fn remove_unused(
 					}
 				}
 				Statement {
-					operation: Operation::LoadFunction(_),
-					..
-				} => {}
+					store,
+					operation: Operation::LoadFunction(typ),
+				} => {
+					if let Some(store) = store {
+						function_types.insert(*store, *typ);
+					}
+				}
 				Statement {
 					store,
 					operation: Operation::InitializeStruct(_, fields),
@@ -93,14 +133,24 @@ pub fn remove_unused(
 					}
 				}
 				Statement {
-					operation: Operation::Call { function, args },
+					operation: Operation::Call {
+						function,
+						args,
+						span,
+					},
 					..
 				} => {
 					for arg_var in args {
+						if let Some(Usage::Value) = poison_vars.get(arg_var) {
+							return Err(compile_time_only(span));
+						}
 						used_vars.insert(*arg_var);
 					}
 					match function {
 						Callable::Var(v) => {
+							if returns_type(function_types.get(v), types) {
+								return Err(compile_time_only(span));
+							}
 							used_vars.insert(*v);
 						}
 						Callable::Partial(_) => {}
@@ -121,7 +171,7 @@ pub fn remove_unused(
 						used_vars.insert(*left);
 						match poison_vars.get(left) {
 							None => {}
-							Some(Usage::Value) => panic!("Use of poisoned var as left of access"),
+							Some(Usage::Value) => return Err(compile_time_only(span)),
 							Some(Usage::Fields(poisoned_fields)) => {
 								if poisoned_fields.contains_key(right) {
 									poison_vars.insert(*store, poisoned_fields[right].clone());
@@ -135,32 +185,30 @@ pub fn remove_unused(
 					operation: Operation::LoadLiteral(v),
 				} => {
 					if let Some(store) = store {
-						fn get_poison(v: &Value) -> Option<Usage> {
-							if v.data.should_poison() {
-								Some(Usage::Value)
-							} else if let Some(struc) = v.data.as_any().downcast_ref::<Struct>() {
-								let mut poison_fields = HashMap::new();
-								for (field_name, value) in &struc.fields {
-									if let Some(value) = value {
-										let poison = get_poison(value);
-										if let Some(poison) = poison {
-											poison_fields.insert(field_name.clone(), poison);
-										}
-									} else {
-										panic!(
-											"all values in literal residualised struct should be known"
-										)
-									}
-								}
-								Some(Usage::Fields(poison_fields))
-							} else {
-								None
-							}
-						}
 						let poison = get_poison(v);
 						if let Some(poison) = poison {
 							poison_vars.insert(*store, poison);
 						}
+						function_types.insert(*store, v.typ);
+					}
+				}
+				Statement {
+					store,
+					operation:
+						operation @ (Operation::StructType(_)
+						| Operation::UnionType(_, _)
+						| Operation::TupleType(_)),
+				} => {
+					let parts: Vec<usize> = match operation {
+						Operation::StructType(fields) => fields.values().cloned().collect(),
+						Operation::UnionType(a, b) => vec![*a, *b],
+						Operation::TupleType(elems) => elems.clone(),
+						_ => unreachable!(),
+					};
+					used_vars.extend(parts);
+					// Types only exist at compile time
+					if let Some(store) = store {
+						poison_vars.insert(*store, Usage::Value);
 					}
 				}
 				Statement {
@@ -207,14 +255,24 @@ pub fn remove_unused(
 				block_queue.push(*then);
 				block_queue.push(*els);
 			}
-			Terminal::TailCall { function, args } => {
+			Terminal::TailCall {
+				function,
+				args,
+				span,
+			} => {
 				match function {
 					Callable::Var(var) => {
+						if returns_type(function_types.get(var), types) {
+							return Err(compile_time_only(span));
+						}
 						used_vars.insert(*var);
 					}
 					Callable::Partial(_) => {}
 				}
 				for arg in args {
+					if let Some(Usage::Value) = poison_vars.get(arg) {
+						return Err(compile_time_only(span));
+					}
 					used_vars.insert(*arg);
 				}
 			}
@@ -227,7 +285,7 @@ pub fn remove_unused(
 		match poison {
 			Usage::Value => {
 				if used_vars.contains(var) {
-					panic!("Use of poisoned var {var}")
+					return Err(compile_time_only(span));
 				}
 			}
 			_ => {}
@@ -247,22 +305,46 @@ pub fn remove_unused(
 					Terminal::Branch { cond, then, els } => Terminal::Branch {
 						cond: *cond,
 						then: match then {
-							RunResult::Concrete(v) => RunResult::Concrete(v.clone()),
+							RunResult::Concrete(v) => {
+								if let Some(Usage::Value) = get_poison(v) {
+									return Err(compile_time_only(span));
+								}
+								RunResult::Concrete(v.clone())
+							}
 							RunResult::Partial(p) => RunResult::Partial(Partial {
-								blocks: remove_unused(blocks, p.start_block, poison_vars.clone()),
+								blocks: remove_unused(
+									blocks,
+									p.start_block,
+									poison_vars.clone(),
+									types,
+									span,
+								)?,
 								start_block: p.start_block,
 								generics: p.generics.clone(),
 							}),
 							RunResult::Residualise => RunResult::Residualise,
+							RunResult::Error(error) => RunResult::Error(error.clone()),
 						},
 						els: match els {
-							RunResult::Concrete(v) => RunResult::Concrete(v.clone()),
+							RunResult::Concrete(v) => {
+								if let Some(Usage::Value) = get_poison(v) {
+									return Err(compile_time_only(span));
+								}
+								RunResult::Concrete(v.clone())
+							}
 							RunResult::Partial(p) => RunResult::Partial(Partial {
-								blocks: remove_unused(blocks, p.start_block, poison_vars.clone()),
+								blocks: remove_unused(
+									blocks,
+									p.start_block,
+									poison_vars.clone(),
+									types,
+									span,
+								)?,
 								start_block: p.start_block,
 								generics: p.generics.clone(),
 							}),
 							RunResult::Residualise => RunResult::Residualise,
+							RunResult::Error(error) => RunResult::Error(error.clone()),
 						},
 					},
 					other => other.clone(),
@@ -289,7 +371,12 @@ pub fn remove_unused(
 					}
 					Statement {
 						store,
-						operation: Operation::Call { function, args },
+						operation:
+							Operation::Call {
+								function,
+								args,
+								span,
+							},
 					} => {
 						let mut poisoned_args = HashMap::new();
 						for (arg_idx, arg_var) in args.iter().enumerate() {
@@ -308,11 +395,18 @@ pub fn remove_unused(
 										generics,
 									}) => Callable::Partial(Partial {
 										start_block: *start_block,
-										blocks: remove_unused(blocks, *start_block, poisoned_args),
+										blocks: remove_unused(
+											blocks,
+											*start_block,
+											poisoned_args,
+											types,
+											span,
+										)?,
 										generics: generics.clone(),
 									}),
 								},
 								args: args.clone(),
+								span: span.clone(),
 							},
 						});
 					}
@@ -326,5 +420,14 @@ pub fn remove_unused(
 		}
 	}
 
-	out
+	Ok(out)
+}
+
+/// Whether a function of type `function` gives back a type when called
+fn returns_type(function: Option<&usize>, types: &[Type]) -> bool {
+	let Some(Type::Concrete(ConcreteType::Function(_, ret, _))) = function.map(|typ| &types[*typ])
+	else {
+		return false;
+	};
+	types[*ret] == Type::Concrete(ConcreteType::Type)
 }
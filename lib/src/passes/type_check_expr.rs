@@ -2,7 +2,7 @@ use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
 	parser::{expression::InfoExpr, typ::InfoTypeExpr},
-	typ::{ConcreteType, InfoTypeError, Instantiator, Type, TypeError, TypeExpr},
+	typ::{ConcreteType, InfoTypeError, Instantiator, Type, TypeError, TypeExpr, type_id},
 };
 
 use crate::parser::expression::Expr;
@@ -41,6 +41,10 @@ impl<'a> Scope<'a> {
 	}
 }
 
+fn is_local(name: &InfoTypeExpr<'_>, scope: &Scope) -> bool {
+	matches!(&name.expr, TypeExpr::Name(name, params) if params.is_empty() && scope.get(name).is_some())
+}
+
 #[derive(Debug, Clone)]
 pub struct TypedExpr {
 	pub typ: usize,
@@ -67,18 +71,22 @@ pub fn infer_expr_type<'a>(
 				idx: _,
 			} = &name
 			{
-				if name.len() == 1 && params.len() == 0 {
-					if let Some(type_id) = scope.get(name) {
-						return Ok(TypedExpr {
-							typ: type_id,
-							expr: Expr::Local(name.clone()),
-						});
-					}
+				if params.is_empty()
+					&& let Some(type_id) = scope.get(name)
+				{
+					return Ok(TypedExpr {
+						typ: type_id,
+						expr: Expr::Local(name.clone()),
+					});
 				}
 			};
 			let typ = ins.instantiate(&name, generics)?;
+			let value_type = match ins.get_type(typ) {
+				Some(Type::Concrete(ConcreteType::Function(..) | ConcreteType::Module(_))) => typ,
+				_ => type_id::Type,
+			};
 			Ok(TypedExpr {
-				typ: typ.clone(),
+				typ: value_type,
 				expr: Expr::Name(typ),
 			})
 		}
@@ -107,7 +115,7 @@ pub fn infer_expr_type<'a>(
 				expr: Expr::Block(typed_statements, returns.clone()),
 			})
 		}
-		Expr::InitializeStruct(struct_type_expr, fields) => {
+		Expr::InitializeStruct(struct_type_expr, fields, base) => {
 			let struct_type_id = ins.instantiate(&struct_type_expr, generics)?;
 			let struct_type = ins.get_type(struct_type_id).unwrap();
 			let struct_members = if let Type::Concrete(ConcreteType::Struct(members)) = struct_type
@@ -116,19 +124,38 @@ pub fn infer_expr_type<'a>(
 			} else {
 				return Err(InfoTypeError {
 					span: struct_type_expr.idx.clone(),
-					error: TypeError::NotAStruct(struct_type.clone()),
+					error: TypeError::NotAStruct(ins.type_name(struct_type_id)),
 				});
 			};
 
-			if fields.len() != struct_members.len() {
-				return Err(InfoTypeError {
-					span: expr.idx.clone(),
-					error: TypeError::IncorrectFieldCount {
-						expected: struct_members.len(),
-						got: fields.len(),
-					},
-				});
-			}
+			let base = match base {
+				Some(base) => {
+					let base = infer_expr_type(*base, ins, scope, return_type, generics, prefix)?;
+					if !ins.compatible(base.typ, struct_type_id, 0).unwrap() {
+						return Err(InfoTypeError {
+							span: expr.idx.clone(),
+							error: TypeError::IncompatibleTypes {
+								expected: ins.type_name(struct_type_id),
+								got: ins.type_name(base.typ),
+							},
+						});
+					}
+					Some(Box::new(base))
+				}
+				None => {
+					for name in struct_members.keys() {
+						if !fields.contains_key(name)
+							&& ins.field_default(struct_type_id, name)?.is_none()
+						{
+							return Err(InfoTypeError {
+								span: expr.idx.clone(),
+								error: TypeError::MissingField(name.clone()),
+							});
+						}
+					}
+					None
+				}
+			};
 
 			let mut assignees = HashMap::new();
 
@@ -151,8 +178,8 @@ pub fn infer_expr_type<'a>(
 					return Err(InfoTypeError {
 						span: expr.idx.clone(),
 						error: TypeError::IncompatibleTypes {
-							expected: ins.get_type(slot).cloned().unwrap(),
-							got: ins.get_type(assignee_type).cloned().unwrap(),
+							expected: ins.type_name(slot),
+							got: ins.type_name(assignee_type),
 						},
 					});
 				}
@@ -160,7 +187,7 @@ pub fn infer_expr_type<'a>(
 
 			Ok(TypedExpr {
 				typ: struct_type_id,
-				expr: Expr::InitializeStruct(struct_type_id, assignees),
+				expr: Expr::InitializeStruct(struct_type_id, assignees, base),
 			})
 		}
 		Expr::Access(struct_expr, field_name) => {
@@ -181,7 +208,7 @@ pub fn infer_expr_type<'a>(
 			} else {
 				Err(InfoTypeError {
 					span: expr.idx.clone(),
-					error: TypeError::NotAStruct(ins.get_type(struct_typed.typ).unwrap().clone()),
+					error: TypeError::NotAStruct(ins.type_name(struct_typed.typ)),
 				})
 			}?;
 
@@ -200,9 +227,38 @@ pub fn infer_expr_type<'a>(
 				typ: ins.instantiate(&comparison_type, generics)?,
 			},
 		}),
+		// What the literal denotes can depend on locals, so only its kind is known
+		Expr::TypeLiteral(_) => Ok(TypedExpr {
+			typ: type_id::Type,
+			expr: Expr::TypeLiteral(type_id::Type),
+		}),
 		Expr::Call(function_expr, args_exprs) => {
-			let function_expr =
-				infer_expr_type(*function_expr, ins, scope, return_type, generics, prefix)?;
+			let function_expr = match function_expr.expr {
+				Expr::Name(name)
+					if !is_local(&name, scope) && ins.needs_inference(&name, generics)? =>
+				{
+					let mut arg_types = Vec::new();
+					for arg in &args_exprs {
+						arg_types.push(
+							infer_expr_type(
+								arg.clone(),
+								ins,
+								scope,
+								return_type,
+								generics,
+								prefix,
+							)?
+							.typ,
+						);
+					}
+					let typ = ins.instantiate_call(&name, generics, &args_exprs, &arg_types)?;
+					TypedExpr {
+						typ,
+						expr: Expr::Name(typ),
+					}
+				}
+				_ => infer_expr_type(*function_expr, ins, scope, return_type, generics, prefix)?,
+			};
 
 			let (args, callee_return_type) =
 				if let Type::Concrete(ConcreteType::Function(args, callee_return_type, _imp)) =
@@ -212,9 +268,7 @@ pub fn infer_expr_type<'a>(
 				} else {
 					return Err(InfoTypeError {
 						span: expr.idx.clone(),
-						error: TypeError::NotAFunction(
-							ins.get_type(function_expr.typ).cloned().unwrap(),
-						),
+						error: TypeError::NotAFunction(ins.type_name(function_expr.typ)),
 					});
 				};
 
@@ -243,8 +297,8 @@ pub fn infer_expr_type<'a>(
 					return Err(InfoTypeError {
 						span: expr.idx.clone(),
 						error: TypeError::IncompatibleTypes {
-							expected: ins.get_type(args[i]).cloned().unwrap(),
-							got: ins.get_type(arg_expr.typ).cloned().unwrap(),
+							expected: ins.type_name(args[i]),
+							got: ins.type_name(arg_expr.typ),
 						},
 					});
 				}
@@ -256,6 +310,61 @@ pub fn infer_expr_type<'a>(
 				expr: Expr::Call(Box::new(function_expr), typed_arg_exprs),
 			})
 		}
+		Expr::MethodCall {
+			receiver,
+			method,
+			args: args_exprs,
+		} => {
+			let receiver = infer_expr_type(*receiver, ins, scope, return_type, generics, prefix)?;
+			let function = ins.instantiate_method(receiver.typ, &method, &expr.idx, generics)?;
+
+			let (args, callee_return_type) =
+				if let Some(Type::Concrete(ConcreteType::Function(args, callee_return_type, _))) =
+					ins.get_type(function).cloned()
+				{
+					(args, callee_return_type)
+				} else {
+					unreachable!("instantiate_method only returns functions")
+				};
+
+			if args_exprs.len() + 1 != args.len() {
+				return Err(InfoTypeError {
+					span: expr.idx.clone(),
+					error: TypeError::IncorrectArgumentCount {
+						expected: args.len() - 1,
+						got: args_exprs.len(),
+					},
+				});
+			}
+
+			let mut typed_arg_exprs = vec![receiver];
+
+			for (arg_expr, slot) in args_exprs.into_iter().zip(&args[1..]) {
+				let arg_expr =
+					infer_expr_type(arg_expr, ins, scope, return_type, generics, prefix)?;
+				if !ins.compatible(arg_expr.typ, *slot, 0).unwrap() {
+					return Err(InfoTypeError {
+						span: expr.idx.clone(),
+						error: TypeError::IncompatibleTypes {
+							expected: ins.type_name(*slot),
+							got: ins.type_name(arg_expr.typ),
+						},
+					});
+				}
+				typed_arg_exprs.push(arg_expr);
+			}
+
+			Ok(TypedExpr {
+				typ: callee_return_type,
+				expr: Expr::Call(
+					Box::new(TypedExpr {
+						typ: function,
+						expr: Expr::Name(function),
+					}),
+					typed_arg_exprs,
+				),
+			})
+		}
 		Expr::If { cond, then, els } => {
 			let cond_typed = infer_expr_type(*cond, ins, scope, return_type, generics, prefix)?;
 			let bool = ins.add(Type::Concrete(ConcreteType::Bool));
@@ -263,8 +372,8 @@ pub fn infer_expr_type<'a>(
 				return Err(InfoTypeError {
 					span: expr.idx.clone(),
 					error: TypeError::IncompatibleTypes {
-						expected: Type::Concrete(ConcreteType::Bool),
-						got: ins.get_type(cond_typed.typ).cloned().unwrap(),
+						expected: ins.type_name(bool),
+						got: ins.type_name(cond_typed.typ),
 					},
 				});
 			}
@@ -317,7 +426,11 @@ pub fn infer_expr_type<'a>(
 				},
 			})
 		}
-		Expr::Index(_, _) => panic!("TODO: remove indexing until i add operator overloading"),
+		// TODO: remove indexing until i add operator overloading
+		Expr::Index(_, _) => Err(InfoTypeError {
+			span: expr.idx.clone(),
+			error: TypeError::UntypedIndex,
+		}),
 		Expr::Let(name, value_expr) => {
 			let value_typed =
 				infer_expr_type(*value_expr, ins, scope, return_type, generics, prefix)?;
@@ -348,8 +461,8 @@ pub fn infer_expr_type<'a>(
 				return Err(InfoTypeError {
 					span: expr.idx.clone(),
 					error: TypeError::IncompatibleTypes {
-						expected: ins.get_type(return_type).cloned().unwrap(),
-						got: ins.get_type(expr_type).cloned().unwrap(),
+						expected: ins.type_name(return_type),
+						got: ins.type_name(expr_type),
 					},
 				});
 			}
@@ -0,0 +1,166 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	path::{Path, PathBuf},
+};
+
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+
+use crate::project::ProjectError;
+
+/// Name of the file describing a project, found in its root directory
+pub const MANIFEST_FILE: &str = "preval.toml";
+
+/// Where a project's sources and native libraries are, and where it starts
+#[derive(Debug, Clone)]
+pub struct Manifest {
+	pub name: String,
+	pub version: Version,
+	/// The directory the manifest is in, if it was read from a file
+	pub dir: Option<PathBuf>,
+	/// Directories whose contents make up the root module. Subdirectories become modules.
+	pub roots: Vec<PathBuf>,
+	/// Directories searched for native libraries before the system's default locations
+	pub search_paths: Vec<PathBuf>,
+	/// Where to load each native library from, by `dylib` name. Either the
+	/// library itself or a directory containing it.
+	pub libraries: HashMap<String, PathBuf>,
+	/// Name of the function the program starts from
+	pub entry: String,
+	/// Where the compiled program is written
+	pub output: PathBuf,
+	/// Packages whose sources are mounted as modules named after them
+	pub dependencies: BTreeMap<String, Dependency>,
+	/// Directory of packages laid out as `name/version/preval.toml`
+	pub registry: Option<PathBuf>,
+}
+
+/// Another package a project uses
+#[derive(Debug, Clone)]
+pub struct Dependency {
+	/// Where the package is, otherwise it's taken from the registry
+	pub path: Option<PathBuf>,
+	pub version: VersionReq,
+}
+
+impl Default for Manifest {
+	fn default() -> Self {
+		Self {
+			name: String::new(),
+			version: Version::new(0, 0, 0),
+			dir: None,
+			roots: Vec::new(),
+			search_paths: Vec::new(),
+			libraries: HashMap::new(),
+			entry: "main".to_string(),
+			output: PathBuf::from("out.pvc"),
+			dependencies: BTreeMap::new(),
+			registry: None,
+		}
+	}
+}
+
+/// The layout of `preval.toml`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+	package: Package,
+	#[serde(default)]
+	native: HashMap<String, PathBuf>,
+	#[serde(default)]
+	dependencies: BTreeMap<String, DependencySpec>,
+}
+
+/// A dependency is either a version requirement, or a table with a path and/or version
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DependencySpec {
+	Version(VersionReq),
+	Table {
+		path: Option<PathBuf>,
+		version: Option<VersionReq>,
+	},
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Package {
+	name: String,
+	version: Option<Version>,
+	registry: Option<PathBuf>,
+	sources: Option<Vec<PathBuf>>,
+	entry: Option<String>,
+	output: Option<PathBuf>,
+}
+
+impl Manifest {
+	/// Parses a manifest, with paths relative to `dir`
+	pub fn parse(contents: &str, dir: &Path) -> Result<Self, ProjectError> {
+		let file: ManifestFile =
+			toml::from_str(contents).map_err(|error| ProjectError::Manifest {
+				path: dir.join(MANIFEST_FILE),
+				message: error.to_string(),
+			})?;
+
+		let default = Self::default();
+		Ok(Self {
+			name: file.package.name,
+			version: file.package.version.unwrap_or(default.version),
+			dir: Some(dir.to_path_buf()),
+			roots: match file.package.sources {
+				Some(sources) => sources.iter().map(|source| dir.join(source)).collect(),
+				None => vec![dir.to_path_buf()],
+			},
+			search_paths: Vec::new(),
+			libraries: file
+				.native
+				.into_iter()
+				.map(|(name, path)| (name, dir.join(path)))
+				.collect(),
+			entry: file.package.entry.unwrap_or(default.entry),
+			output: dir.join(file.package.output.unwrap_or(default.output)),
+			dependencies: file
+				.dependencies
+				.into_iter()
+				.map(|(name, spec)| {
+					let dependency = match spec {
+						DependencySpec::Version(version) => Dependency {
+							path: None,
+							version,
+						},
+						DependencySpec::Table { path, version } => Dependency {
+							path: path.map(|path| dir.join(path)),
+							version: version.unwrap_or(VersionReq::STAR),
+						},
+					};
+					(name, dependency)
+				})
+				.collect(),
+			registry: file.package.registry.map(|registry| dir.join(registry)),
+		})
+	}
+
+	/// Reads the manifest at `path`
+	pub fn read(path: &Path) -> Result<Self, ProjectError> {
+		let contents = std::fs::read_to_string(path).map_err(|error| ProjectError::Io {
+			path: path.to_path_buf(),
+			error,
+		})?;
+		Self::parse(&contents, path.parent().unwrap_or(Path::new(".")))
+	}
+
+	/// Finds the manifest in `dir` or the closest directory above it
+	pub fn find(dir: &Path) -> Option<PathBuf> {
+		dir.ancestors()
+			.map(|dir| dir.join(MANIFEST_FILE))
+			.find(|path| path.is_file())
+	}
+
+	/// Reads the manifest found from `dir`
+	pub fn discover(dir: &Path) -> Result<Self, ProjectError> {
+		match Self::find(dir) {
+			Some(path) => Self::read(&path),
+			None => Err(ProjectError::MissingManifest(dir.to_path_buf())),
+		}
+	}
+}
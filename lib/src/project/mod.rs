@@ -0,0 +1,489 @@
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap},
+	fmt::Display,
+	fs::read_dir,
+	path::{Path, PathBuf},
+};
+
+use semver::{Version, VersionReq};
+
+use crate::{
+	error::{Error, InfoError, Span},
+	ir::{Partial, debug_verify},
+	parser::{module::declaration_pass, typ::InfoTypeExpr},
+	passes::remove_unused::{Usage, remove_unused},
+	stdlib,
+	tokeniser::{TokeniseError, get_line_and_column, tokenise},
+	typ::{
+		ConcreteType, Implementation, Instantiator, Template, Type, TypeError, TypeExpr, type_id,
+	},
+	value::{Value, native, primitive::IO},
+	vm::{EvalError, RunResult, evaluate},
+};
+
+mod manifest;
+mod package;
+
+pub use manifest::{Dependency, MANIFEST_FILE, Manifest};
+pub use package::{LOCK_FILE, Package, PackageSource, resolve, write_lock};
+
+/// A file of declarations
+#[derive(Debug, Clone)]
+pub struct Source {
+	/// Path of the module the declarations go in, empty for the root module
+	pub module: Vec<String>,
+	/// Name used in error locations, usually the file's path
+	pub file: String,
+	pub contents: String,
+}
+
+/// The sources of a program, which may come from disk or memory
+#[derive(Debug, Clone)]
+pub struct Project {
+	pub sources: Vec<Source>,
+	pub search_paths: Vec<PathBuf>,
+	/// Where to load each native library from, by `dylib` name
+	pub libraries: HashMap<String, PathBuf>,
+	pub entry: String,
+	/// The dependencies it was loaded with, by package name
+	pub packages: BTreeMap<String, Package>,
+}
+
+/// A position in a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+	pub file: String,
+	/// Line and column, if the file could be read
+	pub position: Option<(usize, usize)>,
+}
+
+impl Display for Location {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.position {
+			Some((line, column)) => write!(f, "{:?}:{line}:{column}", self.file),
+			None => write!(f, "{:?}", self.file),
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+	Io {
+		path: PathBuf,
+		error: std::io::Error,
+	},
+	Manifest {
+		path: PathBuf,
+		message: String,
+	},
+	/// No manifest in the directory or the ones above it
+	MissingManifest(PathBuf),
+	/// No version of a dependency satisfies its requirement
+	UnknownDependency {
+		name: String,
+		requirement: VersionReq,
+	},
+	/// Two packages depend on different versions of the same package
+	VersionConflict {
+		name: String,
+		/// Each version and the directory it was found in
+		versions: Vec<(Version, PathBuf)>,
+	},
+	/// A dependency is named `std` or after a module in the project's roots
+	PackageNameTaken(String),
+	/// A dependency's path leads to a package with another name
+	PackageName {
+		expected: String,
+		found: String,
+	},
+	/// A dependency's path leads to a version that doesn't satisfy its requirement
+	VersionMismatch {
+		name: String,
+		requirement: VersionReq,
+		found: Version,
+	},
+	/// A registry package's contents differ from when it was locked
+	HashMismatch {
+		name: String,
+		version: Version,
+	},
+	/// A module has the same name as another declaration in its parent
+	DuplicateModule(Vec<String>),
+	Tokenise {
+		location: Location,
+		error: TokeniseError,
+	},
+	Declaration {
+		location: Location,
+		error: Error<'static>,
+	},
+	Type {
+		location: Location,
+		error: TypeError,
+	},
+	/// No declaration has the entry point's name
+	MissingEntry(String),
+	/// The entry point isn't a function taking `compile_io` and `io`
+	InvalidEntry(String),
+	/// The program stopped on a native call whose result isn't known even at run time
+	Residualised,
+	Eval {
+		message: String,
+		/// Where the error was raised, then each call that led to it
+		trace: Vec<Location>,
+	},
+}
+
+impl Display for ProjectError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ProjectError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+			ProjectError::Manifest { path, message } => {
+				write!(f, "invalid manifest {}: {message}", path.display())
+			}
+			ProjectError::MissingManifest(dir) => write!(
+				f,
+				"no {MANIFEST_FILE} in {} or the directories above it",
+				dir.display()
+			),
+			ProjectError::DuplicateModule(path) => {
+				write!(f, "module `{}` is defined more than once", path.join("::"))
+			}
+			ProjectError::UnknownDependency { name, requirement } => {
+				write!(
+					f,
+					"no version of `{name}` matching `{requirement}` was found"
+				)
+			}
+			ProjectError::VersionConflict { name, versions } => {
+				let versions: Vec<_> = versions
+					.iter()
+					.map(|(version, dir)| format!("{version} from {}", dir.display()))
+					.collect();
+				write!(
+					f,
+					"`{name}` is depended on as both {}",
+					versions.join(" and ")
+				)
+			}
+			ProjectError::PackageNameTaken(name) => write!(
+				f,
+				"a package can't be called `{name}`, as that's already the name of a module"
+			),
+			ProjectError::PackageName { expected, found } => {
+				write!(f, "expected package `{expected}`, found `{found}`")
+			}
+			ProjectError::VersionMismatch {
+				name,
+				requirement,
+				found,
+			} => write!(f, "`{name}` {found} doesn't match `{requirement}`"),
+			ProjectError::HashMismatch { name, version } => write!(
+				f,
+				"`{name}` {version} has changed since it was recorded in {LOCK_FILE}"
+			),
+			ProjectError::Tokenise { location, error } => write!(f, "{error} at {location}"),
+			ProjectError::Declaration { location, error } => write!(f, "{error} at {location}"),
+			ProjectError::Type { location, error } => write!(f, "{error} at {location}"),
+			ProjectError::MissingEntry(name) => write!(f, "no `{name}` function was found"),
+			ProjectError::InvalidEntry(name) => {
+				write!(
+					f,
+					"`{name}` is not a function like `fn {name}(compile_io: IO, io: IO)`"
+				)
+			}
+			ProjectError::Residualised => {
+				write!(
+					f,
+					"the program couldn't finish because a native call gave no result"
+				)
+			}
+			ProjectError::Eval { message, trace } => {
+				write!(f, "{message}")?;
+				for (idx, location) in trace.iter().enumerate() {
+					let context = if idx == 0 { "at" } else { "called from" };
+					write!(f, "\n\t{context} {location}")?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+impl Project {
+	pub fn new() -> Self {
+		Self {
+			sources: Vec::new(),
+			search_paths: Vec::new(),
+			libraries: HashMap::new(),
+			entry: "main".to_string(),
+			packages: BTreeMap::new(),
+		}
+	}
+
+	/// Reads every source in the manifest's roots, and in the roots of its
+	/// dependencies as modules named after them
+	pub fn load(manifest: &Manifest) -> Result<Self, ProjectError> {
+		let mut project = Self {
+			sources: Vec::new(),
+			search_paths: manifest.search_paths.clone(),
+			libraries: HashMap::new(),
+			entry: manifest.entry.clone(),
+			packages: resolve(manifest)?,
+		};
+		for root in &manifest.roots {
+			project.add_root(root)?;
+		}
+
+		for (name, package) in project.packages.clone() {
+			for root in &package.manifest.roots {
+				project.add_dir(root, vec![name.clone()])?;
+			}
+			project.libraries.extend(package.manifest.libraries);
+		}
+		// The project's own libraries take precedence over those of its dependencies
+		project.libraries.extend(manifest.libraries.clone());
+
+		Ok(project)
+	}
+
+	/// Adds the `.pv` files in `path` to the root module, and those in its
+	/// subdirectories to modules named after them
+	pub fn add_root(&mut self, path: impl AsRef<Path>) -> Result<(), ProjectError> {
+		self.add_dir(path.as_ref(), Vec::new())
+	}
+
+	fn add_dir(&mut self, path: &Path, module: Vec<String>) -> Result<(), ProjectError> {
+		let io_error = |error| ProjectError::Io {
+			path: path.to_path_buf(),
+			error,
+		};
+
+		// Sorted so that declarations are added in the same order on every platform
+		let mut entries = read_dir(path)
+			.map_err(io_error)?
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(io_error)?;
+		entries.sort_by_key(|entry| entry.file_name());
+
+		for entry in entries {
+			let path = entry.path();
+			if path.is_dir() {
+				let mut child = module.clone();
+				child.push(entry.file_name().to_string_lossy().into_owned());
+				self.add_dir(&path, child)?;
+			} else if path.extension().is_some_and(|extension| extension == "pv") {
+				let contents =
+					std::fs::read_to_string(&path).map_err(|error| ProjectError::Io {
+						path: path.clone(),
+						error,
+					})?;
+				self.add_source(module.clone(), path.to_string_lossy(), contents);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Adds declarations from memory to the module at `module`
+	pub fn add_source(
+		&mut self,
+		module: Vec<String>,
+		file: impl Into<String>,
+		contents: impl Into<String>,
+	) {
+		self.sources.push(Source {
+			module,
+			file: file.into(),
+			contents: contents.into(),
+		});
+	}
+
+	/// Finds the line and column of `span`, from the project's sources if it's
+	/// one of them or otherwise from disk
+	pub fn locate(&self, span: &Span<'_>) -> Location {
+		match self.sources.iter().find(|source| source.file == span.file) {
+			Some(source) => Location {
+				file: source.file.clone(),
+				position: get_line_and_column(&source.contents, span.index).ok(),
+			},
+			None => locate_on_disk(span),
+		}
+	}
+
+	/// Declares everything in the project's sources in a new [`Instantiator`]
+	pub fn instantiator(&self) -> Result<Instantiator<'static>, ProjectError> {
+		let mut ins = Instantiator::new();
+
+		for source in &self.sources {
+			let tokens = tokenise(&source.contents, 0, Cow::Owned(source.file.clone())).map_err(
+				|error| ProjectError::Tokenise {
+					location: self.locate(&error.idx),
+					error: error.error,
+				},
+			)?;
+
+			let module = module_mut(&mut ins.global_namespace, &source.module)?;
+			declaration_pass(&tokens, module, &mut ins.globs, &source.module).map_err(
+				|InfoError { span, data }| ProjectError::Declaration {
+					location: self.locate(&span),
+					error: data,
+				},
+			)?;
+		}
+
+		Ok(ins)
+	}
+
+	/// Partially evaluates the entry point with `compile_io` known and `io` unknown,
+	/// returning what's left to run along with the types it uses
+	pub fn compile(&self) -> Result<(RunResult, Vec<Type>), ProjectError> {
+		// Native functions are checked against their libraries as they're instantiated
+		self.use_native_libraries();
+		let mut ins = self.instantiator()?;
+		if !ins.global_namespace.contains_key(&self.entry) {
+			return Err(ProjectError::MissingEntry(self.entry.clone()));
+		}
+
+		let entry = ins
+			.instantiate(
+				&InfoTypeExpr {
+					expr: TypeExpr::Name(self.entry.clone(), vec![]),
+					idx: Span {
+						file: Cow::Borrowed(file!()),
+						index: 0,
+					},
+				},
+				&[],
+			)
+			.map_err(|error| ProjectError::Type {
+				location: self.locate(&error.span),
+				error: error.error,
+			})?;
+
+		let entry_span = ins.global_namespace[&self.entry]
+			.expr
+			.idx
+			.clone()
+			.into_owned();
+		let mut types = ins.types;
+
+		let Type::Concrete(ConcreteType::Function(_, _, Implementation::Normal(imp))) =
+			types[entry].clone()
+		else {
+			return Err(ProjectError::InvalidEntry(self.entry.clone()));
+		};
+
+		let cio = Some(Value::new(IO, type_id::IO));
+		let mut args = HashMap::from([(0, cio), (1, None)]);
+		let eval = evaluate(&mut types, imp, &mut args, 0, vec![]);
+		debug_verify("evaluation", &eval, 2, &types);
+
+		let mut poisoned_vars = HashMap::new();
+		poisoned_vars.insert(0, Usage::Value);
+
+		let optimized = match eval {
+			RunResult::Residualise => unreachable!(),
+			RunResult::Error(error) => return Err(self.eval_error(error)),
+			RunResult::Concrete(c) => RunResult::Concrete(c),
+			RunResult::Partial(p) => RunResult::Partial(Partial {
+				blocks: remove_unused(&p.blocks, p.start_block, poisoned_vars, &types, &entry_span)
+					.map_err(|error| ProjectError::Type {
+						location: self.locate(&error.span),
+						error: error.error,
+					})?,
+				start_block: p.start_block,
+				generics: p.generics,
+			}),
+		};
+		debug_verify("removing unused code", &optimized, 2, &types);
+
+		Ok((optimized, types))
+	}
+
+	/// Makes native functions load their libraries from where this project says
+	pub fn use_native_libraries(&self) {
+		native::set_search_paths(self.search_paths.clone(), self.libraries.clone());
+	}
+
+	fn eval_error(&self, error: EvalError) -> ProjectError {
+		ProjectError::Eval {
+			message: error.message,
+			trace: error.trace.iter().map(|span| self.locate(span)).collect(),
+		}
+	}
+}
+
+impl Default for Project {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Runs a compiled program with both `compile_io` and `io` available
+pub fn run(program: RunResult, mut types: Vec<Type>) -> Result<(), ProjectError> {
+	let mut vars: HashMap<usize, Option<Value>> = HashMap::new();
+
+	vars.insert(0, Some(Value::new(IO {}, type_id::IO)));
+	vars.insert(1, Some(Value::new(IO {}, type_id::IO)));
+
+	let mut eval = program;
+	loop {
+		match eval {
+			RunResult::Concrete(_) => return Ok(()),
+			RunResult::Partial(p) => {
+				eval = evaluate(&mut types, p.blocks, &mut vars, p.start_block, p.generics);
+			}
+			RunResult::Residualise => return Err(ProjectError::Residualised),
+			RunResult::Error(error) => {
+				return Err(ProjectError::Eval {
+					message: error.message,
+					trace: error.trace.iter().map(locate_on_disk).collect(),
+				});
+			}
+		}
+	}
+}
+
+/// Finds the line and column of `span` in the bundled `std`, or otherwise from disk
+fn locate_on_disk(span: &Span<'_>) -> Location {
+	let contents = match stdlib::source(&span.file) {
+		Some(contents) => Some(contents.to_string()),
+		None => std::fs::read_to_string(span.file.as_ref()).ok(),
+	};
+	Location {
+		file: span.file.to_string(),
+		position: contents.and_then(|contents| get_line_and_column(&contents, span.index).ok()),
+	}
+}
+
+/// The members of the module at `path`, creating any modules that don't exist yet
+fn module_mut<'a, 'b>(
+	namespace: &'b mut HashMap<String, Template<'a>>,
+	path: &[String],
+) -> Result<&'b mut HashMap<String, Template<'a>>, ProjectError> {
+	let mut members = namespace;
+	for (depth, name) in path.iter().enumerate() {
+		let module_path = path[..=depth].to_vec();
+		let template = members.entry(name.clone()).or_insert_with(|| Template {
+			parameters: 0,
+			expr: InfoTypeExpr {
+				expr: TypeExpr::Module(HashMap::new(), module_path.clone()),
+				idx: Span {
+					file: Cow::Borrowed(file!()),
+					index: 0,
+				},
+			},
+			members: HashMap::new(),
+			bounds: Vec::new(),
+			defaults: HashMap::new(),
+			public: true,
+		});
+		members = match &mut template.expr.expr {
+			TypeExpr::Module(members, _) => members,
+			_ => return Err(ProjectError::DuplicateModule(module_path)),
+		};
+	}
+	Ok(members)
+}
@@ -0,0 +1,290 @@
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fs::read_dir,
+	path::{Path, PathBuf},
+};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::project::{MANIFEST_FILE, Manifest, ProjectError, manifest::Dependency};
+
+/// Name of the file recording the packages a project was built with, next to its manifest
+pub const LOCK_FILE: &str = "preval.lock";
+
+/// Where a dependency was found
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageSource {
+	Path,
+	Registry,
+}
+
+/// A dependency resolved to one version of a package
+#[derive(Debug, Clone)]
+pub struct Package {
+	pub manifest: Manifest,
+	pub source: PackageSource,
+	/// SHA-256 of the package's manifest and sources
+	pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LockedPackage {
+	name: String,
+	version: Version,
+	source: PackageSource,
+	hash: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Lockfile {
+	#[serde(default)]
+	package: Vec<LockedPackage>,
+}
+
+/// Finds every package `manifest` depends on, directly or not, by package name.
+/// Registry packages resolve to the newest version satisfying every requirement on
+/// them, or to the version in the lockfile when it still does. A package chosen
+/// before a later requirement ruled it out is chosen again with that requirement
+/// added, though requirements from versions no longer chosen are kept.
+pub fn resolve(manifest: &Manifest) -> Result<BTreeMap<String, Package>, ProjectError> {
+	let lock = read_lock(manifest)?;
+
+	// Requirements only grow, so this finishes once a choice satisfies all of them
+	let mut requirements: BTreeMap<String, Dependency> = BTreeMap::new();
+	'resolve: loop {
+		let mut packages: BTreeMap<String, Package> = BTreeMap::new();
+		let mut pending: VecDeque<(String, Dependency)> =
+			manifest.dependencies.clone().into_iter().collect();
+
+		while let Some((name, dependency)) = pending.pop_front() {
+			// Packages are modules of the project, so can't share a name with another
+			if name == "std" || manifest.roots.iter().any(|root| root.join(&name).is_dir()) {
+				return Err(ProjectError::PackageNameTaken(name));
+			}
+
+			let requirement = requirements
+				.entry(name.clone())
+				.or_insert_with(|| dependency.clone());
+			for comparator in &dependency.version.comparators {
+				if !requirement.version.comparators.contains(comparator) {
+					requirement.version.comparators.push(comparator.clone());
+				}
+			}
+
+			let Some(existing) = packages.get(&name) else {
+				let package = find_package(&name, requirement, manifest, &lock)?;
+				pending.extend(package.manifest.dependencies.clone());
+				packages.insert(name, package);
+				continue;
+			};
+
+			// Two paths, or a path and the registry, can only agree on the same directory
+			if dependency.path.is_some() || existing.source == PackageSource::Path {
+				let package = find_package(&name, &dependency, manifest, &lock)?;
+				if existing.manifest.dir != package.manifest.dir {
+					return Err(ProjectError::VersionConflict {
+						name,
+						versions: [existing, &package]
+							.map(|package| {
+								(
+									package.manifest.version.clone(),
+									package.manifest.dir.clone().unwrap_or_default(),
+								)
+							})
+							.to_vec(),
+					});
+				}
+			}
+			if !requirement.version.matches(&existing.manifest.version) {
+				continue 'resolve;
+			}
+		}
+
+		return Ok(packages);
+	}
+}
+
+/// Rewrites the lockfile next to `manifest` with the packages it was resolved to
+pub fn write_lock(
+	manifest: &Manifest,
+	packages: &BTreeMap<String, Package>,
+) -> Result<(), ProjectError> {
+	if let Some(dir) = &manifest.dir
+		&& !packages.is_empty()
+	{
+		let path = dir.join(LOCK_FILE);
+		let resolved = Lockfile {
+			package: packages
+				.iter()
+				.map(|(name, package)| LockedPackage {
+					name: name.clone(),
+					version: package.manifest.version.clone(),
+					source: package.source,
+					hash: package.hash.clone(),
+				})
+				.collect(),
+		};
+		if resolved != read_lock(manifest)? {
+			let contents = toml::to_string(&resolved).unwrap();
+			std::fs::write(&path, contents).map_err(|error| ProjectError::Io { path, error })?;
+		}
+	}
+
+	Ok(())
+}
+
+fn find_package(
+	name: &String,
+	dependency: &Dependency,
+	root: &Manifest,
+	lock: &Lockfile,
+) -> Result<Package, ProjectError> {
+	let locked = lock.package.iter().find(|locked| &locked.name == name);
+
+	let (dir, source) = match &dependency.path {
+		Some(path) => (path.clone(), PackageSource::Path),
+		None => {
+			let Some(registry) = &root.registry else {
+				return Err(ProjectError::UnknownDependency {
+					name: name.clone(),
+					requirement: dependency.version.clone(),
+				});
+			};
+			let versions = registry_versions(&registry.join(name));
+			let version = match locked {
+				Some(locked)
+					if locked.source == PackageSource::Registry
+						&& dependency.version.matches(&locked.version)
+						&& versions.contains(&locked.version) =>
+				{
+					Some(locked.version.clone())
+				}
+				_ => versions
+					.into_iter()
+					.filter(|version| dependency.version.matches(version))
+					.max(),
+			};
+			let Some(version) = version else {
+				return Err(ProjectError::UnknownDependency {
+					name: name.clone(),
+					requirement: dependency.version.clone(),
+				});
+			};
+			(
+				registry.join(name).join(version.to_string()),
+				PackageSource::Registry,
+			)
+		}
+	};
+
+	let dir = dir
+		.canonicalize()
+		.map_err(|error| ProjectError::Io { path: dir, error })?;
+	let manifest = Manifest::read(&dir.join(MANIFEST_FILE))?;
+	if &manifest.name != name {
+		return Err(ProjectError::PackageName {
+			expected: name.clone(),
+			found: manifest.name,
+		});
+	}
+	if !dependency.version.matches(&manifest.version) {
+		return Err(ProjectError::VersionMismatch {
+			name: name.clone(),
+			requirement: dependency.version.clone(),
+			found: manifest.version,
+		});
+	}
+
+	let hash = package_hash(&dir)?;
+	// Registry packages are never edited in place, so a different hash means the
+	// package was tampered with or republished
+	if source == PackageSource::Registry
+		&& let Some(locked) = locked
+		&& locked.version == manifest.version
+		&& locked.hash != hash
+	{
+		return Err(ProjectError::HashMismatch {
+			name: name.clone(),
+			version: manifest.version,
+		});
+	}
+
+	Ok(Package {
+		manifest,
+		source,
+		hash,
+	})
+}
+
+/// Versions of a package in a registry, from the names of its subdirectories
+fn registry_versions(dir: &Path) -> Vec<Version> {
+	let Ok(entries) = read_dir(dir) else {
+		return Vec::new();
+	};
+	entries
+		.filter_map(|entry| entry.ok())
+		.filter_map(|entry| Version::parse(&entry.file_name().to_string_lossy()).ok())
+		.collect()
+}
+
+/// Reads the lockfile next to `manifest`, which is empty if there isn't one
+fn read_lock(manifest: &Manifest) -> Result<Lockfile, ProjectError> {
+	let path = match &manifest.dir {
+		Some(dir) if dir.join(LOCK_FILE).is_file() => dir.join(LOCK_FILE),
+		_ => return Ok(Lockfile::default()),
+	};
+	let contents = std::fs::read_to_string(&path).map_err(|error| ProjectError::Io {
+		path: path.clone(),
+		error,
+	})?;
+	toml::from_str(&contents).map_err(|error| ProjectError::Manifest {
+		path,
+		message: error.to_string(),
+	})
+}
+
+/// Hashes the manifest and `.pv` files of the package in `dir`, in path order
+fn package_hash(dir: &Path) -> Result<String, ProjectError> {
+	let mut files = Vec::new();
+	collect_files(dir, &mut files)?;
+	files.sort();
+
+	let mut hasher = Sha256::new();
+	for file in files {
+		let contents = std::fs::read(&file).map_err(|error| ProjectError::Io {
+			path: file.clone(),
+			error,
+		})?;
+		let relative = file.strip_prefix(dir).unwrap_or(&file);
+		hasher.update(relative.to_string_lossy().as_bytes());
+		hasher.update([0]);
+		hasher.update(&contents);
+	}
+
+	Ok(hasher
+		.finalize()
+		.iter()
+		.map(|byte| format!("{byte:02x}"))
+		.collect())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), ProjectError> {
+	let io_error = |error| ProjectError::Io {
+		path: dir.to_path_buf(),
+		error,
+	};
+	for entry in read_dir(dir).map_err(io_error)? {
+		let path = entry.map_err(io_error)?.path();
+		if path.is_dir() {
+			collect_files(&path, files)?;
+		} else if path.extension().is_some_and(|extension| extension == "pv")
+			|| path.file_name().is_some_and(|name| name == MANIFEST_FILE)
+		{
+			files.push(path);
+		}
+	}
+	Ok(())
+}
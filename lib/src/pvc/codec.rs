@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use crate::pvc::{Location, PvcError, PvcErrorKind};
+
+/// Writes the body of a program, collecting the strings it uses into the pool
+#[derive(Default)]
+pub struct Encoder {
+	bytes: Vec<u8>,
+	strings: Vec<String>,
+	string_ids: HashMap<String, usize>,
+}
+
+impl Encoder {
+	pub fn byte(&mut self, byte: u8) {
+		self.bytes.push(byte);
+	}
+
+	pub fn usize(&mut self, mut n: usize) {
+		loop {
+			let byte = (n & 0x7f) as u8;
+			n >>= 7;
+			if n == 0 {
+				self.bytes.push(byte);
+				return;
+			}
+			self.bytes.push(byte | 0x80);
+		}
+	}
+
+	pub fn bool(&mut self, b: bool) {
+		self.byte(b as u8);
+	}
+
+	pub fn string(&mut self, string: &str) {
+		let id = match self.string_ids.get(string) {
+			Some(id) => *id,
+			None => {
+				let id = self.strings.len();
+				self.strings.push(string.to_string());
+				self.string_ids.insert(string.to_string(), id);
+				id
+			}
+		};
+		self.usize(id);
+	}
+
+	/// The string pool followed by the body
+	pub fn finish(self) -> Vec<u8> {
+		let mut pool = Encoder::default();
+		pool.usize(self.strings.len());
+		for string in &self.strings {
+			pool.usize(string.len());
+			pool.bytes.extend(string.as_bytes());
+		}
+		pool.bytes.extend(self.bytes);
+		pool.bytes
+	}
+}
+
+/// How deeply functions and values may nest, so corrupt programs can't exhaust the stack
+const MAX_DEPTH: usize = 256;
+
+/// Reads the body of a program, looking strings up in the pool
+pub struct Decoder<'b> {
+	bytes: &'b [u8],
+	pos: usize,
+	strings: Vec<String>,
+	depth: usize,
+}
+
+impl<'b> Decoder<'b> {
+	/// Reads the string pool at `pos`, which is where the body starts
+	pub fn new(bytes: &'b [u8], pos: usize) -> Result<Self, PvcError> {
+		let mut decoder = Decoder {
+			bytes,
+			pos,
+			strings: Vec::new(),
+			depth: 0,
+		};
+		decoder.strings = decoder.list(|decoder| {
+			let len = decoder.usize()?;
+			let start = decoder.pos;
+			let bytes = decoder.take(len)?;
+			String::from_utf8(bytes.to_vec()).map_err(|_| PvcError {
+				location: Location::Byte(start),
+				kind: PvcErrorKind::InvalidString,
+			})
+		})?;
+		Ok(decoder)
+	}
+
+	pub fn position(&self) -> usize {
+		self.pos
+	}
+
+	/// An error at the current position
+	pub fn error(&self, kind: PvcErrorKind) -> PvcError {
+		PvcError {
+			location: Location::Byte(self.pos),
+			kind,
+		}
+	}
+
+	/// Reads something that can contain itself, failing once it nests too deeply
+	pub fn nested<T>(
+		&mut self,
+		read: impl FnOnce(&mut Self) -> Result<T, PvcError>,
+	) -> Result<T, PvcError> {
+		if self.depth == MAX_DEPTH {
+			return Err(self.error(PvcErrorKind::TooDeep));
+		}
+		self.depth += 1;
+		let result = read(self);
+		self.depth -= 1;
+		result
+	}
+
+	fn take(&mut self, len: usize) -> Result<&'b [u8], PvcError> {
+		let bytes = self
+			.pos
+			.checked_add(len)
+			.and_then(|end| self.bytes.get(self.pos..end))
+			.ok_or_else(|| self.error(PvcErrorKind::Truncated))?;
+		self.pos += len;
+		Ok(bytes)
+	}
+
+	pub fn byte(&mut self) -> Result<u8, PvcError> {
+		Ok(self.take(1)?[0])
+	}
+
+	pub fn usize(&mut self) -> Result<usize, PvcError> {
+		let start = self.pos;
+		let mut n = 0usize;
+		let mut shift = 0;
+		loop {
+			let byte = self.byte()?;
+			let bits = ((byte & 0x7f) as usize)
+				.checked_shl(shift)
+				.filter(|bits| bits >> shift == (byte & 0x7f) as usize)
+				.ok_or(PvcError {
+					location: Location::Byte(start),
+					kind: PvcErrorKind::NumberTooLarge,
+				})?;
+			n |= bits;
+			if byte & 0x80 == 0 {
+				return Ok(n);
+			}
+			shift += 7;
+		}
+	}
+
+	pub fn bool(&mut self) -> Result<bool, PvcError> {
+		match self.byte()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			tag => Err(self.unknown("bool", tag)),
+		}
+	}
+
+	pub fn string(&mut self) -> Result<String, PvcError> {
+		let start = self.pos;
+		let id = self.usize()?;
+		self.strings.get(id).cloned().ok_or(PvcError {
+			location: Location::Byte(start),
+			kind: PvcErrorKind::UnknownString(id),
+		})
+	}
+
+	/// Reads a count followed by that many items
+	pub fn list<T>(
+		&mut self,
+		mut item: impl FnMut(&mut Self) -> Result<T, PvcError>,
+	) -> Result<Vec<T>, PvcError> {
+		let len = self.usize()?;
+		// Every item takes at least a byte, which stops corrupt counts allocating
+		if len > self.bytes.len() - self.pos {
+			return Err(self.error(PvcErrorKind::Truncated));
+		}
+		(0..len).map(|_| item(self)).collect()
+	}
+
+	/// Checks nothing follows the program
+	pub fn finish(self) -> Result<(), PvcError> {
+		if self.pos != self.bytes.len() {
+			return Err(self.error(PvcErrorKind::TrailingBytes(self.bytes.len() - self.pos)));
+		}
+		Ok(())
+	}
+
+	/// An error for the tag just read, which doesn't name any variant of `what`
+	pub fn unknown(&self, what: &'static str, tag: u8) -> PvcError {
+		PvcError {
+			location: Location::Byte(self.pos - 1),
+			kind: PvcErrorKind::UnknownTag { what, tag },
+		}
+	}
+}
+
+pub trait Encode {
+	fn encode(&self, encoder: &mut Encoder);
+}
+
+pub trait Decode: Sized {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError>;
+}
+
+impl Encode for usize {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.usize(*self);
+	}
+}
+
+impl Decode for usize {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		decoder.usize()
+	}
+}
+
+impl Encode for bool {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.bool(*self);
+	}
+}
+
+impl Decode for bool {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		decoder.bool()
+	}
+}
+
+impl Encode for String {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.string(self);
+	}
+}
+
+impl Decode for String {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		decoder.string()
+	}
+}
+
+impl<T: Encode> Encode for [T] {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.usize(self.len());
+		for item in self {
+			item.encode(encoder);
+		}
+	}
+}
+
+impl<T: Encode> Encode for Vec<T> {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.as_slice().encode(encoder);
+	}
+}
+
+impl<T: Decode> Decode for Vec<T> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		decoder.list(T::decode)
+	}
+}
+
+impl<T: Encode> Encode for Option<T> {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			None => encoder.byte(0),
+			Some(value) => {
+				encoder.byte(1);
+				value.encode(encoder);
+			}
+		}
+	}
+}
+
+impl<T: Decode> Decode for Option<T> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		match decoder.byte()? {
+			0 => Ok(None),
+			1 => Ok(Some(T::decode(decoder)?)),
+			tag => Err(decoder.unknown("option", tag)),
+		}
+	}
+}
+
+impl<K: Encode + Ord, V: Encode> Encode for HashMap<K, V> {
+	fn encode(&self, encoder: &mut Encoder) {
+		let mut entries = self.iter().collect::<Vec<_>>();
+		entries.sort_by(|a, b| a.0.cmp(b.0));
+		encoder.usize(entries.len());
+		for (key, value) in entries {
+			key.encode(encoder);
+			value.encode(encoder);
+		}
+	}
+}
+
+impl<K: Decode + Eq + std::hash::Hash, V: Decode> Decode for HashMap<K, V> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(decoder
+			.list(|decoder| Ok((K::decode(decoder)?, V::decode(decoder)?)))?
+			.into_iter()
+			.collect())
+	}
+}
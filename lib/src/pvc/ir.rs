@@ -0,0 +1,343 @@
+use std::borrow::Cow;
+
+use crate::{
+	error::Span,
+	ir::{Block, Callable, Function, Operation, Partial, Statement, Terminal},
+	pvc::{
+		PvcError,
+		codec::{Decode, Decoder, Encode, Encoder},
+	},
+	value::Value,
+	vm::{EvalError, RunResult},
+};
+
+impl Encode for RunResult {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			RunResult::Concrete(value) => {
+				encoder.byte(0);
+				value.encode(encoder);
+			}
+			RunResult::Partial(partial) => {
+				encoder.byte(1);
+				partial.encode(encoder);
+			}
+			RunResult::Residualise => encoder.byte(2),
+			RunResult::Error(error) => {
+				encoder.byte(3);
+				error.message.encode(encoder);
+				error.trace.encode(encoder);
+			}
+		}
+	}
+}
+
+impl Decode for RunResult {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => RunResult::Concrete(Value::decode(decoder)?),
+			1 => RunResult::Partial(Partial::decode(decoder)?),
+			2 => RunResult::Residualise,
+			3 => RunResult::Error(EvalError {
+				message: String::decode(decoder)?,
+				trace: Vec::decode(decoder)?,
+				check: false,
+			}),
+			tag => return Err(decoder.unknown("result", tag)),
+		})
+	}
+}
+
+impl Encode for Span<'static> {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.string(&self.file);
+		encoder.usize(self.index);
+	}
+}
+
+impl Decode for Span<'static> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(Span {
+			file: Cow::Owned(decoder.string()?),
+			index: decoder.usize()?,
+		})
+	}
+}
+
+impl Encode for Partial {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.blocks.encode(encoder);
+		encoder.usize(self.start_block);
+		self.generics.encode(encoder);
+	}
+}
+
+impl Decode for Partial {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		decoder.nested(|decoder| {
+			Ok(Partial {
+				blocks: Vec::decode(decoder)?,
+				start_block: decoder.usize()?,
+				generics: Vec::decode(decoder)?,
+			})
+		})
+	}
+}
+
+impl Encode for Function {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.ir.encode(encoder);
+		encoder.bool(self.exported);
+		self.generics.encode(encoder);
+	}
+}
+
+impl Decode for Function {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(Function {
+			ir: Vec::decode(decoder)?,
+			exported: decoder.bool()?,
+			generics: Vec::decode(decoder)?,
+		})
+	}
+}
+
+impl Encode for Block {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.statements.encode(encoder);
+		self.terminal.encode(encoder);
+	}
+}
+
+impl Decode for Block {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(Block {
+			statements: Vec::decode(decoder)?,
+			terminal: Terminal::decode(decoder)?,
+		})
+	}
+}
+
+impl Encode for Statement {
+	fn encode(&self, encoder: &mut Encoder) {
+		self.store.encode(encoder);
+		self.operation.encode(encoder);
+	}
+}
+
+impl Decode for Statement {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(Statement {
+			store: Option::decode(decoder)?,
+			operation: Operation::decode(decoder)?,
+		})
+	}
+}
+
+impl Encode for Callable {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Callable::Var(var) => {
+				encoder.byte(0);
+				encoder.usize(*var);
+			}
+			Callable::Partial(partial) => {
+				encoder.byte(1);
+				partial.encode(encoder);
+			}
+		}
+	}
+}
+
+impl Decode for Callable {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => Callable::Var(decoder.usize()?),
+			1 => Callable::Partial(Partial::decode(decoder)?),
+			tag => return Err(decoder.unknown("callable", tag)),
+		})
+	}
+}
+
+impl Encode for Operation {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Operation::Call {
+				function,
+				args,
+				span,
+			} => {
+				encoder.byte(0);
+				function.encode(encoder);
+				args.encode(encoder);
+				span.encode(encoder);
+			}
+			Operation::LoadLiteral(value) => {
+				encoder.byte(1);
+				value.encode(encoder);
+			}
+			Operation::LoadLocal { src } => {
+				encoder.byte(2);
+				encoder.usize(*src);
+			}
+			Operation::Phi { block_to_var } => {
+				encoder.byte(3);
+				block_to_var.encode(encoder);
+			}
+			Operation::GuardPhi { block, var } => {
+				encoder.byte(4);
+				encoder.usize(*block);
+				encoder.usize(*var);
+			}
+			Operation::Index(value, index) => {
+				encoder.byte(5);
+				encoder.usize(*value);
+				encoder.usize(*index);
+			}
+			Operation::Access(value, field) => {
+				encoder.byte(6);
+				encoder.usize(*value);
+				encoder.string(field);
+			}
+			Operation::InitializeStruct(typ, fields) => {
+				encoder.byte(7);
+				encoder.usize(*typ);
+				fields.encode(encoder);
+			}
+			Operation::LoadFunction(typ) => {
+				encoder.byte(8);
+				encoder.usize(*typ);
+			}
+			Operation::Is { value, typ } => {
+				encoder.byte(9);
+				encoder.usize(*value);
+				encoder.usize(*typ);
+			}
+			Operation::StructType(fields) => {
+				encoder.byte(10);
+				fields.encode(encoder);
+			}
+			Operation::UnionType(a, b) => {
+				encoder.byte(11);
+				encoder.usize(*a);
+				encoder.usize(*b);
+			}
+			Operation::TupleType(elems) => {
+				encoder.byte(12);
+				elems.encode(encoder);
+			}
+		}
+	}
+}
+
+impl Decode for Operation {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => Operation::Call {
+				function: Callable::decode(decoder)?,
+				args: Vec::decode(decoder)?,
+				span: Span::decode(decoder)?,
+			},
+			1 => Operation::LoadLiteral(Value::decode(decoder)?),
+			2 => Operation::LoadLocal {
+				src: decoder.usize()?,
+			},
+			3 => Operation::Phi {
+				block_to_var: Decode::decode(decoder)?,
+			},
+			4 => Operation::GuardPhi {
+				block: decoder.usize()?,
+				var: decoder.usize()?,
+			},
+			5 => Operation::Index(decoder.usize()?, decoder.usize()?),
+			6 => Operation::Access(decoder.usize()?, decoder.string()?),
+			7 => Operation::InitializeStruct(decoder.usize()?, Decode::decode(decoder)?),
+			8 => Operation::LoadFunction(decoder.usize()?),
+			9 => Operation::Is {
+				value: decoder.usize()?,
+				typ: decoder.usize()?,
+			},
+			10 => Operation::StructType(Decode::decode(decoder)?),
+			11 => Operation::UnionType(decoder.usize()?, decoder.usize()?),
+			12 => Operation::TupleType(Vec::decode(decoder)?),
+			tag => return Err(decoder.unknown("operation", tag)),
+		})
+	}
+}
+
+impl Encode for Terminal {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Terminal::Return(var) => {
+				encoder.byte(0);
+				encoder.usize(*var);
+			}
+			Terminal::Jump(block) => {
+				encoder.byte(1);
+				encoder.usize(*block);
+			}
+			Terminal::CondJump { cond, then, els } => {
+				encoder.byte(2);
+				encoder.usize(*cond);
+				encoder.usize(*then);
+				encoder.usize(*els);
+			}
+			Terminal::Branch { cond, then, els } => {
+				encoder.byte(3);
+				encoder.usize(*cond);
+				then.encode(encoder);
+				els.encode(encoder);
+			}
+			Terminal::Guard {
+				dependency,
+				body,
+				continuation,
+			} => {
+				encoder.byte(4);
+				encoder.usize(*dependency);
+				encoder.usize(*body);
+				encoder.usize(*continuation);
+			}
+			Terminal::TailCall {
+				function,
+				args,
+				span,
+			} => {
+				encoder.byte(5);
+				function.encode(encoder);
+				args.encode(encoder);
+				span.encode(encoder);
+			}
+		}
+	}
+}
+
+impl Decode for Terminal {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => Terminal::Return(decoder.usize()?),
+			1 => Terminal::Jump(decoder.usize()?),
+			2 => Terminal::CondJump {
+				cond: decoder.usize()?,
+				then: decoder.usize()?,
+				els: decoder.usize()?,
+			},
+			3 => Terminal::Branch {
+				cond: decoder.usize()?,
+				then: RunResult::decode(decoder)?,
+				els: RunResult::decode(decoder)?,
+			},
+			4 => Terminal::Guard {
+				dependency: decoder.usize()?,
+				body: decoder.usize()?,
+				continuation: decoder.usize()?,
+			},
+			5 => Terminal::TailCall {
+				function: Callable::decode(decoder)?,
+				args: Vec::decode(decoder)?,
+				span: Span::decode(decoder)?,
+			},
+			tag => return Err(decoder.unknown("terminal", tag)),
+		})
+	}
+}
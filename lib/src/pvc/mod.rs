@@ -0,0 +1,151 @@
+//! The `.pvc` format compiled programs are stored in:
+//!
+//! - the magic bytes `PVC\0` and the format version, as a little-endian `u32`
+//! - a pool of the strings the program uses, which are referred to by index
+//! - the type table
+//! - the program itself, as the result of partially evaluating the entry point
+//!
+//! Numbers are LEB128 varints, and maps are written sorted by key so the same
+//! program always gives the same bytes.
+
+mod codec;
+mod ir;
+mod types;
+mod values;
+mod verify;
+
+use std::fmt::Display;
+
+use crate::{ir::IrErrorKind, typ::Type, vm::RunResult};
+use codec::{Decode, Decoder, Encode, Encoder};
+
+pub const MAGIC: &[u8; 4] = b"PVC\0";
+/// Raised whenever the format changes, as programs are only read by the version of
+/// Preval that wrote them
+pub const VERSION: u32 = 2;
+
+/// Why a program couldn't be read, and where in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PvcError {
+	pub location: Location,
+	pub kind: PvcErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Location {
+	/// Offset into the file
+	Byte(usize),
+	/// An entry of the type table
+	Type(usize),
+	/// A block of the program, or of the function whose type is given
+	Block {
+		function: Option<usize>,
+		block: usize,
+	},
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PvcErrorKind {
+	NotAProgram,
+	UnsupportedVersion(u32),
+	Truncated,
+	UnknownTag {
+		what: &'static str,
+		tag: u8,
+	},
+	NumberTooLarge,
+	InvalidString,
+	UnknownString(usize),
+	UnknownName {
+		what: &'static str,
+		name: String,
+	},
+	TrailingBytes(usize),
+	UnknownType(usize),
+	TooDeep,
+	/// Blocks that couldn't be run
+	Ir(IrErrorKind),
+}
+
+impl Display for Location {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Location::Byte(offset) => write!(f, "at byte {offset}"),
+			Location::Type(typ) => write!(f, "in type {typ}"),
+			Location::Block {
+				function: None,
+				block,
+			} => write!(f, "in block {block} of the program"),
+			Location::Block {
+				function: Some(typ),
+				block,
+			} => write!(f, "in block {block} of the function with type {typ}"),
+		}
+	}
+}
+
+impl Display for PvcErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PvcErrorKind::NotAProgram => write!(f, "not a compiled Preval program"),
+			PvcErrorKind::UnsupportedVersion(found) => write!(
+				f,
+				"compiled with format version {found}, but this version of Preval reads version {VERSION}; compile it again"
+			),
+			PvcErrorKind::Truncated => write!(f, "the program ends unexpectedly"),
+			PvcErrorKind::UnknownTag { what, tag } => write!(f, "{tag} isn't a kind of {what}"),
+			PvcErrorKind::NumberTooLarge => write!(f, "a number is too large"),
+			PvcErrorKind::InvalidString => write!(f, "a string isn't valid UTF-8"),
+			PvcErrorKind::UnknownString(id) => write!(f, "string {id} isn't in the pool"),
+			PvcErrorKind::UnknownName { what, name } => write!(f, "unknown {what} `{name}`"),
+			PvcErrorKind::TrailingBytes(count) => write!(f, "{count} bytes follow the program"),
+			PvcErrorKind::UnknownType(typ) => write!(f, "type {typ} isn't in the type table"),
+			PvcErrorKind::TooDeep => write!(f, "the program nests too deeply"),
+			PvcErrorKind::Ir(kind) => write!(f, "{kind}"),
+		}
+	}
+}
+
+impl Display for PvcError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} ({})", self.kind, self.location)
+	}
+}
+
+/// Encodes a compiled program and the types it uses
+pub fn write(program: &RunResult, types: &[Type]) -> Vec<u8> {
+	let mut encoder = Encoder::default();
+	types.encode(&mut encoder);
+	program.encode(&mut encoder);
+
+	let mut bytes = MAGIC.to_vec();
+	bytes.extend(VERSION.to_le_bytes());
+	bytes.extend(encoder.finish());
+	bytes
+}
+
+/// Decodes a program written by [`write`], checking that everything it refers to exists
+pub fn read(bytes: &[u8]) -> Result<(RunResult, Vec<Type>), PvcError> {
+	let header_error = |kind| PvcError {
+		location: Location::Byte(0),
+		kind,
+	};
+	let Some(rest) = bytes.strip_prefix(MAGIC) else {
+		return Err(header_error(PvcErrorKind::NotAProgram));
+	};
+	let Some(version) = rest.first_chunk::<4>() else {
+		return Err(header_error(PvcErrorKind::Truncated));
+	};
+	let version = u32::from_le_bytes(*version);
+	if version != VERSION {
+		return Err(header_error(PvcErrorKind::UnsupportedVersion(version)));
+	}
+
+	let mut decoder = Decoder::new(bytes, MAGIC.len() + 4)?;
+	let types = Vec::<Type>::decode(&mut decoder)?;
+	let program = RunResult::decode(&mut decoder)?;
+	decoder.finish()?;
+
+	verify::verify(&program, &types)?;
+	Ok((program, types))
+}
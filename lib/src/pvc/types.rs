@@ -0,0 +1,158 @@
+use crate::{
+	pvc::{
+		PvcError,
+		codec::{Decode, Decoder, Encode, Encoder},
+	},
+	typ::{ConcreteType, Implementation, IntegerSize, Type},
+	value::{builtin::Builtin, native::NativeFunction, reflection::Reflection},
+};
+
+impl Encode for Type {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Type::Concrete(typ) => {
+				encoder.byte(0);
+				typ.encode(encoder);
+			}
+			Type::Union(a, b) => {
+				encoder.byte(1);
+				encoder.usize(*a);
+				encoder.usize(*b);
+			}
+			Type::EarlyReturn => encoder.byte(2),
+			Type::Placeholder(id) => {
+				encoder.byte(3);
+				encoder.usize(*id);
+			}
+			Type::UnificationVar(id) => {
+				encoder.byte(4);
+				encoder.usize(*id);
+			}
+		}
+	}
+}
+
+impl Decode for Type {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => Type::Concrete(ConcreteType::decode(decoder)?),
+			1 => Type::Union(decoder.usize()?, decoder.usize()?),
+			2 => Type::EarlyReturn,
+			3 => Type::Placeholder(decoder.usize()?),
+			4 => Type::UnificationVar(decoder.usize()?),
+			tag => return Err(decoder.unknown("type", tag)),
+		})
+	}
+}
+
+impl Encode for ConcreteType {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			ConcreteType::Integer { size, signed } => {
+				encoder.byte(0);
+				// Sizes are written one higher, leaving 0 for `isize` and `usize`
+				encoder.usize(match size {
+					IntegerSize::Size => 0,
+					IntegerSize::Number(bits) => bits + 1,
+				});
+				encoder.bool(*signed);
+			}
+			ConcreteType::Float { size } => {
+				encoder.byte(1);
+				encoder.usize(*size);
+			}
+			ConcreteType::Bool => encoder.byte(2),
+			ConcreteType::String => encoder.byte(3),
+			ConcreteType::Struct(fields) => {
+				encoder.byte(4);
+				fields.encode(encoder);
+			}
+			ConcreteType::Function(args, ret, implementation) => {
+				encoder.byte(5);
+				args.encode(encoder);
+				encoder.usize(*ret);
+				implementation.encode(encoder);
+			}
+			ConcreteType::Tuple(elems) => {
+				encoder.byte(6);
+				elems.encode(encoder);
+			}
+			ConcreteType::List(elem) => {
+				encoder.byte(7);
+				encoder.usize(*elem);
+			}
+			ConcreteType::IO => encoder.byte(8),
+			ConcreteType::Type => encoder.byte(9),
+			ConcreteType::Module(path) => {
+				encoder.byte(10);
+				path.encode(encoder);
+			}
+		}
+	}
+}
+
+impl Decode for ConcreteType {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => ConcreteType::Integer {
+				size: match decoder.usize()? {
+					0 => IntegerSize::Size,
+					bits => IntegerSize::Number(bits - 1),
+				},
+				signed: decoder.bool()?,
+			},
+			1 => ConcreteType::Float {
+				size: decoder.usize()?,
+			},
+			2 => ConcreteType::Bool,
+			3 => ConcreteType::String,
+			4 => ConcreteType::Struct(Decode::decode(decoder)?),
+			5 => ConcreteType::Function(
+				Vec::decode(decoder)?,
+				decoder.usize()?,
+				Implementation::decode(decoder)?,
+			),
+			6 => ConcreteType::Tuple(Vec::decode(decoder)?),
+			7 => ConcreteType::List(decoder.usize()?),
+			8 => ConcreteType::IO,
+			9 => ConcreteType::Type,
+			10 => ConcreteType::Module(Vec::decode(decoder)?),
+			tag => return Err(decoder.unknown("concrete type", tag)),
+		})
+	}
+}
+
+impl Encode for Implementation {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Implementation::Native(native) => {
+				encoder.byte(0);
+				native.encode(encoder);
+			}
+			Implementation::Builtin(builtin) => {
+				encoder.byte(1);
+				builtin.encode(encoder);
+			}
+			Implementation::Reflect(reflection) => {
+				encoder.byte(2);
+				reflection.encode(encoder);
+			}
+			Implementation::Normal(blocks) => {
+				encoder.byte(3);
+				blocks.encode(encoder);
+			}
+		}
+	}
+}
+
+impl Decode for Implementation {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		Ok(match decoder.byte()? {
+			0 => Implementation::Native(NativeFunction::decode(decoder)?),
+			1 => Implementation::Builtin(Builtin::decode(decoder)?),
+			2 => Implementation::Reflect(Reflection::decode(decoder)?),
+			3 => Implementation::Normal(Vec::decode(decoder)?),
+			tag => return Err(decoder.unknown("implementation", tag)),
+		})
+	}
+}
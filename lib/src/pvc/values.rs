@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use crate::{
+	ir::{Function, Partial},
+	pvc::{
+		Location, PvcError, PvcErrorKind,
+		codec::{Decode, Decoder, Encode, Encoder},
+	},
+	value::{
+		Value,
+		builtin::Builtin,
+		list::List,
+		native::NativeFunction,
+		primitive::{EmptyTuple, IO, TypeValue},
+		reflection::{Reflect, Reflection},
+		runtime_type::{Poison, TypeDeserializer},
+		structure::Struct,
+	},
+};
+
+/// Reads the data of a value, which the kind of value says how to decode
+fn data<T: 'static>(value: &Value) -> &T {
+	value
+		.data
+		.as_any()
+		.downcast_ref()
+		.expect("value doesn't hold the data its kind says")
+}
+
+impl Encode for Value {
+	fn encode(&self, encoder: &mut Encoder) {
+		let tag = match &self.deserializer {
+			TypeDeserializer::USize => 0,
+			TypeDeserializer::Tuple(_) => 1,
+			TypeDeserializer::IO => 2,
+			TypeDeserializer::Bool => 3,
+			TypeDeserializer::String => 4,
+			TypeDeserializer::Struct => 5,
+			TypeDeserializer::List => 6,
+			TypeDeserializer::Function => 7,
+			TypeDeserializer::Partial => 8,
+			TypeDeserializer::NativeFunction => 9,
+			TypeDeserializer::Builtin => 10,
+			TypeDeserializer::Reflection => 11,
+			TypeDeserializer::Type => 12,
+			TypeDeserializer::Poison => 13,
+		};
+		encoder.byte(tag);
+		encoder.usize(self.typ);
+
+		match &self.deserializer {
+			TypeDeserializer::USize => encoder.usize(*data::<usize>(self)),
+			TypeDeserializer::Bool => encoder.bool(*data::<bool>(self)),
+			TypeDeserializer::String => encoder.string(data::<String>(self)),
+			TypeDeserializer::Struct => data::<Struct>(self).fields.encode(encoder),
+			TypeDeserializer::List => data::<List>(self).0.encode(encoder),
+			TypeDeserializer::Function => data::<Function>(self).encode(encoder),
+			TypeDeserializer::Partial => data::<Partial>(self).encode(encoder),
+			TypeDeserializer::NativeFunction => data::<NativeFunction>(self).encode(encoder),
+			TypeDeserializer::Builtin => data::<Builtin>(self).encode(encoder),
+			TypeDeserializer::Reflection => data::<Reflection>(self).encode(encoder),
+			TypeDeserializer::Type => encoder.usize(data::<TypeValue>(self).0),
+			TypeDeserializer::Tuple(_) | TypeDeserializer::IO | TypeDeserializer::Poison => {}
+		}
+	}
+}
+
+impl Decode for Value {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		decoder.nested(|decoder| {
+			let tag = decoder.byte()?;
+			let typ = decoder.usize()?;
+			Ok(match tag {
+				0 => Value::new(decoder.usize()?, typ),
+				1 => Value::new(EmptyTuple, typ),
+				2 => Value::new(IO, typ),
+				3 => Value::new(decoder.bool()?, typ),
+				4 => Value::new(decoder.string()?, typ),
+				5 => Value::new(
+					Struct {
+						fields: Decode::decode(decoder)?,
+					},
+					typ,
+				),
+				6 => Value::new(List(Vec::decode(decoder)?), typ),
+				7 => Value::new(Function::decode(decoder)?, typ),
+				8 => Value::new(Partial::decode(decoder)?, typ),
+				9 => Value::new(NativeFunction::decode(decoder)?, typ),
+				10 => Value::new(Builtin::decode(decoder)?, typ),
+				11 => Value::new(Reflection::decode(decoder)?, typ),
+				12 => Value::new(TypeValue(decoder.usize()?), typ),
+				13 => Value::new(Poison, typ),
+				tag => return Err(decoder.unknown("value", tag)),
+			})
+		})
+	}
+}
+
+/// Native functions keep the library they were compiled against, found from the
+/// project's search paths
+impl Encode for NativeFunction {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.string(&self.lib_name);
+		encoder.string(&self.func_name);
+		let path = self.library_path();
+		let path = path.canonicalize().unwrap_or(path);
+		encoder.string(&path.to_string_lossy());
+	}
+}
+
+impl Decode for NativeFunction {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		let mut function = NativeFunction::new(decoder.string()?, decoder.string()?);
+		function.path = Some(PathBuf::from(decoder.string()?));
+		Ok(function)
+	}
+}
+
+/// Builtins are written by name, followed by the type they make if they make one
+impl Encode for Builtin {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.string(self.name());
+		encoder.usize(match self {
+			Builtin::ListNew(typ) | Builtin::Args(typ) => *typ,
+			_ => 0,
+		});
+	}
+}
+
+impl Decode for Builtin {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		let start = decoder.position();
+		let name = decoder.string()?;
+		let typ = decoder.usize()?;
+		match Builtin::named(&name) {
+			Some(builtin) => Ok(builtin.returning(typ)),
+			None => Err(PvcError {
+				location: Location::Byte(start),
+				kind: PvcErrorKind::UnknownName {
+					what: "builtin",
+					name,
+				},
+			}),
+		}
+	}
+}
+
+impl Encode for Reflection {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.string(self.reflect.name());
+		encoder.string(&self.type_name);
+		encoder.usize(self.fields.len());
+		for (name, typ) in &self.fields {
+			encoder.string(name);
+			encoder.string(typ);
+		}
+		self.members.encode(encoder);
+	}
+}
+
+impl Decode for Reflection {
+	fn decode(decoder: &mut Decoder) -> Result<Self, PvcError> {
+		let start = decoder.position();
+		let name = decoder.string()?;
+		let Some(reflect) = Reflect::ALL.iter().find(|reflect| reflect.name() == name) else {
+			return Err(PvcError {
+				location: Location::Byte(start),
+				kind: PvcErrorKind::UnknownName {
+					what: "reflection",
+					name,
+				},
+			});
+		};
+		Ok(Reflection {
+			reflect: *reflect,
+			type_name: decoder.string()?,
+			fields: decoder.list(|decoder| Ok((decoder.string()?, decoder.string()?)))?,
+			members: Vec::decode(decoder)?,
+		})
+	}
+}
@@ -0,0 +1,56 @@
+use crate::{
+	ir::{self, IrError},
+	pvc::{Location, PvcError, PvcErrorKind},
+	typ::{ConcreteType, Implementation, Type},
+	value::builtin::Builtin,
+	vm::RunResult,
+};
+
+/// Checks that a decoded program only refers to types, blocks and variables that
+/// exist, so running it can't fail on a missing one
+pub fn verify(program: &RunResult, types: &[Type]) -> Result<(), PvcError> {
+	for (id, typ) in types.iter().enumerate() {
+		typ_ids(typ).try_for_each(|typ| match typ < types.len() {
+			true => Ok(()),
+			false => Err(PvcError {
+				location: Location::Type(id),
+				kind: PvcErrorKind::UnknownType(typ),
+			}),
+		})?;
+		if let Type::Concrete(ConcreteType::Function(args, _, Implementation::Normal(blocks))) = typ
+		{
+			ir::verify(blocks, 0, args.len(), types).map_err(|error| ir_error(error, Some(id)))?;
+		}
+	}
+
+	// The entry point is given `compile_io` and `io`
+	ir::verify_result(program, 2, types).map_err(|error| ir_error(error, None))
+}
+
+/// Types a type table entry refers to
+fn typ_ids(typ: &Type) -> Box<dyn Iterator<Item = usize> + '_> {
+	match typ {
+		Type::Union(a, b) => Box::new([*a, *b].into_iter()),
+		Type::Concrete(ConcreteType::Struct(fields)) => Box::new(fields.values().copied()),
+		Type::Concrete(ConcreteType::Tuple(elems)) => Box::new(elems.iter().copied()),
+		Type::Concrete(ConcreteType::List(elem)) => Box::new([*elem].into_iter()),
+		Type::Concrete(ConcreteType::Function(args, ret, implementation)) => {
+			let made = match implementation {
+				Implementation::Builtin(Builtin::ListNew(typ) | Builtin::Args(typ)) => Some(*typ),
+				_ => None,
+			};
+			Box::new(args.iter().copied().chain([*ret]).chain(made))
+		}
+		_ => Box::new([].into_iter()),
+	}
+}
+
+fn ir_error(error: IrError, function: Option<usize>) -> PvcError {
+	PvcError {
+		location: Location::Block {
+			function,
+			block: error.block,
+		},
+		kind: PvcErrorKind::Ir(error.kind),
+	}
+}
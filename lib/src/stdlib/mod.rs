@@ -0,0 +1,115 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+	error::Span,
+	parser::{
+		module::{Globs, declaration_pass},
+		typ::InfoTypeExpr,
+	},
+	tokeniser::tokenise,
+	typ::{Template, TypeExpr},
+};
+
+/// Declarations put straight into the global namespace
+const PRELUDE: (&str, &str) = ("<std>/prelude.pv", include_str!("prelude.pv"));
+
+/// Sources of the `std` bundled with the compiler, as `(file, module, contents)`
+pub const SOURCES: &[(&str, &str, &str)] = &[
+	("<std>/io.pv", "io", include_str!("io.pv")),
+	("<std>/strings.pv", "strings", include_str!("strings.pv")),
+	("<std>/math.pv", "math", include_str!("math.pv")),
+	(
+		"<std>/collections.pv",
+		"collections",
+		include_str!("collections.pv"),
+	),
+	("<std>/option.pv", "option", include_str!("option.pv")),
+	("<std>/result.pv", "result", include_str!("result.pv")),
+	("<std>/env.pv", "env", include_str!("env.pv")),
+	("<std>/fs.pv", "fs", include_str!("fs.pv")),
+];
+
+/// The contents of a bundled source, by the file name its spans use
+pub fn source(file: &str) -> Option<&'static str> {
+	if file == PRELUDE.0 {
+		return Some(PRELUDE.1);
+	}
+	SOURCES
+		.iter()
+		.find(|(name, _, _)| *name == file)
+		.map(|(_, _, contents)| *contents)
+}
+
+/// Declares `std` and the prelude in `namespace`
+pub fn mount<'a>(namespace: &mut HashMap<String, Template<'a>>, globs: &mut Globs<'a>) {
+	declare(PRELUDE.0, PRELUDE.1, namespace, globs, &[]);
+
+	let mut std = HashMap::new();
+
+	for (file, name, contents) in SOURCES {
+		let path = vec!["std".to_string(), name.to_string()];
+		let mut members = HashMap::new();
+
+		declare(file, contents, &mut members, globs, &path);
+		if *name == "collections" {
+			members.insert("List".to_string(), list_template());
+		}
+
+		std.insert(name.to_string(), module_template(members, path));
+	}
+
+	namespace.insert(
+		"std".to_string(),
+		module_template(std, vec!["std".to_string()]),
+	);
+}
+
+fn declare<'a>(
+	file: &'static str,
+	contents: &'static str,
+	module: &mut HashMap<String, Template<'a>>,
+	globs: &mut Globs<'a>,
+	path: &[String],
+) {
+	let tokens = tokenise(contents, 0, Cow::Borrowed(file)).expect("bundled std should tokenise");
+	declaration_pass(&tokens, module, globs, path).expect("bundled std should parse");
+}
+
+fn span<'a>() -> Span<'a> {
+	Span {
+		file: Cow::Borrowed(file!()),
+		index: 0,
+	}
+}
+
+fn module_template<'a>(members: HashMap<String, Template<'a>>, path: Vec<String>) -> Template<'a> {
+	Template {
+		parameters: 0,
+		expr: InfoTypeExpr {
+			expr: TypeExpr::Module(members, path),
+			idx: span(),
+		},
+		members: HashMap::new(),
+		bounds: Vec::new(),
+		defaults: HashMap::new(),
+		public: true,
+	}
+}
+
+/// `List<T>`, which can't be written in Preval
+fn list_template<'a>() -> Template<'a> {
+	Template {
+		parameters: 1,
+		expr: InfoTypeExpr {
+			expr: TypeExpr::List(Box::new(InfoTypeExpr {
+				expr: TypeExpr::Parameter(0),
+				idx: span(),
+			})),
+			idx: span(),
+		},
+		members: HashMap::new(),
+		bounds: Vec::new(),
+		defaults: HashMap::new(),
+		public: true,
+	}
+}
@@ -1,4 +1,7 @@
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+	borrow::Cow,
+	fmt::{Debug, Display},
+};
 
 use crate::{
 	error::Span,
@@ -16,12 +19,16 @@ pub enum Keyword {
 	Bool(bool),
 	Struct,
 	Dylib,
+	Builtin,
 	Guard,
 	Is,
 	Use,
 	Type,
 	Capability,
 	Impl,
+	Pub,
+	Mod,
+	As,
 }
 
 impl TryFrom<&str> for Keyword {
@@ -38,12 +45,16 @@ impl TryFrom<&str> for Keyword {
 			"false" => Ok(Keyword::Bool(false)),
 			"struct" => Ok(Keyword::Struct),
 			"dylib" => Ok(Keyword::Dylib),
+			"builtin" => Ok(Keyword::Builtin),
 			"guard" => Ok(Keyword::Guard),
 			"is" => Ok(Keyword::Is),
 			"use" => Ok(Keyword::Use),
 			"type" => Ok(Keyword::Type),
 			"capability" => Ok(Keyword::Capability),
 			"impl" => Ok(Keyword::Impl),
+			"pub" => Ok(Keyword::Pub),
+			"mod" => Ok(Keyword::Mod),
+			"as" => Ok(Keyword::As),
 			_ => Err(()),
 		}
 	}
@@ -66,6 +77,8 @@ pub enum Token<'a> {
 	LessThan,
 	GreaterThan,
 	DoubleColon,
+	/// `*`, only used for glob imports
+	Star,
 	InfixOp(InfixOp),
 }
 
@@ -121,6 +134,17 @@ pub enum TokeniseError {
 	ExpectedNumber(String),
 }
 
+impl Display for TokeniseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			TokeniseError::UnclosedParens => write!(f, "unclosed bracket"),
+			TokeniseError::UnclosedQuotes => write!(f, "unclosed string"),
+			TokeniseError::ExpectedToken(c) => write!(f, "unexpected character `{c}`"),
+			TokeniseError::ExpectedNumber(number) => write!(f, "`{number}` is not a number"),
+		}
+	}
+}
+
 #[derive(Debug)]
 pub struct EOF {}
 
@@ -188,6 +212,16 @@ pub fn tokenise<'a>(
 				});
 				i += 1;
 			}
+			Some('*') => {
+				out.push(InfoToken {
+					token: Token::Star,
+					span: Span {
+						index: offset + i,
+						file: file.clone(),
+					},
+				});
+				i += 1;
+			}
 			Some('=') => {
 				out.push(InfoToken {
 					token: Token::Assignment,
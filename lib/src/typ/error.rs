@@ -1,17 +1,83 @@
-use crate::{error::Span, typ::Type};
+use std::fmt::Display;
+
+use crate::error::Span;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TypeError {
 	UnknownVariable(String),
 	UnknownField(String),
 	UnknownType(String),
-	IncompatibleTypes { expected: Type, got: Type },
-	NotAStruct(Type),
-	NotAFunction(Type),
+	IncompatibleTypes { expected: String, got: String },
+	NotAStruct(String),
+	NotAFunction(String),
 	IncorrectArgumentCount { expected: usize, got: usize },
 	IncorrectFieldCount { expected: usize, got: usize },
+	MissingField(String),
+	Private(String),
 	DuplicateName(String),
 	NotAParent,
+	UnknownMethod { typ: String, method: String },
+	MissingCapability { typ: String, capability: String },
+	NotACapability(String),
+	CapabilityAsType(String),
+	Uninferred(String),
+	NotATypeFunction(String),
+	Native(String),
+	CompileTimeOnly,
+	UntypedIndex,
+}
+
+impl Display for TypeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			TypeError::UnknownVariable(name) => write!(f, "unknown variable `{name}`"),
+			TypeError::UnknownField(name) => write!(f, "unknown field `{name}`"),
+			TypeError::UnknownType(name) => write!(f, "unknown type `{name}`"),
+			TypeError::IncompatibleTypes { expected, got } => {
+				write!(f, "expected `{expected}`, got `{got}`")
+			}
+			TypeError::NotAStruct(typ) => write!(f, "`{typ}` is not a struct"),
+			TypeError::NotAFunction(typ) => write!(f, "`{typ}` is not a function"),
+			TypeError::IncorrectArgumentCount { expected, got } => {
+				write!(f, "expected {expected} arguments, got {got}")
+			}
+			TypeError::Private(name) => write!(f, "`{name}` is private"),
+			TypeError::MissingField(name) => {
+				write!(f, "field `{name}` isn't given and has no default")
+			}
+			TypeError::IncorrectFieldCount { expected, got } => {
+				write!(f, "expected {expected} fields, got {got}")
+			}
+			TypeError::DuplicateName(name) => write!(f, "`{name}` is defined more than once"),
+			TypeError::NotAParent => write!(f, "type has no members"),
+			TypeError::UnknownMethod { typ, method } => {
+				write!(f, "no method `{method}` on `{typ}`")
+			}
+			TypeError::MissingCapability { typ, capability } => {
+				write!(f, "`{typ}` does not implement `{capability}`")
+			}
+			TypeError::NotACapability(name) => write!(f, "`{name}` is not a capability"),
+			TypeError::NotATypeFunction(name) => {
+				write!(f, "`{name}` doesn't produce a type at compile time")
+			}
+			TypeError::CapabilityAsType(name) => {
+				write!(f, "`{name}` is a capability, not a type")
+			}
+			TypeError::Uninferred(name) => write!(
+				f,
+				"the type arguments of `{name}` can't be inferred, so they need giving as `{name}::<T>`"
+			),
+			TypeError::Native(message) => write!(f, "{message}"),
+			TypeError::UntypedIndex => write!(
+				f,
+				"indexing has no static type, so methods can't be called on what it gives"
+			),
+			TypeError::CompileTimeOnly => write!(
+				f,
+				"types and `compile_io` only exist at compile time, but this needs one at run time"
+			),
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
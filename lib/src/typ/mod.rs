@@ -6,9 +6,22 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
 	error::Span,
-	ir::{Block, IRContext, Terminal, to_ir},
-	parser::{expression::InfoExpr, typ::InfoTypeExpr},
-	value::native::NativeFunction,
+	ir::{Block, IRContext, Terminal, debug_verify_blocks, to_ir},
+	parser::{
+		expression::{Expr, InfoExpr},
+		module::{Globs, parse_signatures},
+		typ::InfoTypeExpr,
+	},
+	stdlib,
+	tokeniser::{Literal, get_line_and_column, tokenise},
+	value::{
+		Value,
+		builtin::Builtin,
+		native::NativeFunction,
+		primitive::TypeValue,
+		reflection::{Reflect, Reflection},
+	},
+	vm::{RunResult, evaluate},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Serialize, Deserialize)]
@@ -26,7 +39,9 @@ pub enum ConcreteType {
 	Struct(HashMap<String, usize>),
 	Function(Vec<usize>, usize, Implementation),
 	Tuple(Vec<usize>),
+	List(usize),
 	IO,
+	Type,
 	Module(Vec<String>),
 }
 
@@ -51,6 +66,7 @@ pub enum TypeExpr<'a> {
 	Parameter(usize),
 	Struct(HashMap<String, InfoTypeExpr<'a>>),
 	Tuple(Vec<InfoTypeExpr<'a>>),
+	List(Box<InfoTypeExpr<'a>>),
 
 	Integer {
 		size: IntegerSize,
@@ -62,6 +78,9 @@ pub enum TypeExpr<'a> {
 	Bool,
 	String,
 	IO,
+	Type,
+	/// A function returning a type, run at compile time like `Pair(usize)`
+	Call(Box<InfoTypeExpr<'a>>, Vec<InfoTypeExpr<'a>>),
 
 	Function(
 		Vec<InfoTypeExpr<'a>>,
@@ -71,17 +90,23 @@ pub enum TypeExpr<'a> {
 	),
 
 	Module(HashMap<String, Template<'a>>, Vec<String>),
+	/// Functions a type needs to have as methods to satisfy a generic bound
+	Capability(String, HashMap<String, InfoTypeExpr<'a>>),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum Implementation {
 	Native(NativeFunction),
+	Builtin(Builtin),
+	Reflect(Reflection),
 	Normal(Vec<Block>),
 }
 
 #[derive(Debug, Clone)]
 pub enum GenericImplementation<'a> {
 	Native(NativeFunction),
+	Builtin(Builtin),
+	Reflect(Reflect),
 	Normal(Box<InfoExpr<'a>>),
 }
 
@@ -89,14 +114,39 @@ pub enum GenericImplementation<'a> {
 pub struct Template<'a> {
 	pub parameters: usize,
 	pub expr: InfoTypeExpr<'a>,
+	/// Items declared in `impl` blocks, reachable as `Type::member` and as methods
+	pub members: HashMap<String, Template<'a>>,
+	/// Capabilities required of each parameter, checked when instantiated
+	pub bounds: Vec<(usize, InfoTypeExpr<'a>)>,
+	/// Values of struct fields that can be left out when initialising
+	pub defaults: HashMap<String, InfoExpr<'a>>,
+	/// Whether the item can be named from outside the module that declares it
+	pub public: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct Instantiator<'a> {
 	pub global_namespace: HashMap<String, Template<'a>>,
+	pub globs: Globs<'a>,
 	pub types: Vec<Type>,
 	next_unification_var: usize,
 	subtype_members: HashMap<usize, HashMap<String, Template<'a>>>,
+	type_modules: HashMap<usize, usize>,
+	type_names: HashMap<usize, String>,
+	/// Declarations of structs with default field values, and their type arguments
+	struct_defaults: HashMap<usize, (Template<'a>, Vec<usize>)>,
+	instances: HashMap<(Option<usize>, String, Vec<usize>), usize>,
+	/// Modules whose declarations are being instantiated, innermost last
+	scopes: Vec<Option<usize>>,
+	/// Set while resolving the path of a glob import, which can't itself go through globs
+	expanding_globs: bool,
+}
+
+struct Callee<'a> {
+	name: String,
+	params: Vec<Option<InfoTypeExpr<'a>>>,
+	namespace: Option<usize>,
+	template: Template<'a>,
 }
 
 macro_rules! type_ids {
@@ -137,6 +187,7 @@ type_ids! {
 	empty_tuple => TypeExpr::Tuple(vec![]),
 	String => TypeExpr::String,
 	IO => TypeExpr::IO,
+	Type => TypeExpr::Type,
 }
 
 impl<'a> Instantiator<'a> {
@@ -155,15 +206,33 @@ impl<'a> Instantiator<'a> {
 							index: 0,
 						},
 					},
+					members: HashMap::new(),
+					bounds: Vec::new(),
+					defaults: HashMap::new(),
+					public: true,
 				},
 			);
 		}
 
+		for reflect in Reflect::ALL {
+			global_namespace.insert(reflect.name().to_string(), reflect_template(*reflect));
+		}
+
+		let mut globs = HashMap::new();
+		stdlib::mount(&mut global_namespace, &mut globs);
+
 		let mut this = Instantiator {
 			global_namespace,
+			globs,
 			types: Vec::new(),
 			next_unification_var: 0,
 			subtype_members: HashMap::new(),
+			type_modules: HashMap::new(),
+			type_names: HashMap::new(),
+			struct_defaults: HashMap::new(),
+			scopes: Vec::new(),
+			expanding_globs: false,
+			instances: HashMap::new(),
 		};
 
 		for name in TYPE_NAMES {
@@ -183,36 +252,169 @@ impl<'a> Instantiator<'a> {
 		this
 	}
 
-	fn instantiate_name(
+	/// Finds the template for `n`, returning it along with the namespace it was found in.
+	/// Bare names are looked up in the module being instantiated, then globally.
+	fn lookup(
 		&mut self,
 		n: &String,
-		params: &Vec<Option<InfoTypeExpr<'a>>>,
 		span: &Span<'a>,
-		generics: &[usize],
 		namespace: Option<usize>,
-	) -> Result<usize, InfoTypeError<'a>> {
-		let namespace = if let Some(namespace) = namespace {
-			match self.subtype_members.get(&namespace) {
-				Some(namespace) => namespace,
-				None => {
+	) -> Result<(Template<'a>, Option<usize>), InfoTypeError<'a>> {
+		let candidates = match namespace {
+			Some(namespace) => {
+				if !self.subtype_members.contains_key(&namespace) {
 					return Err(InfoTypeError {
 						span: span.clone(),
 						error: TypeError::NotAParent,
 					});
 				}
+				vec![Some(namespace)]
 			}
-		} else {
-			&self.global_namespace
+			None => match self.current_module() {
+				Some(module) => vec![Some(module), None],
+				None => vec![None],
+			},
 		};
-		let template = match namespace.get(n) {
-			Some(temp) => temp.clone(),
-			None => {
-				return Err(InfoTypeError {
-					span: span.clone(),
-					error: TypeError::UnknownType(n.clone()),
-				});
+
+		for candidate in candidates {
+			let members = match candidate {
+				Some(candidate) => &self.subtype_members[&candidate],
+				None => &self.global_namespace,
+			};
+			if let Some(template) = members.get(n) {
+				if namespace.is_some() && !template.public && !self.can_see_private(candidate) {
+					return Err(InfoTypeError {
+						span: span.clone(),
+						error: TypeError::Private(n.clone()),
+					});
+				}
+				return Ok((template.clone(), candidate));
+			}
+
+			if let Some(found) = self.lookup_glob(n, span, candidate)? {
+				return Ok(found);
 			}
+		}
+
+		Err(InfoTypeError {
+			span: span.clone(),
+			error: TypeError::UnknownType(n.clone()),
+		})
+	}
+
+	/// Looks for a public `n` in the modules glob imported into `namespace`
+	fn lookup_glob(
+		&mut self,
+		n: &String,
+		span: &Span<'a>,
+		namespace: Option<usize>,
+	) -> Result<Option<(Template<'a>, Option<usize>)>, InfoTypeError<'a>> {
+		if self.expanding_globs {
+			return Ok(None);
+		}
+		let path = match namespace.map(|namespace| &self.types[namespace]) {
+			Some(Type::Concrete(ConcreteType::Module(path))) => path.clone(),
+			Some(_) => return Ok(None),
+			None => Vec::new(),
 		};
+		let globs = self.globs.get(&path).cloned().unwrap_or_default();
+
+		// Private items are only an error if no glob has a public one
+		let mut private = false;
+		for glob in globs {
+			self.expanding_globs = true;
+			let module = self.instantiate(&glob, &[]);
+			self.expanding_globs = false;
+			let module = module?;
+
+			match self
+				.subtype_members
+				.get(&module)
+				.and_then(|members| members.get(n))
+			{
+				Some(template) if template.public => {
+					return Ok(Some((template.clone(), Some(module))));
+				}
+				Some(_) => private = true,
+				None => {}
+			}
+		}
+
+		if private {
+			return Err(InfoTypeError {
+				span: span.clone(),
+				error: TypeError::Private(n.clone()),
+			});
+		}
+		Ok(None)
+	}
+
+	/// The module whose declarations are currently being instantiated
+	fn current_module(&self) -> Option<usize> {
+		self.scopes.last().copied().flatten()
+	}
+
+	/// The path of the module that `namespace` is, or declares it if it's a type
+	fn module_path(&self, namespace: Option<usize>) -> Vec<String> {
+		let Some(namespace) = namespace else {
+			return Vec::new();
+		};
+		match self.get_type(namespace) {
+			Some(Type::Concrete(ConcreteType::Module(path))) => path.clone(),
+			_ => self.module_path(self.type_modules.get(&namespace).copied()),
+		}
+	}
+
+	/// The module that items found in `namespace` were declared in
+	fn declaring_module(&self, namespace: Option<usize>) -> Option<usize> {
+		match namespace {
+			Some(namespace)
+				if matches!(
+					self.get_type(namespace),
+					Some(Type::Concrete(ConcreteType::Module(_)))
+				) =>
+			{
+				Some(namespace)
+			}
+			Some(namespace) => self.type_modules.get(&namespace).copied(),
+			None => None,
+		}
+	}
+
+	/// Private items are visible inside their module and the modules within it
+	fn can_see_private(&self, namespace: Option<usize>) -> bool {
+		self.module_path(self.current_module())
+			.starts_with(&self.module_path(namespace))
+	}
+
+	fn instantiate_name(
+		&mut self,
+		n: &String,
+		params: &Vec<Option<InfoTypeExpr<'a>>>,
+		span: &Span<'a>,
+		generics: &[usize],
+		namespace: Option<usize>,
+	) -> Result<usize, InfoTypeError<'a>> {
+		let (mut template, namespace) = self.lookup(n, span, namespace)?;
+
+		// Imports and aliases that name a generic item, like `use std::option::Option;`,
+		// pass their arguments on to it
+		if template.parameters == 0
+			&& !params.is_empty()
+			&& let TypeExpr::Name(_, inner) | TypeExpr::Subtype(_, _, inner) =
+				&mut template.expr.expr
+			&& inner.is_empty()
+		{
+			*inner = (0..params.len())
+				.map(|i| {
+					Some(InfoTypeExpr {
+						expr: TypeExpr::Parameter(i),
+						idx: span.clone(),
+					})
+				})
+				.collect();
+			template.parameters = params.len();
+		}
 
 		if params.len() > template.parameters {
 			return Err(InfoTypeError {
@@ -230,17 +432,451 @@ impl<'a> Instantiator<'a> {
 			template_params.push(if let Some(param) = param {
 				self.instantiate(param, generics)?
 			} else {
-				self.next_unification_var += 1;
-				self.add(Type::UnificationVar(self.next_unification_var - 1))
+				self.unification_var()
 			});
 		}
 
 		while template_params.len() < template.parameters {
-			template_params.push(self.add(Type::UnificationVar(self.next_unification_var)));
-			self.next_unification_var += 1;
+			template_params.push(self.unification_var());
+		}
+
+		self.instantiate_template(n, template, template_params, namespace, span)
+	}
+
+	fn unification_var(&mut self) -> usize {
+		self.next_unification_var += 1;
+		self.add(Type::UnificationVar(self.next_unification_var - 1))
+	}
+
+	/// Instantiates a template with fully resolved parameters, reusing the
+	/// instance if this template was already instantiated with them
+	fn instantiate_template(
+		&mut self,
+		n: &String,
+		template: Template<'a>,
+		template_params: Vec<usize>,
+		namespace: Option<usize>,
+		span: &Span<'a>,
+	) -> Result<usize, InfoTypeError<'a>> {
+		let key = (namespace, n.clone(), template_params);
+		if let Some(typ) = self.instances.get(&key) {
+			return Ok(*typ);
+		}
+		let (_, _, template_params) = &key;
+
+		for (param, bound) in &template.bounds {
+			let typ = template_params[*param];
+			match self.get_type(typ) {
+				// Placeholders only stand in for arguments while inferring a call
+				Some(Type::Placeholder(_)) => continue,
+				Some(Type::UnificationVar(_)) => {
+					return Err(InfoTypeError {
+						span: span.clone(),
+						error: TypeError::Uninferred(n.clone()),
+					});
+				}
+				_ => self.check_capability(typ, bound, span)?,
+			}
+		}
+
+		// Names in the template refer to the module it was declared in
+		self.scopes.push(self.declaring_module(namespace));
+		let typ = self.instantiate(&template.expr, template_params);
+		self.scopes.pop();
+		let typ = typ?;
+
+		if !template.defaults.is_empty() {
+			self.struct_defaults
+				.insert(typ, (template.clone(), template_params.clone()));
+		}
+		if !template.members.is_empty() {
+			self.subtype_members
+				.entry(typ)
+				.or_default()
+				.extend(template.members);
+		}
+		if let Some(namespace) = namespace {
+			self.type_modules.entry(typ).or_insert(namespace);
+		}
+		if let Some(Type::Concrete(ConcreteType::Struct(_)) | Type::Union(_, _)) =
+			self.get_type(typ)
+		{
+			let name = if template_params.is_empty() {
+				n.clone()
+			} else {
+				let params: Vec<_> = template_params.iter().map(|p| self.type_name(*p)).collect();
+				format!("{n}<{}>", params.join(", "))
+			};
+			self.type_names.entry(typ).or_insert(name);
 		}
 
-		self.instantiate(&template.expr, &template_params)
+		self.instances.insert(key, typ);
+
+		Ok(typ)
+	}
+
+	/// Instantiates a function returning the default value of `field` in the struct `typ`
+	pub fn field_default(
+		&mut self,
+		typ: usize,
+		field: &str,
+	) -> Result<Option<usize>, InfoTypeError<'a>> {
+		let Some((template, params)) = self.struct_defaults.get(&typ).cloned() else {
+			return Ok(None);
+		};
+		let (Some(value), TypeExpr::Struct(fields)) =
+			(template.defaults.get(field), &template.expr.expr)
+		else {
+			return Ok(None);
+		};
+
+		let function = InfoTypeExpr {
+			expr: TypeExpr::Function(
+				Vec::new(),
+				Box::new(fields[field].clone()),
+				Some(GenericImplementation::Normal(Box::new(value.clone()))),
+				Vec::new(),
+			),
+			idx: value.idx.clone(),
+		};
+		Ok(Some(self.instantiate(&function, &params)?))
+	}
+
+	fn resolve_callee(
+		&mut self,
+		callee: &InfoTypeExpr<'a>,
+		generics: &[usize],
+	) -> Result<Option<Callee<'a>>, InfoTypeError<'a>> {
+		let (name, params, namespace, span) = match &callee.expr {
+			TypeExpr::Name(name, params) | TypeExpr::Subtype(None, name, params) => {
+				(name, params, None, &callee.idx)
+			}
+			TypeExpr::Subtype(Some(parent), name, params) => (
+				name,
+				params,
+				Some(self.instantiate(parent, generics)?),
+				&parent.idx,
+			),
+			_ => return Ok(None),
+		};
+		let (template, namespace) = self.lookup(name, span, namespace)?;
+		// Type arguments written as `_` are inferred too
+		if !matches!(template.expr.expr, TypeExpr::Function(..))
+			|| (params.len() >= template.parameters && params.iter().all(Option::is_some))
+		{
+			return Ok(None);
+		}
+
+		Ok(Some(Callee {
+			name: name.clone(),
+			params: params.clone(),
+			namespace,
+			template,
+		}))
+	}
+
+	/// Whether calling `callee` needs its type arguments inferred from the call's arguments
+	pub fn needs_inference(
+		&mut self,
+		callee: &InfoTypeExpr<'a>,
+		generics: &[usize],
+	) -> Result<bool, InfoTypeError<'a>> {
+		Ok(self.resolve_callee(callee, generics)?.is_some())
+	}
+
+	/// Instantiates a called function, inferring any type arguments that
+	/// weren't given explicitly from the types of the call's arguments
+	pub fn instantiate_call(
+		&mut self,
+		callee: &InfoTypeExpr<'a>,
+		generics: &[usize],
+		args: &[InfoExpr<'a>],
+		arg_types: &[usize],
+	) -> Result<usize, InfoTypeError<'a>> {
+		let Some(resolved) = self.resolve_callee(callee, generics)? else {
+			return self.instantiate(callee, generics);
+		};
+
+		let field = match args.get(1).map(|arg| &arg.expr) {
+			Some(Expr::Literal(Literal::String(field))) => Some(field.as_str()),
+			_ => None,
+		};
+		self.instantiate_inferred(resolved, generics, arg_types, field, &callee.idx)
+	}
+
+	/// Errors unless `typ` has every method that the capability named by `bound` requires
+	fn check_capability(
+		&mut self,
+		typ: usize,
+		bound: &InfoTypeExpr<'a>,
+		span: &Span<'a>,
+	) -> Result<(), InfoTypeError<'a>> {
+		let (name, (capability, namespace)) = match &bound.expr {
+			TypeExpr::Name(name, _) | TypeExpr::Subtype(None, name, _) => {
+				(name, self.lookup(name, &bound.idx, None)?)
+			}
+			TypeExpr::Subtype(Some(parent), name, _) => {
+				let namespace = self.instantiate(parent, &[])?;
+				(name, self.lookup(name, &bound.idx, Some(namespace))?)
+			}
+			_ => {
+				let typ = self.instantiate(bound, &[])?;
+				return Err(InfoTypeError {
+					span: bound.idx.clone(),
+					error: TypeError::NotACapability(self.type_name(typ)),
+				});
+			}
+		};
+
+		let TypeExpr::Capability(_, methods) = &capability.expr.expr else {
+			return Err(InfoTypeError {
+				span: bound.idx.clone(),
+				error: TypeError::NotACapability(name.clone()),
+			});
+		};
+
+		for (method, signature) in methods {
+			let TypeExpr::Function(required_args, required_ret, _, _) = &signature.expr else {
+				continue;
+			};
+			let implemented = match self.instantiate_method(typ, method, span, &[]) {
+				Ok(function) => match self.get_type(function).cloned() {
+					Some(Type::Concrete(ConcreteType::Function(args, ret, _)))
+						if args.len() == required_args.len() =>
+					{
+						let required: Option<Vec<_>> = required_args
+							.iter()
+							.chain([required_ret.as_ref()])
+							.map(substitute_self)
+							.collect();
+						match required {
+							Some(required) => {
+								// Names in the signature refer to the capability's module
+								self.scopes.push(self.declaring_module(namespace));
+								let required: Result<Vec<_>, _> = required
+									.iter()
+									.map(|expr| self.instantiate(expr, &[typ]))
+									.collect();
+								self.scopes.pop();
+								required?
+									.into_iter()
+									.zip(args.into_iter().chain([ret]))
+									.all(|(required, actual)| self.same(required, actual))
+							}
+							None => true,
+						}
+					}
+					_ => false,
+				},
+				Err(InfoTypeError {
+					error: TypeError::UnknownMethod { .. },
+					..
+				}) => false,
+				Err(error) => return Err(error),
+			};
+
+			if !implemented {
+				return Err(InfoTypeError {
+					span: span.clone(),
+					error: TypeError::MissingCapability {
+						typ: self.type_name(typ),
+						capability: name.clone(),
+					},
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	fn instantiate_inferred(
+		&mut self,
+		Callee {
+			name,
+			params,
+			namespace,
+			template,
+		}: Callee<'a>,
+		generics: &[usize],
+		arg_types: &[usize],
+		field: Option<&str>,
+		span: &Span<'a>,
+	) -> Result<usize, InfoTypeError<'a>> {
+		let mut bindings = Vec::new();
+		for param in &params {
+			bindings.push(match param {
+				Some(param) => Some(self.instantiate(param, generics)?),
+				None => None,
+			});
+		}
+		bindings.resize(template.parameters, None);
+
+		let pattern_generics: Vec<_> = bindings
+			.iter()
+			.enumerate()
+			.map(|(i, binding)| binding.unwrap_or_else(|| self.add(Type::Placeholder(i))))
+			.collect();
+
+		if let TypeExpr::Function(arg_exprs, _, _, _) = &template.expr.expr {
+			self.scopes.push(self.declaring_module(namespace));
+			let patterns: Result<Vec<_>, _> = arg_exprs
+				.iter()
+				.map(|arg_expr| self.instantiate(arg_expr, &pattern_generics))
+				.collect();
+			self.scopes.pop();
+			for (pattern, actual) in patterns?.into_iter().zip(arg_types) {
+				self.unify(pattern, *actual, &mut bindings);
+			}
+		}
+
+		// `get_field` returns the type of the field it's given, if the name is written out
+		if let TypeExpr::Function(_, _, Some(GenericImplementation::Reflect(Reflect::GetField)), _) =
+			&template.expr.expr
+			&& bindings[1].is_none()
+		{
+			let fields = match bindings[0].and_then(|subject| self.get_type(subject)) {
+				Some(Type::Concrete(ConcreteType::Struct(fields))) => Some(fields),
+				_ => None,
+			};
+			bindings[1] = match (fields, field) {
+				(Some(fields), Some(field)) => match fields.get(field) {
+					Some(typ) => Some(*typ),
+					None => {
+						return Err(InfoTypeError {
+							span: span.clone(),
+							error: TypeError::UnknownField(field.to_string()),
+						});
+					}
+				},
+				_ => {
+					return Err(InfoTypeError {
+						span: span.clone(),
+						error: TypeError::Uninferred(name),
+					});
+				}
+			};
+		}
+
+		let template_params = bindings
+			.into_iter()
+			.map(|binding| binding.unwrap_or_else(|| self.unification_var()))
+			.collect();
+
+		self.instantiate_template(&name, template, template_params, namespace, span)
+	}
+
+	/// Binds the placeholders in `pattern` to the matching parts of `actual`
+	fn unify(&self, pattern: usize, actual: usize, bindings: &mut [Option<usize>]) {
+		match (self.get_type(pattern), self.get_type(actual)) {
+			// Unknown types don't say anything, so another argument can bind the placeholder
+			(_, Some(Type::UnificationVar(_))) => {}
+			(Some(Type::Placeholder(i)), _) if bindings[*i].is_none() => {
+				bindings[*i] = Some(actual);
+			}
+			(Some(Type::Union(a, b)), Some(Type::Union(c, d))) => {
+				self.unify(*a, *c, bindings);
+				self.unify(*b, *d, bindings);
+			}
+			(Some(Type::Concrete(pattern)), Some(Type::Concrete(actual))) => {
+				match (pattern, actual) {
+					(ConcreteType::Tuple(a), ConcreteType::Tuple(b)) if a.len() == b.len() => {
+						for (a, b) in a.iter().zip(b) {
+							self.unify(*a, *b, bindings);
+						}
+					}
+					(ConcreteType::List(a), ConcreteType::List(b)) => {
+						self.unify(*a, *b, bindings);
+					}
+					(ConcreteType::Struct(a), ConcreteType::Struct(b)) => {
+						for (name, a) in a {
+							if let Some(b) = b.get(name) {
+								self.unify(*a, *b, bindings);
+							}
+						}
+					}
+					(ConcreteType::Function(a, a_ret, _), ConcreteType::Function(b, b_ret, _))
+						if a.len() == b.len() =>
+					{
+						for (a, b) in a.iter().zip(b) {
+							self.unify(*a, *b, bindings);
+						}
+						self.unify(*a_ret, *b_ret, bindings);
+					}
+					_ => {}
+				}
+			}
+			_ => {}
+		}
+	}
+
+	/// Resolves `receiver.method`, looking first at the receiver's `impl` members,
+	/// then at the module the receiver's type was declared in. Types declared
+	/// outside a module, like `String`, use the functions visible to the caller.
+	pub fn instantiate_method(
+		&mut self,
+		receiver: usize,
+		method: &str,
+		span: &Span<'a>,
+		generics: &[usize],
+	) -> Result<usize, InfoTypeError<'a>> {
+		let has_member = |ins: &Self, namespace: usize| {
+			ins.subtype_members
+				.get(&namespace)
+				.is_some_and(|members| members.contains_key(method))
+		};
+
+		let namespace = if has_member(self, receiver) {
+			Some(Some(receiver))
+		} else {
+			match self.type_modules.get(&receiver) {
+				Some(module) if has_member(self, *module) => Some(Some(*module)),
+				Some(_) => None,
+				None => Some(None),
+			}
+		};
+
+		let no_method = InfoTypeError {
+			span: span.clone(),
+			error: TypeError::UnknownMethod {
+				typ: self.type_name(receiver),
+				method: method.to_string(),
+			},
+		};
+
+		let Some(namespace) = namespace else {
+			return Err(no_method);
+		};
+
+		// Generic methods get their type arguments inferred from the receiver
+		let method = method.to_string();
+		let (template, namespace) = match self.lookup(&method, span, namespace) {
+			Ok(found) => found,
+			Err(InfoTypeError {
+				error: TypeError::UnknownType(_),
+				..
+			}) => return Err(no_method),
+			Err(error) => return Err(error),
+		};
+		let function = if template.parameters > 0 {
+			let callee = Callee {
+				name: method.clone(),
+				params: Vec::new(),
+				namespace,
+				template,
+			};
+			self.instantiate_inferred(callee, generics, &[receiver], None, span)?
+		} else {
+			self.instantiate_template(&method, template, Vec::new(), namespace, span)?
+		};
+
+		match self.get_type(function).cloned() {
+			Some(Type::Concrete(ConcreteType::Function(args, _, _)))
+				if !args.is_empty() && self.compatible(receiver, args[0], 0).unwrap() =>
+			{
+				Ok(function)
+			}
+			_ => Err(no_method),
+		}
 	}
 
 	pub fn instantiate(
@@ -264,6 +900,12 @@ impl<'a> Instantiator<'a> {
 				}
 			},
 			TypeExpr::Parameter(i) => generics[*i],
+			TypeExpr::Capability(name, _) => {
+				return Err(InfoTypeError {
+					span: expr.idx.clone(),
+					error: TypeError::CapabilityAsType(name.clone()),
+				});
+			}
 			TypeExpr::Name(n, params) => {
 				self.instantiate_name(n, params, &expr.idx, generics, None)?
 			}
@@ -283,6 +925,8 @@ impl<'a> Instantiator<'a> {
 			TypeExpr::Bool => self.add(Type::Concrete(ConcreteType::Bool)),
 			TypeExpr::String => self.add(Type::Concrete(ConcreteType::String)),
 			TypeExpr::IO => self.add(Type::Concrete(ConcreteType::IO)),
+			TypeExpr::Type => self.add(Type::Concrete(ConcreteType::Type)),
+			TypeExpr::Call(callee, args) => self.call_type_function(callee, args, generics)?,
 			TypeExpr::Integer { size, signed } => self.add(Type::Concrete(ConcreteType::Integer {
 				size: *size,
 				signed: *signed,
@@ -298,6 +942,10 @@ impl<'a> Instantiator<'a> {
 				}
 				self.add(Type::Concrete(ConcreteType::Tuple(ins_elems)))
 			}
+			TypeExpr::List(elem) => {
+				let elem = self.instantiate(elem, generics)?;
+				self.add(Type::Concrete(ConcreteType::List(elem)))
+			}
 			TypeExpr::Function(args, ret, imp, arg_names) => {
 				let mut ins_args = Vec::new();
 				for arg in args {
@@ -309,17 +957,33 @@ impl<'a> Instantiator<'a> {
 					.as_ref()
 					.expect("should not be null after implementation pass")
 				{
-					GenericImplementation::Native(native) => Implementation::Native(native.clone()),
+					GenericImplementation::Native(native) => {
+						self.check_native(native, &ins_args, ret, &expr.idx, generics)?;
+						Implementation::Native(native.clone())
+					}
+					GenericImplementation::Builtin(builtin) => {
+						Implementation::Builtin(builtin.returning(ret))
+					}
+					GenericImplementation::Reflect(reflect) => {
+						let field = match reflect {
+							Reflect::GetField => Some(ret),
+							Reflect::SetField => Some(ins_args[2]),
+							_ => None,
+						};
+						Implementation::Reflect(self.reflection(*reflect, ins_args[0], field))
+					}
 					GenericImplementation::Normal(body) => Implementation::Normal({
 						let mut last_var = arg_names.len();
 						let last_var2 = last_var;
 						let mut locals = HashMap::new();
+						let mut local_types = HashMap::new();
 						let mut ir = vec![Block {
 							terminal: Terminal::Return(last_var),
 							statements: Vec::new(),
 						}];
 						for (idx, arg) in arg_names.iter().enumerate() {
 							locals.insert(arg.clone(), idx);
+							local_types.insert(arg.clone(), Ok(ins_args[idx]));
 						}
 
 						let mut context = IRContext {
@@ -328,6 +992,9 @@ impl<'a> Instantiator<'a> {
 							ins: self,
 							locals: &mut locals,
 							next_var: &mut last_var,
+							local_types: &mut local_types,
+							pending_locals: &mut Vec::new(),
+							return_type: ret,
 						};
 
 						let mut block = 0;
@@ -337,8 +1004,9 @@ impl<'a> Instantiator<'a> {
 							Some(last_var2),
 							true,
 							&mut context,
-						);
+						)?;
 
+						debug_verify_blocks("lowering", &ir, arg_names.len(), &self.types);
 						ir
 					}),
 				};
@@ -350,6 +1018,173 @@ impl<'a> Instantiator<'a> {
 		Ok(type_)
 	}
 
+	/// Evaluates a call to a function that returns a type
+	fn call_type_function(
+		&mut self,
+		callee: &InfoTypeExpr<'a>,
+		args: &[InfoTypeExpr<'a>],
+		generics: &[usize],
+	) -> Result<usize, InfoTypeError<'a>> {
+		let function = self.instantiate(callee, generics)?;
+		let not_a_type = |this: &Self| InfoTypeError {
+			span: callee.idx.clone(),
+			error: TypeError::NotATypeFunction(this.type_name(function)),
+		};
+
+		let Some(Type::Concrete(ConcreteType::Function(arg_types, _, Implementation::Normal(ir)))) =
+			self.get_type(function).cloned()
+		else {
+			return Err(not_a_type(self));
+		};
+		if arg_types.len() != args.len() {
+			return Err(InfoTypeError {
+				span: callee.idx.clone(),
+				error: TypeError::IncorrectArgumentCount {
+					expected: arg_types.len(),
+					got: args.len(),
+				},
+			});
+		}
+
+		let mut arg_names = Vec::new();
+		let mut vars = HashMap::new();
+		for (idx, arg) in args.iter().enumerate() {
+			let typ = self.instantiate(arg, generics)?;
+			arg_names.push(self.type_name(typ));
+			vars.insert(idx, Some(Value::new(TypeValue(typ), type_id::Type)));
+		}
+
+		let RunResult::Concrete(result) = evaluate(&mut self.types, ir, &mut vars, 0, Vec::new())
+		else {
+			return Err(not_a_type(self));
+		};
+		let Some(TypeValue(typ)) = result.data.as_any().downcast_ref::<TypeValue>() else {
+			return Err(not_a_type(self));
+		};
+
+		// Name the type after the call that made it, unless it already has a name
+		if let Some(Type::Concrete(ConcreteType::Struct(_)) | Type::Union(_, _)) =
+			self.get_type(*typ)
+			&& let TypeExpr::Name(name, _) | TypeExpr::Subtype(_, name, _) = &callee.expr
+		{
+			self.type_names
+				.entry(*typ)
+				.or_insert_with(|| format!("{name}({})", arg_names.join(", ")));
+		}
+
+		Ok(*typ)
+	}
+
+	/// Checks a `dylib` function against the declarations in its library's metadata,
+	/// if it has any
+	fn check_native(
+		&mut self,
+		native: &NativeFunction,
+		args: &[usize],
+		ret: usize,
+		span: &Span<'a>,
+		generics: &[usize],
+	) -> Result<(), InfoTypeError<'a>> {
+		let error = |error| InfoTypeError {
+			span: span.clone(),
+			error,
+		};
+		let Some(declarations) = native
+			.declarations()
+			.map_err(|message| error(TypeError::Native(message)))?
+		else {
+			return Ok(());
+		};
+
+		let invalid = |message: String, index: usize| {
+			let at = match get_line_and_column(declarations, index) {
+				Ok((line, column)) => format!("{line}:{column}"),
+				Err(_) => "the end".to_string(),
+			};
+			error(TypeError::Native(format!(
+				"native library `{}` has invalid metadata at {at}: {message}",
+				native.lib_name
+			)))
+		};
+		let file = format!("<{} metadata>", native.lib_name);
+		let tokens = tokenise(declarations, 0, Cow::Owned(file))
+			.map_err(|error| invalid(error.error.to_string(), error.idx.index))?;
+		let signatures = parse_signatures(&tokens)
+			.map_err(|error| invalid(error.error.to_string(), error.span.index))?;
+
+		let function = native.func_name.clone();
+		let Some(signature) = signatures.into_iter().find(|sig| sig.name == function) else {
+			return Err(error(TypeError::Native(format!(
+				"native library `{}` doesn't declare `{function}` in its metadata",
+				native.lib_name
+			))));
+		};
+		if signature.generics.len() != generics.len() {
+			return Err(error(TypeError::Native(format!(
+				"`{function}` takes {} type arguments in its library, but is declared with {}",
+				signature.generics.len(),
+				generics.len()
+			))));
+		}
+		if signature.arg_types.len() != args.len() {
+			return Err(error(TypeError::Native(format!(
+				"`{function}` takes {} arguments in its library, but is declared with {}",
+				signature.arg_types.len(),
+				args.len()
+			))));
+		}
+
+		let declared = args.iter().chain([&ret]);
+		for (expected, got) in signature
+			.arg_types
+			.iter()
+			.chain([&signature.return_type])
+			.zip(declared)
+		{
+			let expected = self.instantiate(expected, generics)?;
+			if expected != *got {
+				return Err(error(TypeError::Native(format!(
+					"`{function}` is declared with `{}` where its library has `{}`",
+					self.type_name(*got),
+					self.type_name(expected)
+				))));
+			}
+		}
+		Ok(())
+	}
+
+	/// Specialises a reflection builtin for `subject`. `get_field` and `set_field`
+	/// only see the fields with the type they were instantiated with.
+	fn reflection(&self, reflect: Reflect, subject: usize, field: Option<usize>) -> Reflection {
+		let field = field.map(|field| self.type_name(field));
+		let mut fields = Vec::new();
+		if let Some(Type::Concrete(ConcreteType::Struct(struct_fields))) = self.get_type(subject) {
+			for (name, typ) in struct_fields {
+				let typ = self.type_name(*typ);
+				if field.as_ref().is_none_or(|field| *field == typ) {
+					fields.push((name.clone(), typ));
+				}
+			}
+			fields.sort();
+		}
+
+		let mut members = Vec::new();
+		let mut pending = vec![subject];
+		while let Some(typ) = pending.pop() {
+			match self.get_type(typ) {
+				Some(Type::Union(a, b)) => pending.extend([*b, *a]),
+				_ => members.push(self.type_name(typ)),
+			}
+		}
+
+		Reflection {
+			reflect,
+			type_name: self.type_name(subject),
+			fields,
+			members,
+		}
+	}
+
 	pub fn add(&mut self, typ: Type) -> usize {
 		if let Some((id, _)) = self
 			.types
@@ -368,6 +1203,57 @@ impl<'a> Instantiator<'a> {
 		self.types.get(index)
 	}
 
+	/// Name of a type as it would be written in Preval source, for diagnostics
+	pub fn type_name(&self, index: usize) -> String {
+		if let Some(name) = self.type_names.get(&index) {
+			return name.clone();
+		}
+
+		let list = |ids: &[usize]| {
+			ids.iter()
+				.map(|id| self.type_name(*id))
+				.collect::<Vec<_>>()
+				.join(", ")
+		};
+
+		match self.get_type(index) {
+			None => "<unknown>".to_string(),
+			Some(Type::Concrete(typ)) => match typ {
+				ConcreteType::Integer {
+					size: IntegerSize::Size,
+					signed,
+				} => if *signed { "isize" } else { "usize" }.to_string(),
+				ConcreteType::Integer {
+					size: IntegerSize::Number(bits),
+					signed,
+				} => format!("{}{bits}", if *signed { 'i' } else { 'u' }),
+				ConcreteType::Float { size } => format!("f{size}"),
+				ConcreteType::Bool => "bool".to_string(),
+				ConcreteType::String => "String".to_string(),
+				ConcreteType::IO => "IO".to_string(),
+				ConcreteType::Type => "Type".to_string(),
+				ConcreteType::Tuple(elems) => format!("({})", list(elems)),
+				ConcreteType::List(elem) => format!("List<{}>", self.type_name(*elem)),
+				ConcreteType::Struct(fields) => {
+					let mut fields: Vec<_> = fields
+						.iter()
+						.map(|(name, typ)| format!("{name}: {}", self.type_name(*typ)))
+						.collect();
+					fields.sort();
+					format!("struct {{ {} }}", fields.join(", "))
+				}
+				ConcreteType::Function(args, ret, _) => {
+					format!("fn({}): {}", list(args), self.type_name(*ret))
+				}
+				ConcreteType::Module(path) => path.join("::"),
+			},
+			Some(Type::Union(a, b)) => format!("{} | {}", self.type_name(*a), self.type_name(*b)),
+			Some(Type::EarlyReturn) => "!".to_string(),
+			Some(Type::Placeholder(i)) => format!("T{i}"),
+			Some(Type::UnificationVar(_)) => "_".to_string(),
+		}
+	}
+
 	pub fn compatible(&mut self, assignee: usize, slot: usize, index: usize) -> Result<bool, ()> {
 		let assignee_t = self.get_type(assignee).ok_or(())?.clone();
 		if let Type::EarlyReturn = assignee_t {
@@ -376,8 +1262,8 @@ impl<'a> Instantiator<'a> {
 		let slot_t = self.get_type(slot).ok_or(())?.clone();
 
 		match &slot_t {
-			Type::Concrete(a) => match &assignee_t {
-				Type::Concrete(b) => Ok(a == b),
+			Type::Concrete(_) => match &assignee_t {
+				Type::Concrete(_) => Ok(self.same(assignee, slot)),
 				Type::Union(a, b) => Ok(self.compatible(*a, slot, index + 1)?
 					|| self.compatible(*b, slot, index + 1)?),
 				Type::Placeholder(_) => Ok(slot_t == assignee_t),
@@ -391,7 +1277,160 @@ impl<'a> Instantiator<'a> {
 				|| self.compatible(assignee, *b, index + 1)?),
 			Type::EarlyReturn => panic!("Early return can't be a slot"),
 			Type::Placeholder(_) => Ok(slot_t == assignee_t),
-			Type::UnificationVar(_) => Ok(slot_t == assignee_t),
+			Type::UnificationVar(_) => {
+				self.types[slot] = assignee_t;
+				Ok(true)
+			}
 		}
 	}
+
+	/// Whether `a` and `b` are the same type, binding unification variables in either.
+	/// Types are compared by structure, as binding a variable can leave a type with two ids.
+	fn same(&mut self, a: usize, b: usize) -> bool {
+		if a == b {
+			return true;
+		}
+		let (Some(a_t), Some(b_t)) = (self.get_type(a).cloned(), self.get_type(b).cloned()) else {
+			return false;
+		};
+
+		match (a_t, b_t) {
+			(Type::UnificationVar(_), typ) => {
+				self.types[a] = typ;
+				true
+			}
+			(typ, Type::UnificationVar(_)) => {
+				self.types[b] = typ;
+				true
+			}
+			(Type::Union(a, b), Type::Union(c, d)) => self.same(a, c) && self.same(b, d),
+			(Type::Concrete(a), Type::Concrete(b)) => match (a, b) {
+				(ConcreteType::List(a), ConcreteType::List(b)) => self.same(a, b),
+				(ConcreteType::Tuple(a), ConcreteType::Tuple(b)) => {
+					a.len() == b.len() && a.into_iter().zip(b).all(|(a, b)| self.same(a, b))
+				}
+				(ConcreteType::Struct(a), ConcreteType::Struct(b)) => {
+					a.len() == b.len()
+						&& a.into_iter()
+							.all(|(name, a)| b.get(&name).is_some_and(|b| self.same(a, *b)))
+				}
+				(
+					ConcreteType::Function(a, a_ret, a_imp),
+					ConcreteType::Function(b, b_ret, b_imp),
+				) => {
+					a_imp == b_imp
+						&& a.len() == b.len()
+						&& a.into_iter().zip(b).all(|(a, b)| self.same(a, b))
+						&& self.same(a_ret, b_ret)
+				}
+				(a, b) => a == b,
+			},
+			(a, b) => a == b,
+		}
+	}
+}
+
+/// Replaces `Self` in a capability's required signature with the first type parameter.
+/// Signatures that are generic themselves are left to the argument count check.
+fn substitute_self<'a>(expr: &InfoTypeExpr<'a>) -> Option<InfoTypeExpr<'a>> {
+	let list = |exprs: &Vec<InfoTypeExpr<'a>>| -> Option<Vec<_>> {
+		exprs.iter().map(substitute_self).collect()
+	};
+	let params = |params: &Vec<Option<InfoTypeExpr<'a>>>| -> Option<Vec<_>> {
+		params
+			.iter()
+			.map(|param| match param {
+				Some(param) => substitute_self(param).map(Some),
+				None => Some(None),
+			})
+			.collect()
+	};
+
+	let typ = match &expr.expr {
+		TypeExpr::Name(name, params) if name == "Self" && params.is_empty() => {
+			TypeExpr::Parameter(0)
+		}
+		TypeExpr::Name(name, inner) => TypeExpr::Name(name.clone(), params(inner)?),
+		TypeExpr::Subtype(parent, name, inner) => TypeExpr::Subtype(
+			match parent {
+				Some(parent) => Some(Box::new(substitute_self(parent)?)),
+				None => None,
+			},
+			name.clone(),
+			params(inner)?,
+		),
+		TypeExpr::Union(a, b) => {
+			TypeExpr::Union(Box::new(substitute_self(a)?), Box::new(substitute_self(b)?))
+		}
+		TypeExpr::Struct(fields) => TypeExpr::Struct(
+			fields
+				.iter()
+				.map(|(name, field)| Some((name.clone(), substitute_self(field)?)))
+				.collect::<Option<_>>()?,
+		),
+		TypeExpr::Tuple(elems) => TypeExpr::Tuple(list(elems)?),
+		TypeExpr::List(elem) => TypeExpr::List(Box::new(substitute_self(elem)?)),
+		TypeExpr::Call(callee, args) => {
+			TypeExpr::Call(Box::new(substitute_self(callee)?), list(args)?)
+		}
+		TypeExpr::Parameter(_) | TypeExpr::Function(..) => return None,
+		other => other.clone(),
+	};
+
+	Some(InfoTypeExpr {
+		expr: typ,
+		idx: expr.idx.clone(),
+	})
+}
+
+/// Declares a reflection builtin as a function generic over the reflected type
+fn reflect_template<'a>(reflect: Reflect) -> Template<'a> {
+	let span = Span {
+		file: Cow::Borrowed(file!()),
+		index: 0,
+	};
+	let expr = |expr| InfoTypeExpr {
+		expr,
+		idx: span.clone(),
+	};
+
+	let subject = expr(TypeExpr::Parameter(0));
+	let field = expr(TypeExpr::Parameter(1));
+	let index = expr(TypeExpr::Integer {
+		size: IntegerSize::Size,
+		signed: false,
+	});
+	let string = expr(TypeExpr::String);
+
+	let (args, ret) = match reflect {
+		Reflect::TypeName => (vec![subject], string),
+		Reflect::FieldCount | Reflect::MemberCount => (vec![subject], index),
+		Reflect::FieldName | Reflect::FieldType | Reflect::MemberName => {
+			(vec![subject, index], string)
+		}
+		Reflect::GetField => (vec![subject, string], field),
+		Reflect::SetField => (vec![subject.clone(), string, field], subject),
+	};
+	let names = ["value", "key", "field"][..args.len()]
+		.iter()
+		.map(|name| name.to_string())
+		.collect();
+	let parameters = match reflect {
+		Reflect::GetField | Reflect::SetField => 2,
+		_ => 1,
+	};
+
+	Template {
+		parameters,
+		expr: expr(TypeExpr::Function(
+			args,
+			Box::new(ret),
+			Some(GenericImplementation::Reflect(reflect)),
+			names,
+		)),
+		members: HashMap::new(),
+		bounds: Vec::new(),
+		defaults: HashMap::new(),
+		public: true,
+	}
 }
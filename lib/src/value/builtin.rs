@@ -0,0 +1,394 @@
+use std::{
+	io::BufRead,
+	path::Path,
+	sync::{PoisonError, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	typ::{Type, type_id},
+	value::{
+		PrevalValue, Value, list::List, primitive::EmptyTuple, runtime_type::TypeDeserializer,
+	},
+	vm::{EvalError, RunResult},
+};
+
+/// Functions implemented by the VM itself
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Builtin {
+	/// `compile_error(io: IO, message: String)` stops evaluation with `message`
+	CompileError,
+	/// `static_assert(io: IO, condition: bool, message: String)` stops evaluation
+	/// with `message` if `condition` is false or isn't known yet
+	StaticAssert,
+
+	Print,
+	Input,
+
+	Concat,
+	Length,
+	Equals,
+	FromUsize,
+	IsUsize,
+	ToUsize,
+	Slice,
+	Contains,
+	Trim,
+
+	Plus,
+	Minus,
+	Times,
+	Divide,
+	Remainder,
+	Min,
+	Max,
+	Equal,
+	LessThan,
+
+	/// Holds the type of the list it makes, filled in when instantiated
+	ListNew(usize),
+	ListPush,
+	ListGet,
+	ListLength,
+
+	/// Holds the type of the list it makes, filled in when instantiated
+	Args(usize),
+	HasVar,
+	GetVar,
+
+	ReadFile,
+	WriteFile,
+	FileExists,
+}
+
+/// Arguments given to the program after the file being run
+static ARGS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+pub fn set_args(args: Vec<String>) {
+	*ARGS.write().unwrap_or_else(PoisonError::into_inner) = args;
+}
+
+impl Builtin {
+	pub const ALL: &[Builtin] = &[
+		Builtin::CompileError,
+		Builtin::StaticAssert,
+		Builtin::Print,
+		Builtin::Input,
+		Builtin::Concat,
+		Builtin::Length,
+		Builtin::Equals,
+		Builtin::FromUsize,
+		Builtin::IsUsize,
+		Builtin::ToUsize,
+		Builtin::Slice,
+		Builtin::Contains,
+		Builtin::Trim,
+		Builtin::Plus,
+		Builtin::Minus,
+		Builtin::Times,
+		Builtin::Divide,
+		Builtin::Remainder,
+		Builtin::Min,
+		Builtin::Max,
+		Builtin::Equal,
+		Builtin::LessThan,
+		Builtin::ListNew(0),
+		Builtin::ListPush,
+		Builtin::ListGet,
+		Builtin::ListLength,
+		Builtin::Args(0),
+		Builtin::HasVar,
+		Builtin::GetVar,
+		Builtin::ReadFile,
+		Builtin::WriteFile,
+		Builtin::FileExists,
+	];
+
+	/// The name `builtin "name"` declarations use
+	pub fn name(&self) -> &'static str {
+		match self {
+			Builtin::CompileError => "compile_error",
+			Builtin::StaticAssert => "static_assert",
+			Builtin::Print => "print",
+			Builtin::Input => "input",
+			Builtin::Concat => "concat",
+			Builtin::Length => "string_length",
+			Builtin::Equals => "string_equals",
+			Builtin::FromUsize => "usize_to_string",
+			Builtin::IsUsize => "is_usize",
+			Builtin::ToUsize => "string_to_usize",
+			Builtin::Slice => "slice",
+			Builtin::Contains => "contains",
+			Builtin::Trim => "trim",
+			Builtin::Plus => "plus",
+			Builtin::Minus => "minus",
+			Builtin::Times => "times",
+			Builtin::Divide => "divide",
+			Builtin::Remainder => "remainder",
+			Builtin::Min => "min",
+			Builtin::Max => "max",
+			Builtin::Equal => "usize_equal",
+			Builtin::LessThan => "less_than",
+			Builtin::ListNew(_) => "list_new",
+			Builtin::ListPush => "list_push",
+			Builtin::ListGet => "list_get",
+			Builtin::ListLength => "list_length",
+			Builtin::Args(_) => "args",
+			Builtin::HasVar => "has_var",
+			Builtin::GetVar => "get_var",
+			Builtin::ReadFile => "read_file",
+			Builtin::WriteFile => "write_file",
+			Builtin::FileExists => "file_exists",
+		}
+	}
+
+	/// What a `builtin "name"` declaration has to look like, apart from the names
+	pub fn signature(&self) -> &'static str {
+		match self {
+			Builtin::CompileError => "fn compile_error(io: IO, message: String);",
+			Builtin::StaticAssert => "fn static_assert(io: IO, condition: bool, message: String);",
+			Builtin::Print => "fn print(io: IO, text: String);",
+			Builtin::Input => "fn input(io: IO): String;",
+			Builtin::Concat => "fn concat(a: String, b: String): String;",
+			Builtin::Length => "fn string_length(text: String): usize;",
+			Builtin::Equals => "fn string_equals(a: String, b: String): bool;",
+			Builtin::FromUsize => "fn usize_to_string(number: usize): String;",
+			Builtin::IsUsize => "fn is_usize(text: String): bool;",
+			Builtin::ToUsize => "fn string_to_usize(text: String): usize;",
+			Builtin::Slice => "fn slice(text: String, start: usize, end: usize): String;",
+			Builtin::Contains => "fn contains(text: String, pattern: String): bool;",
+			Builtin::Trim => "fn trim(text: String): String;",
+			Builtin::Plus => "fn plus(a: usize, b: usize): usize;",
+			Builtin::Minus => "fn minus(a: usize, b: usize): usize;",
+			Builtin::Times => "fn times(a: usize, b: usize): usize;",
+			Builtin::Divide => "fn divide(a: usize, b: usize): usize;",
+			Builtin::Remainder => "fn remainder(a: usize, b: usize): usize;",
+			Builtin::Min => "fn min(a: usize, b: usize): usize;",
+			Builtin::Max => "fn max(a: usize, b: usize): usize;",
+			Builtin::Equal => "fn usize_equal(a: usize, b: usize): bool;",
+			Builtin::LessThan => "fn less_than(a: usize, b: usize): bool;",
+			Builtin::ListNew(_) => "fn list_new<T>(): List<T>;",
+			Builtin::ListPush => "fn list_push<T>(list: List<T>, item: T): List<T>;",
+			Builtin::ListGet => "fn list_get<T>(list: List<T>, index: usize): T;",
+			Builtin::ListLength => "fn list_length<T>(list: List<T>): usize;",
+			Builtin::Args(_) => "fn args(io: IO): List<String>;",
+			Builtin::HasVar => "fn has_var(io: IO, name: String): bool;",
+			Builtin::GetVar => "fn get_var(io: IO, name: String): String;",
+			Builtin::ReadFile => "fn read_file(io: IO, path: String): String;",
+			Builtin::WriteFile => "fn write_file(io: IO, path: String, contents: String);",
+			Builtin::FileExists => "fn file_exists(io: IO, path: String): bool;",
+		}
+	}
+
+	pub fn named(name: &str) -> Option<Builtin> {
+		Builtin::ALL
+			.iter()
+			.find(|builtin| builtin.name() == name)
+			.copied()
+	}
+
+	/// Specialises the builtin for the type it returns, for those that make values of it
+	pub fn returning(self, typ: usize) -> Self {
+		match self {
+			Builtin::ListNew(_) => Builtin::ListNew(typ),
+			Builtin::Args(_) => Builtin::Args(typ),
+			builtin => builtin,
+		}
+	}
+
+	/// Stops evaluation for `compile_error` and failed `static_assert`s
+	fn check(&self, args: Vec<&Option<Value>>) -> Result<RunResult, String> {
+		// Without an IO these run later, which for `compile_io` never happens
+		if args[0].is_none() {
+			return Ok(RunResult::Residualise);
+		}
+		let Some(message) = args[args.len() - 1] else {
+			return Ok(RunResult::Residualise);
+		};
+		let message = string(message)?.clone();
+
+		let message = match self {
+			Builtin::StaticAssert => match args[1] {
+				Some(condition) if boolean(condition)? => return Ok(unit()),
+				Some(_) => message,
+				None => format!("{message} (the condition isn't known at compile time)"),
+			},
+			_ => message,
+		};
+
+		Ok(RunResult::Error(EvalError {
+			message,
+			trace: Vec::new(),
+			check: true,
+		}))
+	}
+
+	/// Runs a builtin whose arguments are all known
+	fn run(&self, args: &[Value]) -> Result<Value, String> {
+		let text = |value: String| Value::new(value, type_id::String);
+		let number = |value: usize| Value::new(value, type_id::usize);
+		let truth = |value: bool| Value::new(value, type_id::bool);
+		let operands = || Ok::<_, String>((usize(&args[0])?, usize(&args[1])?));
+		let arithmetic = |operation: fn(usize, usize) -> Option<usize>, name: &str| {
+			let (a, b) = operands()?;
+			operation(a, b)
+				.map(number)
+				.ok_or_else(|| format!("{name} of {a} and {b} is out of range"))
+		};
+
+		match self {
+			Builtin::CompileError | Builtin::StaticAssert => unreachable!(),
+
+			Builtin::Print => {
+				println!("{}", string(&args[1])?);
+				Ok(Value::new(EmptyTuple, type_id::empty_tuple))
+			}
+			Builtin::Input => {
+				let mut line = String::new();
+				std::io::stdin()
+					.lock()
+					.read_line(&mut line)
+					.map_err(|error| format!("couldn't read input: {error}"))?;
+				Ok(text(line.trim_end_matches(['\n', '\r']).to_string()))
+			}
+
+			Builtin::Concat => Ok(text(format!("{}{}", string(&args[0])?, string(&args[1])?))),
+			Builtin::Length => Ok(number(string(&args[0])?.chars().count())),
+			Builtin::Equals => Ok(truth(string(&args[0])? == string(&args[1])?)),
+			Builtin::FromUsize => Ok(text(usize(&args[0])?.to_string())),
+			Builtin::IsUsize => Ok(truth(string(&args[0])?.parse::<usize>().is_ok())),
+			Builtin::ToUsize => {
+				let digits = string(&args[0])?;
+				digits
+					.parse()
+					.map(number)
+					.map_err(|_| format!("`{digits}` isn't a number"))
+			}
+			Builtin::Slice => {
+				let (string, start, end) = (string(&args[0])?, usize(&args[1])?, usize(&args[2])?);
+				if start > end || end > string.chars().count() {
+					return Err(format!(
+						"{start}..{end} is out of range for a string of length {}",
+						string.chars().count()
+					));
+				}
+				Ok(text(string.chars().skip(start).take(end - start).collect()))
+			}
+			Builtin::Contains => Ok(truth(string(&args[0])?.contains(string(&args[1])?))),
+			Builtin::Trim => Ok(text(string(&args[0])?.trim().to_string())),
+
+			Builtin::Plus => arithmetic(usize::checked_add, "sum"),
+			Builtin::Minus => arithmetic(usize::checked_sub, "difference"),
+			Builtin::Times => arithmetic(usize::checked_mul, "product"),
+			Builtin::Divide => arithmetic(usize::checked_div, "quotient"),
+			Builtin::Remainder => arithmetic(usize::checked_rem, "remainder"),
+			Builtin::Min => operands().map(|(a, b)| number(a.min(b))),
+			Builtin::Max => operands().map(|(a, b)| number(a.max(b))),
+			Builtin::Equal => operands().map(|(a, b)| truth(a == b)),
+			Builtin::LessThan => operands().map(|(a, b)| truth(a < b)),
+
+			Builtin::ListNew(typ) => Ok(Value::new(List(Vec::new()), *typ)),
+			Builtin::ListPush => {
+				let mut elems = list(&args[0])?.0.clone();
+				elems.push(args[1].clone());
+				Ok(Value::new(List(elems), args[0].typ))
+			}
+			Builtin::ListGet => {
+				let (elems, index) = (&list(&args[0])?.0, usize(&args[1])?);
+				elems.get(index).cloned().ok_or_else(|| {
+					format!(
+						"index {index} is out of range for a list of length {}",
+						elems.len()
+					)
+				})
+			}
+			Builtin::ListLength => Ok(number(list(&args[0])?.0.len())),
+
+			Builtin::Args(typ) => {
+				let args = ARGS.read().unwrap_or_else(PoisonError::into_inner);
+				Ok(Value::new(
+					List(args.iter().cloned().map(text).collect()),
+					*typ,
+				))
+			}
+			Builtin::HasVar => Ok(truth(std::env::var(string(&args[1])?).is_ok())),
+			Builtin::GetVar => {
+				let name = string(&args[1])?;
+				std::env::var(name)
+					.map(text)
+					.map_err(|error| format!("environment variable `{name}`: {error}"))
+			}
+
+			Builtin::ReadFile => {
+				let path = string(&args[1])?;
+				std::fs::read_to_string(path)
+					.map(text)
+					.map_err(|error| format!("couldn't read `{path}`: {error}"))
+			}
+			Builtin::WriteFile => {
+				let path = string(&args[1])?;
+				std::fs::write(path, string(&args[2])?)
+					.map(|_| Value::new(EmptyTuple, type_id::empty_tuple))
+					.map_err(|error| format!("couldn't write `{path}`: {error}"))
+			}
+			Builtin::FileExists => Ok(truth(Path::new(string(&args[1])?).exists())),
+		}
+	}
+}
+
+fn argument<'v, T: 'static>(value: &'v Value, expected: &str) -> Result<&'v T, String> {
+	value.data.as_any().downcast_ref().ok_or_else(|| {
+		format!(
+			"expected a `{expected}` argument, got {:?}",
+			value.deserializer
+		)
+	})
+}
+
+fn string(value: &Value) -> Result<&String, String> {
+	argument(value, "String")
+}
+
+fn usize(value: &Value) -> Result<usize, String> {
+	argument(value, "usize").copied()
+}
+
+fn boolean(value: &Value) -> Result<bool, String> {
+	argument(value, "bool").copied()
+}
+
+fn list(value: &Value) -> Result<&List, String> {
+	argument(value, "List")
+}
+
+fn unit() -> RunResult {
+	RunResult::Concrete(Value::new(EmptyTuple, type_id::empty_tuple))
+}
+
+impl PrevalValue for Builtin {
+	fn get_type(&self) -> TypeDeserializer {
+		TypeDeserializer::Builtin
+	}
+
+	fn vcall(&mut self, _module: &mut Vec<Type>, args: Vec<&Option<Value>>) -> RunResult {
+		let result = if let Builtin::CompileError | Builtin::StaticAssert = self {
+			self.check(args)
+		} else {
+			// The rest run once every argument is known, so those taking an IO run at
+			// compile time for `compile_io` and are left for run time for `io`
+			let Some(args) = args.into_iter().cloned().collect::<Option<Vec<_>>>() else {
+				return RunResult::Residualise;
+			};
+			self.run(&args).map(RunResult::Concrete)
+		};
+		match result {
+			Ok(result) => result,
+			Err(message) => RunResult::Error(EvalError {
+				message,
+				trace: Vec::new(),
+				check: false,
+			}),
+		}
+	}
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+use crate::value::{PrevalValue, Value, runtime_type::TypeDeserializer};
+
+/// A `std::collections::List`, whose elements are always known
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct List(pub Vec<Value>);
+
+impl PrevalValue for List {
+	fn get_type(&self) -> TypeDeserializer {
+		TypeDeserializer::List
+	}
+}
@@ -1,6 +1,9 @@
 mod value;
 pub use value::*;
+pub mod builtin;
+pub mod list;
 pub mod native;
 pub mod primitive;
+pub mod reflection;
 pub mod runtime_type;
 pub mod structure;
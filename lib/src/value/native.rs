@@ -1,15 +1,188 @@
 use crate::{
-	typ::{Type, type_id},
-	value::{PrevalValue, Value, primitive::EmptyTuple, runtime_type::TypeDeserializer},
+	typ::{ConcreteType, Type, type_id},
+	value::{
+		PrevalValue, Value, list::List, primitive::EmptyTuple, runtime_type::TypeDeserializer,
+		structure::Struct,
+	},
+	vm::{EvalError, RunResult, is_member},
 };
 use libloading::Library;
-use preval_api::RawAPI;
+use preval_api::{
+	ApiVersion, METADATA_SYMBOL, Metadata, PREVAL_API_VERSION, PREVAL_NO_TYPE, RawAPI,
+	VERSION_SYMBOL, ValueKind,
+};
 use serde::{Deserialize, Serialize};
+use std::{
+	borrow::Cow,
+	cell::Cell,
+	collections::HashMap,
+	ffi::CStr,
+	panic::{self, AssertUnwindSafe},
+	path::{Path, PathBuf},
+	ptr::null_mut,
+	sync::{Arc, LazyLock, Mutex, OnceLock, PoisonError, RwLock},
+};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+type Symbol =
+	extern "C" fn(api: *const RawAPI<Value>, argc: usize, args: *const *const Value) -> *mut Value;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NativeFunction {
 	pub lib_name: String,
 	pub func_name: String,
+	/// Where the library was found when the program was compiled, so running it
+	/// doesn't need the project
+	#[serde(default)]
+	pub path: Option<PathBuf>,
+	/// The function once it's been looked up, shared between copies of this value
+	#[serde(skip)]
+	symbol: Arc<OnceLock<Result<Symbol, String>>>,
+}
+
+impl NativeFunction {
+	pub fn new(lib_name: String, func_name: String) -> Self {
+		Self {
+			lib_name,
+			func_name,
+			path: None,
+			symbol: Arc::default(),
+		}
+	}
+
+	/// The file the library is loaded from, which is looked for by name if it's no
+	/// longer where the program was compiled against it
+	pub fn library_path(&self) -> PathBuf {
+		match &self.path {
+			Some(path) if path.is_file() => path.clone(),
+			_ => library_path(&self.lib_name),
+		}
+	}
+
+	/// Loads the function from its library, the first time it's needed
+	fn symbol(&self) -> Result<Symbol, String> {
+		self.symbol
+			.get_or_init(|| {
+				let library = load_library(&self.library_path())?;
+				check_version(library, &self.lib_name)?;
+				unsafe { library.get::<Symbol>(self.func_name.as_bytes()) }
+					.map(|symbol| *symbol)
+					.map_err(|error| {
+						format!(
+							"native library `{}` has no function `{}`: {error}",
+							self.lib_name, self.func_name
+						)
+					})
+			})
+			.clone()
+	}
+
+	/// Checks the function exists ahead of calling it, returning the declarations its
+	/// library gives in its metadata. A library that can't be loaded isn't an error
+	/// until the function is called, as it may only be available at run time.
+	pub fn declarations(&self) -> Result<Option<&'static str>, String> {
+		let Ok(library) = load_library(&self.library_path()) else {
+			return Ok(None);
+		};
+		check_version(library, &self.lib_name)?;
+		self.symbol()?;
+		Ok(metadata(library))
+	}
+}
+
+impl PartialEq for NativeFunction {
+	fn eq(&self, other: &Self) -> bool {
+		self.lib_name == other.lib_name && self.func_name == other.func_name
+	}
+}
+
+/// Where native libraries are loaded from, before the system's default locations
+#[derive(Default)]
+struct SearchPaths {
+	/// Directories searched for every library
+	dirs: Vec<PathBuf>,
+	/// Libraries, or directories containing them, by `dylib` name
+	libraries: HashMap<String, PathBuf>,
+}
+
+static SEARCH_PATHS: LazyLock<RwLock<SearchPaths>> = LazyLock::new(Default::default);
+
+pub fn set_search_paths(dirs: Vec<PathBuf>, libraries: HashMap<String, PathBuf>) {
+	*SEARCH_PATHS.write().unwrap() = SearchPaths { dirs, libraries };
+}
+
+/// The file to load the library `lib_name` from
+fn library_path(lib_name: &str) -> PathBuf {
+	let file_name = libloading::library_filename(lib_name);
+	let paths = SEARCH_PATHS.read().unwrap();
+	if let Some(path) = paths.libraries.get(lib_name) {
+		return if path.is_dir() {
+			path.join(file_name)
+		} else {
+			path.clone()
+		};
+	}
+	paths
+		.dirs
+		.iter()
+		.map(|dir| dir.join(&file_name))
+		.find(|path| path.is_file())
+		.unwrap_or_else(|| PathBuf::from(file_name))
+}
+
+/// Loaded libraries by resolved path. They're never unloaded, because values they
+/// make can outlive the call that made them.
+static LIBRARIES: LazyLock<Mutex<HashMap<PathBuf, &'static Library>>> =
+	LazyLock::new(Default::default);
+
+fn load_library(path: &Path) -> Result<&'static Library, String> {
+	let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+	let mut libraries = LIBRARIES.lock().unwrap_or_else(PoisonError::into_inner);
+	if let Some(library) = libraries.get(&path) {
+		return Ok(library);
+	}
+
+	let library = unsafe { Library::new(&path) }
+		.map_err(|error| format!("couldn't load native library {}: {error}", path.display()))?;
+	let library = Box::leak(Box::new(library));
+	libraries.insert(path, library);
+	Ok(library)
+}
+
+/// Checks a library was built against this version of the API before anything in it is called
+fn check_version(library: &Library, lib_name: &str) -> Result<(), String> {
+	let version = unsafe { library.get::<ApiVersion>(VERSION_SYMBOL.as_bytes()) }.map_err(|_| {
+		format!(
+			"native library `{lib_name}` doesn't export `{VERSION_SYMBOL}`, so it needs building again with the current preval.h"
+		)
+	})?;
+	let version = unsafe { version() };
+	if version != PREVAL_API_VERSION {
+		return Err(format!(
+			"native library `{lib_name}` was built for version {version} of the native API, but this is version {PREVAL_API_VERSION}"
+		));
+	}
+	Ok(())
+}
+
+/// Metadata of loaded libraries, by address, which is read once as it's static
+static METADATA: LazyLock<Mutex<HashMap<usize, Option<&'static str>>>> =
+	LazyLock::new(Default::default);
+
+fn metadata(library: &'static Library) -> Option<&'static str> {
+	let mut metadata = METADATA.lock().unwrap_or_else(PoisonError::into_inner);
+	*metadata
+		.entry(library as *const Library as usize)
+		.or_insert_with(|| {
+			let function = unsafe { library.get::<Metadata>(METADATA_SYMBOL.as_bytes()) }.ok()?;
+			let text = unsafe { function() };
+			if text.is_null() {
+				return None;
+			}
+			let text = unsafe { CStr::from_ptr(text) }
+				.to_string_lossy()
+				.into_owned();
+			Some(&*Box::leak(text.into_boxed_str()))
+		})
 }
 
 impl PrevalValue for NativeFunction {
@@ -17,79 +190,418 @@ impl PrevalValue for NativeFunction {
 		TypeDeserializer::NativeFunction
 	}
 
-	fn vcall(
-		&mut self,
-		_module: &mut Vec<Type>,
-		args: Vec<&Option<super::Value>>,
-	) -> crate::vm::RunResult {
-		unsafe {
-			let lib = Library::new(libloading::library_filename(self.lib_name.clone())).unwrap();
-
-			let symbol = lib
-				.get::<extern "C" fn(
-					api: *const RawAPI<Value>,
-					argc: usize,
-					args: *const *const Value,
-				) -> *mut Value>(self.func_name.as_bytes())
-				.unwrap();
-
-			let mut args_c = Vec::new();
-			for arg in args {
-				match arg {
-					Some(value) => args_c.push(value as *const Value),
-					None => args_c.push(std::ptr::null_mut()),
+	fn vcall(&mut self, module: &mut Vec<Type>, args: Vec<&Option<Value>>) -> RunResult {
+		let symbol = match self.symbol() {
+			Ok(symbol) => symbol,
+			Err(message) => {
+				return RunResult::Error(EvalError {
+					message,
+					trace: Vec::new(),
+					check: false,
+				});
+			}
+		};
+
+		let args_c: Vec<*const Value> = args
+			.into_iter()
+			.map(|arg| match arg {
+				Some(value) => value as *const Value,
+				None => std::ptr::null(),
+			})
+			.collect();
+
+		let (previous_module, previous_error) = (MODULE.replace(module), ERROR.take());
+		let result = symbol(&API, args_c.len(), args_c.as_ptr());
+		MODULE.set(previous_module);
+		let error = ERROR.replace(previous_error);
+
+		match (result, error) {
+			(ptr, Some(error)) => {
+				if !ptr.is_null() {
+					drop_value(ptr);
 				}
+				RunResult::Error(error)
 			}
-			let args_ptr = args_c.as_ptr();
-			let argc = args_c.len();
-
-			let api = RawAPI::<Value> {
-				drop_value,
-				string_value_length,
-				string_value_start,
-				new_tuple_value,
-				new_string_value,
-			};
-
-			match symbol(&api, argc, args_ptr) {
-				p if std::ptr::null() == p => crate::vm::RunResult::Residualise,
-				ptr => crate::vm::RunResult::Concrete(*Box::from_raw(ptr)),
+			(ptr, None) if ptr.is_null() => RunResult::Residualise,
+			(ptr, None) => {
+				let value = *unsafe { Box::from_raw(ptr) };
+				match check_complete(module, &value) {
+					Ok(()) => RunResult::Concrete(value),
+					Err(message) => RunResult::Error(EvalError {
+						message: format!("`{}` returned {message}", self.func_name),
+						trace: Vec::new(),
+						check: false,
+					}),
+				}
 			}
 		}
 	}
 }
 
-extern "C" fn drop_value(value: *mut Value) {
-	unsafe {
-		drop(Box::from_raw(value));
-	}
+static API: RawAPI<Value> = RawAPI {
+	version: PREVAL_API_VERSION,
+	drop_value,
+	clone_value,
+	value_kind,
+	value_type,
+	type_is,
+	usize_value,
+	new_usize_value,
+	bool_value,
+	new_bool_value,
+	string_value_length,
+	string_value_start,
+	new_string_value,
+	new_tuple_value,
+	new_struct_value,
+	struct_field,
+	set_struct_field,
+	new_list_value,
+	list_length,
+	list_get,
+	list_push,
+	raise_error,
+	call,
+};
+
+thread_local! {
+	/// Types of the program making the current native call, for `type_is` and `call`
+	static MODULE: Cell<*mut Vec<Type>> = const { Cell::new(std::ptr::null_mut()) };
+	/// Error raised during the current native call, which fails it
+	static ERROR: Cell<Option<EvalError>> = const { Cell::new(None) };
 }
 
-extern "C" fn string_value_length(value: *const Value) -> usize {
-	unsafe {
-		match (*value).data.as_any().downcast_ref::<String>() {
-			Some(s) => s.len(),
-			None => panic!("Value is not a string"),
+/// Runs the body of a callback, so that misuse by a library fails the current call
+/// instead of unwinding into C. The callback returns `fallback` after failing.
+fn guard<T>(callback: &str, fallback: T, body: impl FnOnce() -> Result<T, String>) -> T {
+	let message = match panic::catch_unwind(AssertUnwindSafe(body)) {
+		Ok(Ok(value)) => return value,
+		Ok(Err(message)) => message,
+		Err(payload) => payload
+			.downcast_ref::<&str>()
+			.map(|message| message.to_string())
+			.or_else(|| payload.downcast_ref::<String>().cloned())
+			.unwrap_or_else(|| "panicked".to_string()),
+	};
+	fail(EvalError {
+		message: format!("native call to `{callback}` failed: {message}"),
+		trace: Vec::new(),
+		check: false,
+	});
+	fallback
+}
+
+/// Fails the current native call, unless it's already failed. The first failure is
+/// the one worth reporting, as later ones tend to follow from it.
+fn fail(error: EvalError) {
+	let previous = ERROR.take();
+	ERROR.set(Some(previous.unwrap_or(error)));
+}
+
+/// Checks that the structs in a value made by a native library have all their fields
+fn check_complete(module: &[Type], value: &Value) -> Result<(), String> {
+	if let Some(structure) = value.data.as_any().downcast_ref::<Struct>()
+		&& let Some(Type::Concrete(ConcreteType::Struct(fields))) = module.get(value.typ)
+	{
+		for name in fields.keys() {
+			match structure.fields.get(name) {
+				Some(Some(field)) => check_complete(module, field)?,
+				_ => return Err(format!("a struct without its field `{name}`")),
+			}
+		}
+	} else if let Some(list) = value.data.as_any().downcast_ref::<List>() {
+		for elem in &list.0 {
+			check_complete(module, elem)?;
 		}
 	}
+	Ok(())
 }
 
-extern "C" fn string_value_start(value: *const Value) -> *const u8 {
-	unsafe {
-		match (*value).data.as_any().downcast_ref::<String>() {
-			Some(s) => s.as_ptr(),
-			None => panic!("Value is not a string"),
-		}
+/// Types of the program making the current native call
+fn module<'m>() -> Result<&'m [Type], String> {
+	let module = MODULE.get();
+	if module.is_null() {
+		return Err("there's no native call in progress".to_string());
 	}
+	Ok(unsafe { &*module })
 }
 
-extern "C" fn new_tuple_value() -> *mut Value {
-	Box::into_raw(Box::new(Value::new(EmptyTuple, type_id::empty_tuple)))
+/// Gives a value to a native library, which then owns it
+fn into_raw(value: Value) -> *mut Value {
+	Box::into_raw(Box::new(value))
+}
+
+/// Borrows a value given by a native library
+fn value<'v>(value: *const Value) -> Result<&'v Value, String> {
+	unsafe { value.as_ref() }.ok_or_else(|| "the value is null".to_string())
+}
+
+/// Takes back ownership of a value given by a native library
+fn from_raw(value: *mut Value) -> Result<Value, String> {
+	if value.is_null() {
+		return Err("the value is null".to_string());
+	}
+	Ok(*unsafe { Box::from_raw(value) })
+}
+
+/// Borrows the data of a value given by a native library
+fn data<'v, T: 'static>(ptr: *const Value, kind: &str) -> Result<&'v T, String> {
+	value(ptr)?
+		.data
+		.as_any()
+		.downcast_ref::<T>()
+		.ok_or_else(|| format!("the value is not a {kind}"))
+}
+
+fn data_mut<'v, T: 'static>(ptr: *mut Value, kind: &str) -> Result<&'v mut T, String> {
+	unsafe { ptr.as_mut() }
+		.ok_or_else(|| "the value is null".to_string())?
+		.data
+		.as_any_mut()
+		.downcast_mut::<T>()
+		.ok_or_else(|| format!("the value is not a {kind}"))
+}
+
+fn name<'v>(start: *const u8, len: usize) -> Result<Cow<'v, str>, String> {
+	if start.is_null() && len > 0 {
+		return Err("the string is null".to_string());
+	}
+	if len == 0 {
+		return Ok(Cow::Borrowed(""));
+	}
+	Ok(String::from_utf8_lossy(unsafe {
+		std::slice::from_raw_parts(start, len)
+	}))
+}
+
+extern "C" fn drop_value(value: *mut Value) {
+	guard("drop_value", (), || {
+		if !value.is_null() {
+			drop(from_raw(value)?);
+		}
+		Ok(())
+	})
+}
+
+extern "C" fn clone_value(ptr: *const Value) -> *mut Value {
+	guard("clone_value", null_mut(), || {
+		Ok(into_raw(value(ptr)?.clone()))
+	})
+}
+
+extern "C" fn value_kind(ptr: *const Value) -> ValueKind {
+	guard("value_kind", ValueKind::Other, || {
+		Ok(match value(ptr)?.deserializer {
+			TypeDeserializer::USize => ValueKind::USize,
+			TypeDeserializer::Bool => ValueKind::Bool,
+			TypeDeserializer::String => ValueKind::String,
+			TypeDeserializer::Tuple(_) => ValueKind::Tuple,
+			TypeDeserializer::Struct => ValueKind::Struct,
+			TypeDeserializer::List => ValueKind::List,
+			_ => ValueKind::Other,
+		})
+	})
+}
+
+extern "C" fn value_type(ptr: *const Value) -> usize {
+	guard("value_type", PREVAL_NO_TYPE, || Ok(value(ptr)?.typ))
+}
+
+extern "C" fn type_is(typ: usize, expected: usize) -> bool {
+	guard("type_is", false, || {
+		Ok(MODULE.with(|module| match module.get() {
+			module if module.is_null() => typ == expected,
+			module => is_member(unsafe { &*module }, typ, expected),
+		}))
+	})
+}
+
+extern "C" fn usize_value(value: *const Value) -> usize {
+	guard("usize_value", 0, || data::<usize>(value, "usize").copied())
+}
+
+extern "C" fn new_usize_value(value: usize) -> *mut Value {
+	into_raw(Value::new(value, type_id::usize))
+}
+
+extern "C" fn bool_value(value: *const Value) -> bool {
+	guard("bool_value", false, || data::<bool>(value, "bool").copied())
+}
+
+extern "C" fn new_bool_value(value: bool) -> *mut Value {
+	into_raw(Value::new(value, type_id::bool))
+}
+
+extern "C" fn string_value_length(value: *const Value) -> usize {
+	guard("string_value_length", 0, || {
+		Ok(data::<String>(value, "string")?.len())
+	})
+}
+
+extern "C" fn string_value_start(value: *const Value) -> *const u8 {
+	guard("string_value_start", std::ptr::null(), || {
+		Ok(data::<String>(value, "string")?.as_ptr())
+	})
 }
 
 extern "C" fn new_string_value(value: *const u8, len: usize) -> *mut Value {
-	Box::into_raw(Box::new(Value::new(
-		String::from_utf8_lossy(unsafe { std::slice::from_raw_parts(value, len) }).into_owned(),
-		type_id::String,
-	)))
+	guard("new_string_value", null_mut(), || {
+		Ok(into_raw(Value::new(
+			name(value, len)?.into_owned(),
+			type_id::String,
+		)))
+	})
+}
+
+extern "C" fn new_tuple_value() -> *mut Value {
+	into_raw(Value::new(EmptyTuple, type_id::empty_tuple))
+}
+
+extern "C" fn new_struct_value(typ: usize) -> *mut Value {
+	guard("new_struct_value", null_mut(), || {
+		match module()?.get(typ) {
+			Some(Type::Concrete(ConcreteType::Struct(_))) => Ok(into_raw(Value::new(
+				Struct {
+					fields: HashMap::new(),
+				},
+				typ,
+			))),
+			_ => Err(format!("type {typ} is not a struct")),
+		}
+	})
+}
+
+extern "C" fn struct_field(value: *const Value, start: *const u8, len: usize) -> *mut Value {
+	guard("struct_field", null_mut(), || {
+		Ok(
+			match data::<Struct>(value, "struct")?
+				.fields
+				.get(name(start, len)?.as_ref())
+			{
+				Some(Some(field)) => into_raw(field.clone()),
+				_ => null_mut(),
+			},
+		)
+	})
+}
+
+extern "C" fn set_struct_field(value: *mut Value, start: *const u8, len: usize, field: *mut Value) {
+	guard("set_struct_field", (), || {
+		let field = from_raw(field)?;
+		let name = name(start, len)?.into_owned();
+		let module = module()?;
+		let Some(Type::Concrete(ConcreteType::Struct(fields))) =
+			module.get(self::value(value)?.typ)
+		else {
+			return Err("the value is not a struct".to_string());
+		};
+		match fields.get(&name) {
+			Some(typ) if is_member(module, field.typ, *typ) => {}
+			Some(_) => return Err(format!("field `{name}` has a different type")),
+			None => return Err(format!("the struct has no field `{name}`")),
+		}
+		data_mut::<Struct>(value, "struct")?
+			.fields
+			.insert(name, Some(field));
+		Ok(())
+	})
+}
+
+extern "C" fn new_list_value(typ: usize) -> *mut Value {
+	guard("new_list_value", null_mut(), || match module()?.get(typ) {
+		Some(Type::Concrete(ConcreteType::List(_))) => {
+			Ok(into_raw(Value::new(List(Vec::new()), typ)))
+		}
+		_ => Err(format!("type {typ} is not a list")),
+	})
+}
+
+extern "C" fn list_length(value: *const Value) -> usize {
+	guard("list_length", 0, || {
+		Ok(data::<List>(value, "list")?.0.len())
+	})
+}
+
+extern "C" fn list_get(value: *const Value, index: usize) -> *mut Value {
+	guard("list_get", null_mut(), || {
+		Ok(match data::<List>(value, "list")?.0.get(index) {
+			Some(elem) => into_raw(elem.clone()),
+			None => null_mut(),
+		})
+	})
+}
+
+extern "C" fn list_push(value: *mut Value, elem: *mut Value) {
+	guard("list_push", (), || {
+		let elem = from_raw(elem)?;
+		let module = module()?;
+		if let Some(Type::Concrete(ConcreteType::List(typ))) = module.get(self::value(value)?.typ)
+			&& !is_member(module, elem.typ, *typ)
+		{
+			return Err("the element has a different type to the list's".to_string());
+		}
+		data_mut::<List>(value, "list")?.0.push(elem);
+		Ok(())
+	})
+}
+
+extern "C" fn raise_error(start: *const u8, len: usize) -> *mut Value {
+	guard("raise_error", null_mut(), || {
+		fail(EvalError {
+			message: name(start, len)?.into_owned(),
+			trace: Vec::new(),
+			check: false,
+		});
+		Ok(null_mut())
+	})
+}
+
+extern "C" fn call(function: *const Value, argc: usize, args: *const *const Value) -> *mut Value {
+	guard("call", null_mut(), || {
+		let mut function = value(function)?.clone();
+		let module = MODULE.get();
+		if module.is_null() {
+			return Err("there's no native call in progress".to_string());
+		}
+		// The module is only borrowed by the native call that's waiting on this one
+		let module = unsafe { &mut *module };
+
+		if !matches!(
+			function.deserializer,
+			TypeDeserializer::Function
+				| TypeDeserializer::Partial
+				| TypeDeserializer::NativeFunction
+				| TypeDeserializer::Builtin
+				| TypeDeserializer::Reflection
+		) {
+			return Err("the value is not a function".to_string());
+		}
+		if let Some(Type::Concrete(ConcreteType::Function(params, _, _))) = module.get(function.typ)
+			&& params.len() != argc
+		{
+			return Err(format!(
+				"the function takes {} arguments, but was given {argc}",
+				params.len()
+			));
+		}
+
+		let args = match argc {
+			0 => Vec::new(),
+			_ if args.is_null() => return Err("the arguments are null".to_string()),
+			_ => unsafe { std::slice::from_raw_parts(args, argc) }
+				.iter()
+				.map(|arg| unsafe { arg.as_ref() }.cloned())
+				.collect(),
+		};
+
+		Ok(match function.data.call(module, args.iter().collect()) {
+			RunResult::Concrete(value) => into_raw(value),
+			// Left for run time, which the native can only follow by returning null itself
+			RunResult::Partial(_) | RunResult::Residualise => null_mut(),
+			RunResult::Error(error) => {
+				fail(error);
+				null_mut()
+			}
+		})
+	})
 }
@@ -3,12 +3,13 @@ use serde::{Deserialize, Serialize};
 use crate::value::{PrevalValue, Value, runtime_type::TypeDeserializer};
 
 impl PrevalValue for String {
-	fn vindex(&mut self, _value: &Value) -> Value {
+	fn vindex(&mut self, _value: &Value) -> Result<Value, String> {
 		// match value.data.as_any().downcast_ref::<usize>() {
 		//     Some(other) => Value::new(self.chars().nth(*other).unwrap().to_string()),
 		//     None => panic!("Index string with non-usize"),
 		// }
-		todo!("Pass around type context to enable creation of values from rust")
+		// TODO: Pass around type context to enable creation of values from rust
+		Err("strings can't be indexed yet".to_string())
 	}
 
 	fn get_type(&self) -> TypeDeserializer {
@@ -40,6 +41,19 @@ impl PrevalValue for IO {
 	}
 }
 
+/// A type used as a value. Poisoned so that types can't reach run time.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct TypeValue(pub usize);
+impl PrevalValue for TypeValue {
+	fn get_type(&self) -> TypeDeserializer {
+		TypeDeserializer::Type
+	}
+
+	fn vshould_poison(&self) -> bool {
+		true
+	}
+}
+
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct EmptyTuple;
 impl PrevalValue for EmptyTuple {
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	typ::{Type, type_id},
+	value::{PrevalValue, Value, runtime_type::TypeDeserializer, structure::Struct},
+	vm::{EvalError, RunResult},
+};
+
+/// Builtin functions that inspect the type of their first argument
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Reflect {
+	TypeName,
+	FieldCount,
+	FieldName,
+	FieldType,
+	MemberCount,
+	MemberName,
+	GetField,
+	SetField,
+}
+
+impl Reflect {
+	pub const ALL: &[Reflect] = &[
+		Reflect::TypeName,
+		Reflect::FieldCount,
+		Reflect::FieldName,
+		Reflect::FieldType,
+		Reflect::MemberCount,
+		Reflect::MemberName,
+		Reflect::GetField,
+		Reflect::SetField,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Reflect::TypeName => "type_name",
+			Reflect::FieldCount => "field_count",
+			Reflect::FieldName => "field_name",
+			Reflect::FieldType => "field_type",
+			Reflect::MemberCount => "member_count",
+			Reflect::MemberName => "member_name",
+			Reflect::GetField => "get_field",
+			Reflect::SetField => "set_field",
+		}
+	}
+}
+
+/// A reflection builtin specialised for one type. Everything that only depends
+/// on the type is worked out during instantiation, so calls fold to constants
+/// even when the value itself isn't known.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reflection {
+	pub reflect: Reflect,
+	pub type_name: String,
+	/// Field names and type names, sorted by field name
+	pub fields: Vec<(String, String)>,
+	/// Names of the members of a union, or just the type itself
+	pub members: Vec<String>,
+}
+
+impl PrevalValue for Reflection {
+	fn get_type(&self) -> TypeDeserializer {
+		TypeDeserializer::Reflection
+	}
+
+	fn vcall(&mut self, _module: &mut Vec<Type>, args: Vec<&Option<Value>>) -> RunResult {
+		match self.run(args) {
+			Ok(result) => result,
+			Err(message) => RunResult::Error(EvalError {
+				message,
+				trace: Vec::new(),
+				check: false,
+			}),
+		}
+	}
+}
+
+impl Reflection {
+	fn run(&self, args: Vec<&Option<Value>>) -> Result<RunResult, String> {
+		let string = |s: &String| RunResult::Concrete(Value::new(s.clone(), type_id::String));
+		let count = |n: usize| RunResult::Concrete(Value::new(n, type_id::usize));
+
+		Ok(match self.reflect {
+			Reflect::TypeName => string(&self.type_name),
+			Reflect::FieldCount => count(self.fields.len()),
+			Reflect::MemberCount => count(self.members.len()),
+			Reflect::FieldName | Reflect::FieldType | Reflect::MemberName => {
+				let Some(index) = args[1] else {
+					return Ok(RunResult::Residualise);
+				};
+				let index = *index
+					.data
+					.as_any()
+					.downcast_ref::<usize>()
+					.ok_or("reflection index isn't a usize")?;
+
+				let name = match self.reflect {
+					Reflect::FieldName => self.fields.get(index).map(|field| &field.0),
+					Reflect::FieldType => self.fields.get(index).map(|field| &field.1),
+					_ => self.members.get(index),
+				};
+				match name {
+					Some(name) => string(name),
+					None => {
+						let (kind, len) = match self.reflect {
+							Reflect::MemberName => ("members", self.members.len()),
+							_ => ("fields", self.fields.len()),
+						};
+						return Err(format!(
+							"`{}` has {len} {kind}, so there's no {index}",
+							self.type_name
+						));
+					}
+				}
+			}
+			Reflect::GetField => {
+				let (Some(value), Some(name)) = (args[0], args[1]) else {
+					return Ok(RunResult::Residualise);
+				};
+				let (structure, name) = self.struct_field(value, name)?;
+				match &structure.fields[&name] {
+					Some(field) => RunResult::Concrete(field.clone()),
+					None => RunResult::Residualise,
+				}
+			}
+			Reflect::SetField => {
+				let (Some(value), Some(name), Some(field)) = (args[0], args[1], args[2]) else {
+					return Ok(RunResult::Residualise);
+				};
+				let (mut structure, name) = self.struct_field(value, name)?;
+				structure.fields.insert(name, Some(field.clone()));
+				RunResult::Concrete(Value::new(structure, value.typ))
+			}
+		})
+	}
+
+	/// Checks that `name` is a field of the struct in `value`. For `get_field` and
+	/// `set_field`, `fields` only has the fields of the type they were declared with.
+	fn struct_field(&self, value: &Value, name: &Value) -> Result<(Struct, String), String> {
+		let name = name
+			.data
+			.as_any()
+			.downcast_ref::<String>()
+			.ok_or("field name isn't a String")?;
+		let structure = value
+			.data
+			.as_any()
+			.downcast_ref::<Struct>()
+			.ok_or_else(|| format!("`{}` isn't a struct", self.type_name))?;
+		if !self.fields.iter().any(|(field, _)| field == name) {
+			return Err(match structure.fields.contains_key(name) {
+				true => format!(
+					"field `{name}` of `{}` has a different type",
+					self.type_name
+				),
+				false => format!("`{}` has no field `{name}`", self.type_name),
+			});
+		}
+		Ok((structure.clone(), name.clone()))
+	}
+}
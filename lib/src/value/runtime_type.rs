@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
 
 use crate::ir::{Function, Partial};
+use crate::value::builtin::Builtin;
+use crate::value::list::List;
 use crate::value::native::NativeFunction;
-use crate::value::primitive::{EmptyTuple, IO};
+use crate::value::primitive::{EmptyTuple, IO, TypeValue};
+use crate::value::reflection::Reflection;
 use crate::value::structure::Struct;
 use crate::value::{PrevalValue, ValueData};
 
@@ -14,9 +17,13 @@ pub enum TypeDeserializer {
 	Bool,
 	String,
 	Struct,
+	List,
 	Function,
 	Partial,
 	NativeFunction,
+	Builtin,
+	Reflection,
+	Type,
 	Poison,
 }
 
@@ -29,19 +36,25 @@ impl PrevalValue for Poison {
 	}
 }
 
-pub fn deserialize_type(typ: &TypeDeserializer, data: String) -> Box<dyn ValueData> {
-	match typ {
+/// Reads a value's data, failing rather than panicking if it doesn't match its type
+pub fn deserialize_type(
+	typ: &TypeDeserializer,
+	data: String,
+) -> Result<Box<dyn ValueData>, ron::error::SpannedError> {
+	Ok(match typ {
 		TypeDeserializer::Poison => Box::new(Poison),
-		TypeDeserializer::NativeFunction => {
-			Box::new(ron::de::from_str::<NativeFunction>(&data).unwrap())
-		}
-		TypeDeserializer::String => Box::new(ron::de::from_str::<String>(&data).unwrap()),
-		TypeDeserializer::Tuple(_) => Box::new(ron::de::from_str::<EmptyTuple>(&data).unwrap()),
+		TypeDeserializer::NativeFunction => Box::new(ron::de::from_str::<NativeFunction>(&data)?),
+		TypeDeserializer::Builtin => Box::new(ron::de::from_str::<Builtin>(&data)?),
+		TypeDeserializer::Reflection => Box::new(ron::de::from_str::<Reflection>(&data)?),
+		TypeDeserializer::Type => Box::new(ron::de::from_str::<TypeValue>(&data)?),
+		TypeDeserializer::String => Box::new(ron::de::from_str::<String>(&data)?),
+		TypeDeserializer::Tuple(_) => Box::new(ron::de::from_str::<EmptyTuple>(&data)?),
 		TypeDeserializer::IO => Box::new(IO),
-		TypeDeserializer::Bool => Box::new(ron::de::from_str::<bool>(&data).unwrap()),
-		TypeDeserializer::USize => Box::new(ron::de::from_str::<usize>(&data).unwrap()),
-		TypeDeserializer::Struct => Box::new(ron::de::from_str::<Struct>(&data).unwrap()),
-		TypeDeserializer::Function => Box::new(ron::de::from_str::<Function>(&data).unwrap()),
-		TypeDeserializer::Partial => Box::new(ron::de::from_str::<Partial>(&data).unwrap()),
-	}
+		TypeDeserializer::Bool => Box::new(ron::de::from_str::<bool>(&data)?),
+		TypeDeserializer::USize => Box::new(ron::de::from_str::<usize>(&data)?),
+		TypeDeserializer::Struct => Box::new(ron::de::from_str::<Struct>(&data)?),
+		TypeDeserializer::List => Box::new(ron::de::from_str::<List>(&data)?),
+		TypeDeserializer::Function => Box::new(ron::de::from_str::<Function>(&data)?),
+		TypeDeserializer::Partial => Box::new(ron::de::from_str::<Partial>(&data)?),
+	})
 }
@@ -8,16 +8,27 @@ use crate::value::{PrevalValue, Value, runtime_type::TypeDeserializer};
 pub struct Struct {
 	pub fields: HashMap<String, Option<Value>>,
 }
+
+impl Struct {
+	/// Whether every field is known, so the struct can be written out as a literal
+	pub fn is_complete(&self) -> bool {
+		self.fields.values().all(Option::is_some)
+	}
+}
+
 impl PrevalValue for Struct {
 	fn get_type(&self) -> TypeDeserializer {
 		TypeDeserializer::Struct
 	}
 
-	fn vindex(&mut self, value: &Value) -> Value {
-		if let Some(name) = value.data.as_any().downcast_ref::<String>() {
-			self.fields[name].clone().unwrap()
-		} else {
-			todo!("Index structs by number")
+	fn vindex(&mut self, value: &Value) -> Result<Value, String> {
+		let Some(name) = value.data.as_any().downcast_ref::<String>() else {
+			return Err("structs can only be indexed by field name".to_string());
+		};
+		match self.fields.get(name) {
+			Some(Some(field)) => Ok(field.clone()),
+			Some(None) => Err(format!("field `{name}` isn't known yet")),
+			None => Err(format!("the struct has no field `{name}`")),
 		}
 	}
 }
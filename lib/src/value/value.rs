@@ -1,12 +1,9 @@
-use std::{
-	any::{Any, type_name},
-	fmt::Debug,
-};
+use std::{any::Any, fmt::Debug};
 
 use crate::{
 	typ::Type,
 	value::runtime_type::{TypeDeserializer, deserialize_type},
-	vm::RunResult,
+	vm::{EvalError, RunResult},
 };
 
 #[repr(C)]
@@ -35,13 +32,12 @@ impl Value {
 
 pub trait ValueData: Debug {
 	fn vclone(&self) -> Box<dyn ValueData>;
-	fn index(&mut self, _value: &Value) -> Value {
-		panic!("Type is not indexable")
-	}
+	fn index(&mut self, value: &Value) -> Result<Value, String>;
 	fn call(&mut self, module: &mut Vec<Type>, args: Vec<&Option<Value>>) -> RunResult;
 	fn vto_string(&self) -> String;
 	fn veq(&self, other: &Value) -> bool;
 	fn as_any(&self) -> &dyn Any;
+	fn as_any_mut(&mut self) -> &mut dyn Any;
 	fn pre_serialize<'a>(&'a self) -> Option<&'a dyn erased_serde::Serialize>;
 	fn get_deserializer(&self) -> TypeDeserializer;
 	fn should_poison(&self) -> bool;
@@ -75,12 +71,16 @@ impl Clone for Box<dyn ValueData> {
 // }
 
 pub trait PrevalValue: PreSerialize {
-	fn vindex(&mut self, _value: &Value) -> Value {
-		panic!("Not indexable: {}", type_name::<Self>())
+	fn vindex(&mut self, _value: &Value) -> Result<Value, String> {
+		Err(format!("a {:?} can't be indexed", self.get_type()))
 	}
 
 	fn vcall(&mut self, _module: &mut Vec<Type>, _args: Vec<&Option<Value>>) -> RunResult {
-		panic!("Not callable: {}", type_name::<Self>())
+		RunResult::Error(EvalError {
+			message: format!("a {:?} can't be called", self.get_type()),
+			trace: Vec::new(),
+			check: false,
+		})
 	}
 
 	fn vshould_poison(&self) -> bool {
@@ -101,7 +101,7 @@ impl<'de> serde::Deserialize<'de> for Value {
 		let RawValue(deserializer, data, typ) = RawValue::deserialize(deserializer)?;
 
 		Ok(Value {
-			data: deserialize_type(&deserializer, data),
+			data: deserialize_type(&deserializer, data).map_err(serde::de::Error::custom)?,
 			deserializer,
 			typ,
 		})
@@ -151,7 +151,11 @@ impl<T: PartialEq + Clone + Debug + PrevalValue + 'static> ValueData for T {
 		self
 	}
 
-	fn index(&mut self, value: &Value) -> Value {
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+
+	fn index(&mut self, value: &Value) -> Result<Value, String> {
 		self.vindex(value)
 	}
 
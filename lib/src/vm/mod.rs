@@ -1,14 +1,18 @@
 mod operation;
+pub use operation::is_member;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
+	error::Span,
 	ir::{Block, Callable, Function, Operation, Partial, Statement, Terminal},
 	typ::{ConcreteType, Implementation, Type},
 	value::{Value, structure::Struct},
-	vm::operation::{access, call, guard_phi, index, initialize_struct, is, load_local, phi},
+	vm::operation::{
+		access, call, construct_type, guard_phi, index, initialize_struct, is, load_local, phi,
+	},
 };
 
 #[repr(C)]
@@ -17,17 +21,64 @@ pub enum RunResult {
 	Concrete(Value),
 	Partial(Partial),
 	Residualise, // Native functions only! Because all preval functions can be partially evaluated even if there are no known arguments
+	Error(EvalError),
 }
 
+/// Raised by `compile_error`, failed `static_assert`s and builtins that can't give a result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvalError {
+	pub message: String,
+	/// The call that raised the error, followed by the calls that led to it
+	pub trace: Vec<Span<'static>>,
+	/// Whether it's from `compile_error` or `static_assert`, which stop compilation
+	/// even in branches that might not be taken
+	pub check: bool,
+}
+
+/// Raised when a condition isn't a `bool`, which only malformed IR can do
+fn not_bool() -> EvalError {
+	EvalError {
+		message: "a condition isn't a `bool`".to_string(),
+		trace: Vec::new(),
+		check: false,
+	}
+}
+
+/// How many of the latest tail calls errors list, as loops written as tail calls can
+/// run indefinitely
+const MAX_TAIL_CALLS: usize = 100;
+
 pub fn evaluate(
+	module: &mut Vec<Type>,
+	blocks: Vec<Block>,
+	vars: &mut HashMap<usize, Option<Value>>,
+	start_block: usize,
+	generics: Vec<usize>,
+) -> RunResult {
+	let mut tail_calls = VecDeque::new();
+	match evaluate_frames(module, blocks, vars, start_block, generics, &mut tail_calls) {
+		// Tail calls replace the frame they're made from, so aren't otherwise traced
+		RunResult::Error(mut error) => {
+			error.trace.extend(tail_calls.into_iter().rev());
+			RunResult::Error(error)
+		}
+		result => result,
+	}
+}
+
+/// Evaluates a function, then each function it tail calls in its place, recording
+/// those calls in `tail_calls`
+fn evaluate_frames(
 	module: &mut Vec<Type>,
 	mut blocks: Vec<Block>,
 	vars: &mut HashMap<usize, Option<Value>>,
 	start_block: usize,
 	mut generics: Vec<usize>,
+	tail_calls: &mut VecDeque<Span<'static>>,
 ) -> RunResult {
 	let mut last_block_num = start_block;
 	let mut block_num = start_block;
+	let mut residualised = false;
 
 	loop {
 		let mut out: Vec<Statement> = Vec::new();
@@ -46,8 +97,16 @@ pub fn evaluate(
 				} => guard_phi(block, var, store, last_block_num, &mut out, vars),
 				Statement {
 					store,
-					operation: Operation::Call { function, args },
-				} => call(function, args, store, &mut out, module, vars),
+					operation: Operation::Call {
+						function,
+						args,
+						span,
+					},
+				} => {
+					if let Err(error) = call(function, args, span, store, &mut out, module, vars) {
+						return RunResult::Error(error);
+					}
+				}
 				Statement {
 					store,
 					operation: Operation::LoadFunction(type_id),
@@ -63,6 +122,12 @@ pub fn evaluate(
 											Implementation::Native(imp) => {
 												Value::new(imp.clone(), type_id)
 											}
+											Implementation::Builtin(imp) => {
+												Value::new(imp, type_id)
+											}
+											Implementation::Reflect(imp) => {
+												Value::new(imp.clone(), type_id)
+											}
 											Implementation::Normal(imp) => Value::new(
 												Function {
 													ir: imp.clone(),
@@ -73,7 +138,13 @@ pub fn evaluate(
 											),
 										}
 									}
-									_ => todo!(),
+									_ => {
+										return RunResult::Error(EvalError {
+											message: format!("type {type_id} isn't a function"),
+											trace: Vec::new(),
+											check: false,
+										});
+									}
 								}),
 							);
 						}
@@ -97,7 +168,9 @@ pub fn evaluate(
 					store,
 					operation: Operation::Index(left, right),
 				} => {
-					index(left, right, store, &mut out, vars);
+					if let Err(error) = index(left, right, store, &mut out, vars) {
+						return RunResult::Error(error);
+					}
 				}
 				Statement {
 					store,
@@ -115,26 +188,35 @@ pub fn evaluate(
 					store,
 					operation: Operation::Access(left, right),
 				} => {
-					access(left, right, store, &mut out, vars);
+					if let Err(error) = access(left, right, store, &mut out, vars) {
+						return RunResult::Error(error);
+					}
+				}
+				Statement {
+					store,
+					operation:
+						operation @ (Operation::StructType(_)
+						| Operation::UnionType(_, _)
+						| Operation::TupleType(_)),
+				} => {
+					if let Err(error) = construct_type(operation, store, &mut out, module, vars) {
+						return RunResult::Error(error);
+					}
 				}
 			}
 		}
 
 		let new_vars: Vec<_> = vars.keys().filter(|k| !old_vars.contains(k)).collect();
 
-		let residualise = out.len() != 0;
+		// Anything left for later in an earlier block still has to run, so the
+		// function can't be replaced by its result
+		residualised |= !out.is_empty();
+		let residualise = residualised;
 
 		for var_num in new_vars {
 			if let Some(Some(var)) = vars.get(var_num) {
 				if let Some(struc) = var.data.as_any().downcast_ref::<Struct>() {
-					let mut complete = true;
-					for field in &struc.fields {
-						if field.1.is_none() {
-							complete = false;
-							break;
-						}
-					}
-					if complete {
+					if struc.is_complete() {
 						out.insert(
 							0,
 							Statement {
@@ -179,7 +261,11 @@ pub fn evaluate(
 				}
 				None => panic!("undefined variable in guard"),
 			},
-			Terminal::TailCall { function, args } => {
+			Terminal::TailCall {
+				function,
+				args,
+				span,
+			} => {
 				let mut callable_var = None;
 				let ir: Option<Partial> = match function {
 					Callable::Var(var) => {
@@ -220,6 +306,10 @@ pub fn evaluate(
 										}
 										RunResult::Partial(p) => Some(p),
 										RunResult::Residualise => None,
+										RunResult::Error(mut error) => {
+											error.trace.push(span);
+											return RunResult::Error(error);
+										}
 									}
 								}
 							} else {
@@ -239,6 +329,7 @@ pub fn evaluate(
 						terminal: Terminal::TailCall {
 							function: Callable::Var(callable_var.unwrap()),
 							args,
+							span,
 						},
 					};
 
@@ -283,6 +374,7 @@ pub fn evaluate(
 								terminal: Terminal::TailCall {
 									function: Callable::Partial(p),
 									args,
+									span,
 								},
 							};
 						}
@@ -294,6 +386,10 @@ pub fn evaluate(
 								generics: generics.to_vec(),
 							});
 						}
+						RunResult::Error(mut error) => {
+							error.trace.push(span);
+							return RunResult::Error(error);
+						}
 					}
 
 					return RunResult::Partial(Partial {
@@ -302,6 +398,13 @@ pub fn evaluate(
 						generics: generics.to_vec(),
 					});
 				} else {
+					// A loop written as a tail call is only listed once
+					if tail_calls.back() != Some(&span) {
+						if tail_calls.len() == MAX_TAIL_CALLS {
+							tail_calls.pop_front();
+						}
+						tail_calls.push_back(span);
+					}
 					blocks = new.blocks;
 					last_block_num = block_num;
 					block_num = new.start_block;
@@ -322,17 +425,41 @@ pub fn evaluate(
 						last_block_num = block_num;
 						block_num = next_block;
 					} else {
-						panic!("Non-bool condition")
+						return RunResult::Error(not_bool());
 					}
 				}
 				Some(None) => {
+					// Each branch gets its own copy of the frame, so values one branch works
+					// out are still defined in the residual code of the other
+					let mut branches = [then, els].map(|start| {
+						let mut vars = vars.clone();
+						(
+							start,
+							evaluate(module, blocks.clone(), &mut vars, start, generics.clone()),
+						)
+					});
+					// Other errors only matter if the branch is taken, so unless both
+					// branches fail the failing one is left to fail at run time
+					let failed = branches
+						.iter()
+						.filter(|(_, branch)| matches!(branch, RunResult::Error(_)))
+						.count();
+					for (start, branch) in &mut branches {
+						if let RunResult::Error(error) = branch {
+							if error.check || failed == 2 {
+								return RunResult::Error(error.clone());
+							}
+							*branch = RunResult::Partial(Partial {
+								blocks: blocks.clone(),
+								start_block: *start,
+								generics: generics.clone(),
+							});
+						}
+					}
+					let [(_, then), (_, els)] = branches;
 					blocks[block_num] = Block {
 						statements: out,
-						terminal: Terminal::Branch {
-							cond: cond,
-							then: evaluate(module, blocks.clone(), vars, then, generics.clone()),
-							els: evaluate(module, blocks.clone(), vars, els, generics.clone()),
-						},
+						terminal: Terminal::Branch { cond, then, els },
 					};
 					return RunResult::Partial(Partial {
 						blocks,
@@ -345,13 +472,16 @@ pub fn evaluate(
 			Terminal::Branch { cond, then, els } => match vars.get(&cond) {
 				Some(Some(value)) => {
 					if let Some(cond_bool) = value.data.as_any().downcast_ref::<bool>() {
-						if *cond_bool {
-							return then.clone();
-						} else {
-							return els.clone();
-						}
+						let branch = if *cond_bool { then } else { els };
+						// The branch may still have work left from when the condition wasn't known
+						return match branch {
+							RunResult::Partial(p) => {
+								evaluate(module, p.blocks, vars, p.start_block, p.generics)
+							}
+							branch => branch,
+						};
 					} else {
-						panic!("Non-bool condition")
+						return RunResult::Error(not_bool());
 					}
 				}
 				Some(None) => {
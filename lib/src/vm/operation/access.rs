@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use crate::{
 	ir::{Operation, Statement},
 	typ::type_id,
-	value::Value,
+	value::{Value, structure::Struct},
+	vm::EvalError,
 };
 
 pub fn access(
@@ -12,7 +13,7 @@ pub fn access(
 	store: Option<usize>,
 	out: &mut Vec<Statement>,
 	vars: &mut HashMap<usize, Option<Value>>,
-) {
+) -> Result<(), EvalError> {
 	match vars.get_mut(&left) {
 		Some(None) => {
 			if let Some(store) = store {
@@ -24,13 +25,34 @@ pub fn access(
 			});
 		}
 		None => panic!("Undefined variable in left of index"),
+		Some(Some(structure))
+			if structure
+				.data
+				.as_any()
+				.downcast_ref::<Struct>()
+				.is_some_and(|structure| matches!(structure.fields.get(&right), Some(None))) =>
+		{
+			// The struct is only partially known and this field isn't
+			if let Some(store) = store {
+				vars.insert(store, None);
+			}
+			out.push(Statement {
+				store,
+				operation: Operation::Access(left, right),
+			});
+		}
 		Some(Some(left)) => {
 			let val = Value::new(right.clone(), type_id::String);
-			let v = left.data.index(&val);
+			let v = left.data.index(&val).map_err(|message| EvalError {
+				message,
+				trace: Vec::new(),
+				check: false,
+			})?;
 
 			if let Some(store) = store {
 				vars.insert(store, Some(v));
 			}
 		}
 	}
+	Ok(())
 }
@@ -1,32 +1,38 @@
 use std::collections::HashMap;
 
 use crate::{
+	error::Span,
 	ir::{Callable, Operation, Statement},
 	typ::Type,
 	value::Value,
-	vm::RunResult,
+	vm::{EvalError, RunResult},
 };
 
 pub fn call(
 	function: Callable,
 	args: Vec<usize>,
+	span: Span<'static>,
 	store: Option<usize>,
 	out: &mut Vec<Statement>,
 	module: &mut Vec<Type>,
 	vars: &mut HashMap<usize, Option<Value>>,
-) {
+) -> Result<(), EvalError> {
 	let mut function_value = match &function {
 		Callable::Partial(function) => Value::new(function.clone(), 0), // the type ID isn't used - this is a hack
 		Callable::Var(function_var) => match vars.get(&function_var) {
 			Some(None) => {
 				out.push(Statement {
 					store,
-					operation: Operation::Call { function, args },
+					operation: Operation::Call {
+						function,
+						args,
+						span,
+					},
 				});
 				if let Some(store) = store {
 					vars.insert(store, None);
 				}
-				return;
+				return Ok(());
 			}
 			None => panic!(
 				"Undefined variable {} in call, vars is {vars:?}",
@@ -48,6 +54,7 @@ pub fn call(
 				operation: Operation::Call {
 					function: Callable::Partial(p),
 					args,
+					span,
 				},
 			});
 			if let Some(store) = store {
@@ -57,13 +64,23 @@ pub fn call(
 		RunResult::Residualise => {
 			out.push(Statement {
 				store,
-				operation: Operation::Call { function, args },
+				operation: Operation::Call {
+					function,
+					args,
+					span,
+				},
 			});
 			if let Some(store) = store {
 				vars.insert(store, None);
 			}
 		}
+		RunResult::Error(mut error) => {
+			error.trace.push(span);
+			return Err(error);
+		}
 	}
+
+	Ok(())
 }
 
 pub fn prepare_args<'a>(
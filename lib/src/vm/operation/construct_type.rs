@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::{
+	ir::{Operation, Statement},
+	typ::{ConcreteType, Type, type_id},
+	value::{Value, primitive::TypeValue},
+	vm::EvalError,
+};
+
+/// Builds the type described by a `StructType`, `UnionType` or `TupleType` operation
+pub fn construct_type(
+	operation: Operation,
+	store: Option<usize>,
+	out: &mut Vec<Statement>,
+	module: &mut Vec<Type>,
+	vars: &mut HashMap<usize, Option<Value>>,
+) -> Result<(), EvalError> {
+	let Some(store) = store else {
+		return Ok(());
+	};
+
+	let parts: Vec<usize> = match &operation {
+		Operation::StructType(fields) => fields.values().cloned().collect(),
+		Operation::UnionType(a, b) => vec![*a, *b],
+		Operation::TupleType(elems) => elems.clone(),
+		_ => unreachable!(),
+	};
+
+	let mut known = true;
+	for part in &parts {
+		match vars.get(part) {
+			Some(Some(_)) => {}
+			Some(None) => known = false,
+			None => panic!("Undefined variable {part} in type"),
+		}
+	}
+	if !known {
+		vars.insert(store, None);
+		out.push(Statement {
+			store: Some(store),
+			operation,
+		});
+		return Ok(());
+	}
+
+	let type_of = |var: &usize| match vars[var]
+		.as_ref()
+		.and_then(|value| value.data.as_any().downcast_ref::<TypeValue>())
+	{
+		Some(TypeValue(typ)) => Ok(*typ),
+		None => Err(EvalError {
+			message: "a type is made of something that isn't a type".to_string(),
+			trace: Vec::new(),
+			check: false,
+		}),
+	};
+
+	let typ = match &operation {
+		Operation::StructType(fields) => Type::Concrete(ConcreteType::Struct(
+			fields
+				.iter()
+				.map(|(name, var)| Ok((name.clone(), type_of(var)?)))
+				.collect::<Result<_, _>>()?,
+		)),
+		Operation::UnionType(a, b) => Type::Union(type_of(a)?, type_of(b)?),
+		Operation::TupleType(elems) => Type::Concrete(ConcreteType::Tuple(
+			elems.iter().map(type_of).collect::<Result<_, _>>()?,
+		)),
+		_ => unreachable!(),
+	};
+
+	let typ = match module.iter().position(|old_typ| *old_typ == typ) {
+		Some(id) => id,
+		None => {
+			module.push(typ);
+			module.len() - 1
+		}
+	};
+
+	vars.insert(store, Some(Value::new(TypeValue(typ), type_id::Type)));
+	Ok(())
+}
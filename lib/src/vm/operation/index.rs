@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use crate::{
 	ir::{Operation, Statement},
 	value::Value,
+	vm::EvalError,
 };
 
 pub fn index(
@@ -11,7 +12,7 @@ pub fn index(
 	store: Option<usize>,
 	out: &mut Vec<Statement>,
 	vars: &mut HashMap<usize, Option<Value>>,
-) {
+) -> Result<(), EvalError> {
 	let r = vars.get(&rightn).cloned();
 	match vars.get_mut(&leftn) {
 		Some(None) => {
@@ -26,6 +27,9 @@ pub fn index(
 		None => panic!("Undefined variable in left of index"),
 		Some(Some(left)) => match r {
 			Some(None) => {
+				if let Some(store) = store {
+					vars.insert(store, None);
+				}
 				out.push(Statement {
 					store,
 					operation: Operation::Index(leftn, rightn),
@@ -33,7 +37,11 @@ pub fn index(
 			}
 			None => panic!("Undefined variable in left of index"),
 			Some(Some(right)) => {
-				let v = left.data.index(&right);
+				let v = left.data.index(&right).map_err(|message| EvalError {
+					message,
+					trace: Vec::new(),
+					check: false,
+				})?;
 
 				if let Some(store) = store {
 					vars.insert(store, Some(v));
@@ -41,4 +49,5 @@ pub fn index(
 			}
 		},
 	}
+	Ok(())
 }
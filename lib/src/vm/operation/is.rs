@@ -2,26 +2,22 @@ use std::collections::HashMap;
 
 use crate::{
 	ir::{Operation, Statement},
-	typ::Type,
+	typ::{Type, type_id},
 	value::Value,
 };
 
 pub fn is(
 	value: usize,
 	typ: usize,
-	_module: &mut Vec<Type>,
+	module: &[Type],
 	vars: &mut HashMap<usize, Option<Value>>,
 	out: &mut Vec<Statement>,
 	store: Option<usize>,
 ) {
 	if let Some(store) = store {
-		if let Some(_value) = &vars[&value] {
-			// if module.compatible(value.typ, typ, 0).unwrap() {
-			// 	vars.insert(store, Some(Value::new(true, type_id::bool)));
-			// } else {
-			// 	vars.insert(store, Some(Value::new(false, type_id::bool)));
-			// }
-			todo!("re-add is with module separated from type info")
+		if let Some(value) = &vars[&value] {
+			let is = is_member(module, value.typ, typ);
+			vars.insert(store, Some(Value::new(is, type_id::bool)));
 		} else {
 			vars.insert(store, None);
 			out.push(Statement {
@@ -31,3 +27,10 @@ pub fn is(
 		}
 	}
 }
+
+/// Whether a value of type `actual` is `typ` or one of the members of the union `typ`
+pub fn is_member(module: &[Type], actual: usize, typ: usize) -> bool {
+	actual == typ
+		|| matches!(module.get(typ), Some(Type::Union(a, b))
+			if is_member(module, actual, *a) || is_member(module, actual, *b))
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
 	ir::{Operation, Statement},
-	value::Value,
+	value::{Value, structure::Struct},
 };
 
 pub fn load_local(
@@ -14,6 +14,18 @@ pub fn load_local(
 	if let Some(store) = store {
 		match vars.get(&src) {
 			Some(Some(value)) => {
+				// Partially known structs still need their copy to exist at run time
+				if value
+					.data
+					.as_any()
+					.downcast_ref::<Struct>()
+					.is_some_and(|structure| !structure.is_complete())
+				{
+					out.push(Statement {
+						store: Some(store),
+						operation: Operation::LoadLocal { src },
+					});
+				}
 				vars.insert(store, Some(value.clone()));
 			}
 			Some(None) => {
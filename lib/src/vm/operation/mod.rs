@@ -21,3 +21,6 @@ pub use guard_phi::*;
 
 mod is;
 pub use is::*;
+
+mod construct_type;
+pub use construct_type::*;
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
 	ir::{Operation, Statement},
-	value::Value,
+	value::{Value, structure::Struct},
 };
 
 pub fn phi(
@@ -20,11 +20,25 @@ pub fn phi(
 			"Phi evaluated to undefined variable, must have forgot to store the result of the block",
 		);
 
-		if var.is_none() {
-			out.push(Statement {
+		match var {
+			None => out.push(Statement {
 				store: Some(store),
 				operation: Operation::Phi { block_to_var },
-			});
+			}),
+			// Like locals, partially known structs still need their copy at run time
+			Some(value)
+				if value
+					.data
+					.as_any()
+					.downcast_ref::<Struct>()
+					.is_some_and(|structure| !structure.is_complete()) =>
+			{
+				out.push(Statement {
+					store: Some(store),
+					operation: Operation::LoadLocal { src: *var_num },
+				})
+			}
+			Some(_) => {}
 		}
 
 		vars.insert(
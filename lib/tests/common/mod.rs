@@ -0,0 +1,45 @@
+//! Shared by the integration tests, each of which uses only some of it
+#![allow(dead_code)]
+
+use preval_lib::{
+	project::{Manifest, Project, ProjectError},
+	typ::Type,
+	vm::RunResult,
+};
+
+/// Uses most of the language, so compiling it gives functions, partials, branches,
+/// structs, lists and types to write out
+pub const PROGRAM: &str = r#"use std::{io, strings, collections};
+
+struct Point { x: usize, label: String }
+
+fn describe(p: Point): String { p.label }
+
+fn main(compile_io: IO, io: IO) {
+    let line = io::input(io);
+    let p = Point { x: strings::length(line), label: line };
+    let items = collections::push(collections::new::<String>(), "item");
+    io::print(io, if strings::is_empty(line) { "empty" } else { describe(p) });
+    io::print(io, strings::from_usize(collections::length(items)));
+    io::print(io, type_name(p));
+}
+"#;
+
+/// Compiles `source` as the `main.pv` of a project in a directory of its own
+pub fn compile(name: &str, source: &str) -> (RunResult, Vec<Type>) {
+	try_compile(name, source).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Like [`compile`], but gives back why compiling failed
+pub fn try_compile(name: &str, source: &str) -> Result<(RunResult, Vec<Type>), ProjectError> {
+	let dir = std::env::temp_dir().join(format!("preval-{name}-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).expect("temporary directory is writable");
+	std::fs::write(dir.join("main.pv"), source).expect("temporary directory is writable");
+	let manifest = Manifest {
+		roots: vec![dir.clone()],
+		..Manifest::default()
+	};
+	let compiled = Project::load(&manifest).and_then(|project| project.compile());
+	let _ = std::fs::remove_dir_all(&dir);
+	compiled
+}
@@ -0,0 +1,170 @@
+//! How partially evaluating blocks treats `is`, work left for run time in earlier
+//! blocks, branches whose condition becomes known, and errors in tail calls
+
+use std::{borrow::Cow, collections::HashMap};
+
+use preval_lib::{
+	error::Span,
+	ir::{self, Block, Callable, Operation, Partial, Statement, Terminal},
+	typ::{ConcreteType, IntegerSize, Type},
+	value::Value,
+	vm::{RunResult, evaluate},
+};
+
+/// `usize`, `bool`, the union of them, and `struct {x: usize}`
+fn module() -> Vec<Type> {
+	vec![
+		Type::Concrete(ConcreteType::Integer {
+			size: IntegerSize::Size,
+			signed: false,
+		}),
+		Type::Concrete(ConcreteType::Bool),
+		Type::Union(0, 1),
+		Type::Concrete(ConcreteType::Struct(HashMap::from([("x".to_string(), 0)]))),
+	]
+}
+
+fn literal(store: usize, value: Value) -> Statement {
+	Statement {
+		store: Some(store),
+		operation: Operation::LoadLiteral(value),
+	}
+}
+
+fn run(blocks: Vec<Block>, vars: &[(usize, Option<Value>)]) -> RunResult {
+	let mut vars = vars.iter().cloned().collect::<HashMap<_, _>>();
+	evaluate(&mut module(), blocks, &mut vars, 0, vec![])
+}
+
+#[test]
+fn is_checks_union_members() {
+	let is = |typ| {
+		run(
+			vec![Block {
+				statements: vec![
+					literal(1, Value::new(1usize, 0)),
+					Statement {
+						store: Some(2),
+						operation: Operation::Is { value: 1, typ },
+					},
+				],
+				terminal: Terminal::Return(2),
+			}],
+			&[],
+		)
+	};
+	assert_eq!(is(2), RunResult::Concrete(Value::new(true, 1)));
+	assert_eq!(is(1), RunResult::Concrete(Value::new(false, 1)));
+}
+
+#[test]
+fn keeps_work_left_in_earlier_blocks() {
+	// Calling the unknown `v0` is left for run time, so the function can't be replaced
+	// by the result its last block works out
+	let blocks = vec![
+		Block {
+			statements: vec![Statement {
+				store: None,
+				operation: Operation::Call {
+					function: Callable::Var(0),
+					args: Vec::new(),
+					span: Span {
+						file: Cow::Borrowed("main.pv"),
+						index: 0,
+					},
+				},
+			}],
+			terminal: Terminal::Jump(1),
+		},
+		Block {
+			statements: vec![literal(1, Value::new(1usize, 0))],
+			terminal: Terminal::Return(1),
+		},
+	];
+	assert!(matches!(run(blocks, &[(0, None)]), RunResult::Partial(_)));
+}
+
+#[test]
+fn finishes_branches_once_their_condition_is_known() {
+	let then = Partial {
+		blocks: vec![Block {
+			statements: vec![literal(1, Value::new(1usize, 0))],
+			terminal: Terminal::Return(1),
+		}],
+		start_block: 0,
+		generics: Vec::new(),
+	};
+	let blocks = vec![Block {
+		statements: Vec::new(),
+		terminal: Terminal::Branch {
+			cond: 0,
+			then: RunResult::Partial(then),
+			els: RunResult::Concrete(Value::new(2usize, 0)),
+		},
+	}];
+	assert_eq!(
+		run(blocks, &[(0, Some(Value::new(true, 1)))]),
+		RunResult::Concrete(Value::new(1usize, 0))
+	);
+}
+
+/// Evaluates blocks taking the unknown arguments `v0` and `v1`, and checks the residual
+/// code only uses variables it defines
+fn residual(text: &str) -> Result<(), ir::IrError> {
+	let blocks = ir::parse_blocks(text).unwrap_or_else(|error| panic!("{error}"));
+	let result = run(blocks, &[(0, None), (1, None)]);
+	ir::verify_result(&result, 2, &module())
+}
+
+#[test]
+fn branches_define_what_they_work_out() {
+	// Both sides work out `v2`, and each has to keep its own definition of it
+	let text = "b0:
+	if v0 then b1 else b2
+b1:
+	jump b3
+b2:
+	jump b3
+b3:
+	v2 = const t0 1
+	call v1(v2) @ \"main.pv\":0
+	return v2
+";
+	assert_eq!(residual(text), Ok(()));
+}
+
+#[test]
+fn phis_keep_partially_known_structs() {
+	let text = "b0:
+	if v0 then b1 else b2
+b1:
+	v2 = struct t3 {x: v1}
+	jump b3
+b2:
+	v3 = struct t3 {x: v1}
+	jump b3
+b3:
+	v4 = phi [b1: v2, b2: v3]
+	return v4
+";
+	assert_eq!(residual(text), Ok(()));
+}
+
+#[test]
+fn traces_tail_calls() {
+	// The tail called function calls the number it's given
+	let text = "b0:
+	v2 = const t0 1
+	tail call partial start b0 {
+		b0:
+			v1 = call v0() @ \"main.pv\":20
+			return v1
+	}(v2) @ \"main.pv\":10
+";
+	let blocks = ir::parse_blocks(text).unwrap_or_else(|error| panic!("{error}"));
+	let RunResult::Error(error) = run(blocks, &[]) else {
+		panic!("calling a number didn't fail");
+	};
+	let indexes: Vec<_> = error.trace.iter().map(|span| span.index).collect();
+	assert_eq!(indexes, [20, 10]);
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::{PROGRAM, compile};
+use preval_lib::ir::{self, IrError, IrErrorKind};
+
+#[test]
+fn print_parse_round_trip() {
+	let (program, types) = compile("ir-round-trip", PROGRAM);
+	let text = ir::print_program(&program, &types);
+	let parsed = ir::parse_program(&text).unwrap_or_else(|error| panic!("{error}\n{text}"));
+	assert_eq!(parsed, (program, types));
+}
+
+#[test]
+fn blocks_round_trip() {
+	let text = "b0:
+	v1 = const t0 1
+	if v1 then b1 else b2
+b1:
+	v2 = v0[v1]
+	jump b3
+b2:
+	v3 = v0.x
+	jump b3
+b3:
+	v4 = phi [b1: v2, b2: v3]
+	return v4
+";
+	let blocks = ir::parse_blocks(text).unwrap_or_else(|error| panic!("{error}"));
+	assert_eq!(ir::print_blocks(&blocks), text);
+}
+
+#[test]
+fn parse_error_position() {
+	let error = ir::parse_blocks("b0:\n\tv1 = const t0 1\n\treturn x1\n").unwrap_err();
+	assert_eq!((error.line, error.column), (3, 9));
+	assert_eq!(error.message, "expected a variable, found `x1`");
+}
+
+/// Verifies blocks taking one argument, `v0`
+fn verify(text: &str) -> Result<(), IrError> {
+	let blocks = ir::parse_blocks(text).unwrap_or_else(|error| panic!("{error}"));
+	ir::verify(&blocks, 0, 1, &[])
+}
+
+#[test]
+fn verify_accepts_phis() {
+	let text = "b0:
+	if v0 then b1 else b2
+b1:
+	v1 = v0
+	jump b3
+b2:
+	v2 = v0
+	jump b3
+b3:
+	v3 = phi [b1: v1, b2: v2]
+	return v3
+b4:
+	return v9
+";
+	assert_eq!(verify(text), Ok(()));
+}
+
+#[test]
+fn verify_rejects_undefined_variables() {
+	assert_eq!(
+		verify("b0:\n\tv1 = v0\n\treturn v2\n"),
+		Err(IrError {
+			block: 0,
+			kind: IrErrorKind::UndefinedVariable(2)
+		})
+	);
+	let only_one_side = "b0:
+	if v0 then b1 else b2
+b1:
+	v1 = v0
+	jump b3
+b2:
+	jump b3
+b3:
+	return v1
+";
+	assert_eq!(
+		verify(only_one_side),
+		Err(IrError {
+			block: 3,
+			kind: IrErrorKind::UndefinedVariable(1)
+		})
+	);
+}
+
+#[test]
+fn verify_rejects_missing_phi_sources() {
+	let text = "b0:
+	if v0 then b1 else b2
+b1:
+	jump b3
+b2:
+	jump b3
+b3:
+	v1 = phi [b1: v0]
+	return v1
+";
+	assert_eq!(
+		verify(text),
+		Err(IrError {
+			block: 3,
+			kind: IrErrorKind::MissingPhiSource(2)
+		})
+	);
+}
+
+#[test]
+fn verify_rejects_missing_blocks() {
+	assert_eq!(
+		verify("b0:\n\tjump b5\n"),
+		Err(IrError {
+			block: 0,
+			kind: IrErrorKind::UnknownBlock(5)
+		})
+	);
+	assert_eq!(
+		verify("b0:\n\tif v0 then b1 else b2\nb1:\n\treturn v0\n"),
+		Err(IrError {
+			block: 0,
+			kind: IrErrorKind::UnknownBlock(2)
+		})
+	);
+}
+
+#[test]
+fn verify_rejects_unknown_types() {
+	assert_eq!(
+		verify("b0:\n\tv1 = fn t3\n\treturn v1\n"),
+		Err(IrError {
+			block: 0,
+			kind: IrErrorKind::UnknownType(3)
+		})
+	);
+}
+
+#[test]
+fn verify_accepts_guard_phis() {
+	let guarded = "b0:
+	guard v0 then b1 continue b2
+b1:
+	v1 = v0
+	jump b2
+b2:
+	v2 = guard_phi b1: v1
+	return v2
+";
+	assert_eq!(verify(guarded), Ok(()));
+	// Evaluating the guard leaves a jump through its body
+	let evaluated = "b0:
+	jump b1
+b1:
+	v1 = v0
+	jump b2
+b2:
+	v2 = guard_phi b1: v1
+	return v2
+";
+	assert_eq!(verify(evaluated), Ok(()));
+}
+
+#[test]
+fn verify_rejects_guard_phis_of_other_blocks() {
+	// `b1` jumps to the phi, but so does `b2`, and neither is a guard's body
+	let join = "b0:
+	if v0 then b1 else b2
+b1:
+	v1 = v0
+	jump b3
+b2:
+	jump b3
+b3:
+	v2 = guard_phi b1: v1
+	return v2
+";
+	assert_eq!(
+		verify(join),
+		Err(IrError {
+			block: 3,
+			kind: IrErrorKind::NotAGuardBody(1)
+		})
+	);
+	assert_eq!(
+		verify("b0:\n\tjump b1\nb1:\n\tv1 = guard_phi b5: v0\n\treturn v1\n"),
+		Err(IrError {
+			block: 1,
+			kind: IrErrorKind::NotAGuardBody(5)
+		})
+	);
+}
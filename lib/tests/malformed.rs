@@ -0,0 +1,101 @@
+//! Programs that pass verification but use values as the wrong kind of thing, which
+//! running has to report instead of panicking
+
+use preval_lib::{
+	ir,
+	project::{ProjectError, run},
+	pvc,
+};
+
+const TYPES: &str = "t0 = usize
+t1 = bool
+t2 = ()
+t3 = String
+t4 = IO
+t5 = type
+t6 = struct {x: t0}
+";
+
+/// Runs a program after writing and reading it back, as `preval run` would
+fn run_program(program: &str) -> Result<(), ProjectError> {
+	let (program, types) = ir::parse_program(&format!("{TYPES}\nprogram = {program}"))
+		.unwrap_or_else(|error| panic!("{error}"));
+	let (program, types) = pvc::read(&pvc::write(&program, &types)).expect("program verifies");
+	run(program, types)
+}
+
+fn expect_error(program: &str, expected: &str) {
+	match run_program(program) {
+		Err(ProjectError::Eval { message, .. }) => assert_eq!(message, expected),
+		Err(error) => panic!("expected `{expected}`, got {error}"),
+		Ok(()) => panic!("expected `{expected}`, but it ran"),
+	}
+}
+
+#[test]
+fn indexing_a_type() {
+	expect_error(
+		"partial start b0 {
+	b0:
+		v2 = const t0 0
+		v3 = tuple_type ()
+		v4 = v3[v2]
+		return v4
+}",
+		"a Type can't be indexed",
+	);
+}
+
+#[test]
+fn accessing_a_missing_field() {
+	expect_error(
+		"partial start b0 {
+	b0:
+		v2 = const t0 1
+		v3 = struct t6 {x: v2}
+		v4 = v3.y
+		return v4
+}",
+		"the struct has no field `y`",
+	);
+}
+
+#[test]
+fn calling_a_number() {
+	expect_error(
+		"partial start b0 {
+	b0:
+		v2 = const t0 1
+		v3 = call v2(v0) @ \"main.pv\":0
+		return v3
+}",
+		"a USize can't be called",
+	);
+}
+
+#[test]
+fn branching_on_a_number() {
+	expect_error(
+		"partial start b0 {
+	b0:
+		v2 = const t0 1
+		if v2 then b1 else b1
+	b1:
+		return v2
+}",
+		"a condition isn't a `bool`",
+	);
+}
+
+#[test]
+fn building_a_type_from_a_number() {
+	expect_error(
+		"partial start b0 {
+	b0:
+		v2 = const t0 1
+		v3 = tuple_type (v2)
+		return v3
+}",
+		"a type is made of something that isn't a type",
+	);
+}
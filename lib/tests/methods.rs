@@ -0,0 +1,59 @@
+//! Method calls, which need the static type of their receiver
+
+mod common;
+
+use common::try_compile;
+
+fn error(name: &str, source: &str) -> String {
+	match try_compile(name, source) {
+		Ok(_) => panic!("compiled"),
+		Err(error) => error.to_string(),
+	}
+}
+
+#[test]
+fn receivers_can_be_any_local() {
+	let source = r#"use std::{io, strings};
+
+struct Counter { count: usize }
+
+impl Counter {
+    fn label(self): String { strings::from_usize(self.count) }
+}
+
+fn shout(text: String): String { text }
+
+fn main(compile_io: IO, io: IO) {
+    let counter = Counter { count: 2 };
+    let text = counter.label();
+    io::print(io, text.shout());
+}
+"#;
+	assert!(try_compile("methods-locals", source).is_ok());
+}
+
+#[test]
+fn self_needs_a_type_outside_impls() {
+	let source = r#"fn label(self): usize { 1 }
+
+fn main(compile_io: IO, io: IO) {}
+"#;
+	assert!(error("methods-untyped-self", source).contains("arguments need a type"));
+}
+
+#[test]
+fn receivers_that_cant_be_typed() {
+	// Indexing has no static type, which has to be reported rather than looking
+	// `first` up as a type
+	let source = r#"use std::{io, collections};
+
+fn shout(text: String): String { text }
+
+fn main(compile_io: IO, io: IO) {
+    let items = collections::push(collections::new::<String>(), "item");
+    let first = items[0];
+    io::print(io, first.shout());
+}
+"#;
+	assert!(error("methods-untyped", source).contains("indexing has no static type"));
+}
@@ -0,0 +1,84 @@
+//! Resolving dependencies from a registry and from paths
+
+use std::{fs, path::Path};
+
+use preval_lib::project::{Manifest, ProjectError, resolve};
+
+/// Writes a package manifest, with `extra` added to the end of it
+fn package(dir: &Path, name: &str, version: &str, extra: &str) {
+	fs::create_dir_all(dir).expect("temporary directory is writable");
+	fs::write(
+		dir.join("preval.toml"),
+		format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n{extra}"),
+	)
+	.expect("temporary directory is writable");
+}
+
+/// A project depending on `dependencies`, with a registry holding `a` 1.0.0, 1.5.0
+/// and 2.0.0, and `b` 1.0.0 which requires `a` ^1
+fn resolve_project(name: &str, dependencies: &str) -> Result<Vec<(String, String)>, ProjectError> {
+	let dir = std::env::temp_dir().join(format!("preval-{name}-{}", std::process::id()));
+	let registry = dir.join("registry");
+	for version in ["1.0.0", "1.5.0", "2.0.0"] {
+		package(&registry.join("a").join(version), "a", version, "");
+	}
+	package(
+		&registry.join("b/1.0.0"),
+		"b",
+		"1.0.0",
+		"[dependencies]\na = \"^1\"\n",
+	);
+	fs::create_dir_all(dir.join("app/modules")).expect("temporary directory is writable");
+	package(
+		&dir.join("app"),
+		"app",
+		"0.1.0",
+		&format!("registry = \"../registry\"\n[dependencies]\n{dependencies}"),
+	);
+
+	let resolved =
+		Manifest::read(&dir.join("app/preval.toml")).and_then(|manifest| resolve(&manifest));
+	let _ = fs::remove_dir_all(&dir);
+	resolved.map(|packages| {
+		packages
+			.into_iter()
+			.map(|(name, package)| (name, package.manifest.version.to_string()))
+			.collect()
+	})
+}
+
+#[test]
+fn newest_version_satisfying_every_requirement() {
+	// `a` would be 2.0.0 on its own, but `b` needs a 1.x
+	assert_eq!(
+		resolve_project("packages-shared", "a = \">=1\"\nb = \"1\"\n").unwrap(),
+		[
+			("a".to_string(), "1.5.0".to_string()),
+			("b".to_string(), "1.0.0".to_string())
+		]
+	);
+}
+
+#[test]
+fn requirements_nothing_satisfies() {
+	assert!(matches!(
+		resolve_project("packages-unsatisfiable", "a = \">=2\"\nb = \"1\"\n"),
+		Err(ProjectError::UnknownDependency { name, .. }) if name == "a"
+	));
+}
+
+#[test]
+fn names_of_modules() {
+	for (name, dependencies) in [
+		("packages-std", "std = { path = \"../registry/a/1.0.0\" }\n"),
+		("packages-module", "modules = \"1\"\n"),
+	] {
+		assert!(
+			matches!(
+				resolve_project(name, dependencies),
+				Err(ProjectError::PackageNameTaken(_))
+			),
+			"{dependencies}"
+		);
+	}
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use std::{panic, thread};
+
+use common::{PROGRAM, compile};
+use preval_lib::{
+	ir::{Block, Operation, Partial, Statement, Terminal},
+	pvc::{self, PvcErrorKind},
+	typ::{ConcreteType, Implementation, IntegerSize, Type},
+	value::{Value, native::NativeFunction},
+	vm::RunResult,
+};
+
+#[test]
+fn round_trip() {
+	let (program, types) = compile("pvc-round-trip", PROGRAM);
+	let bytes = pvc::write(&program, &types);
+	assert_eq!(pvc::read(&bytes), Ok((program, types)));
+}
+
+#[test]
+fn same_program_same_bytes() {
+	let (program, types) = compile("pvc-same-bytes", PROGRAM);
+	let bytes = pvc::write(&program, &types);
+	let (read, read_types) = pvc::read(&bytes).expect("program reads back");
+	assert_eq!(pvc::write(&read, &read_types), bytes);
+}
+
+#[test]
+fn truncated() {
+	let (program, types) = compile("pvc-truncated", PROGRAM);
+	let bytes = pvc::write(&program, &types);
+	for len in 0..bytes.len() {
+		assert!(
+			pvc::read(&bytes[..len]).is_err(),
+			"read the first {len} of {} bytes",
+			bytes.len()
+		);
+	}
+	assert_eq!(
+		pvc::read(&bytes[..bytes.len() - 1])
+			.map(|_| ())
+			.unwrap_err()
+			.kind,
+		PvcErrorKind::Truncated
+	);
+}
+
+#[test]
+fn corrupt() {
+	let (program, types) = compile("pvc-corrupt", PROGRAM);
+	let bytes = pvc::write(&program, &types);
+	// Changed bytes can still make a valid program, but reading must never panic
+	for offset in 0..bytes.len() {
+		for change in [1, 0x80, 0xff] {
+			let mut corrupt = bytes.clone();
+			corrupt[offset] ^= change;
+			let read = panic::catch_unwind(|| pvc::read(&corrupt).map(|_| ()));
+			assert!(
+				read.is_ok(),
+				"reading panicked with byte {offset} ^ {change:#x}"
+			);
+		}
+	}
+}
+
+#[test]
+fn header() {
+	let (program, types) = compile("pvc-header", PROGRAM);
+	let mut bytes = pvc::write(&program, &types);
+	let kind = |bytes: &[u8]| pvc::read(bytes).map(|_| ()).unwrap_err().kind;
+
+	assert_eq!(kind(b"#!/bin/sh"), PvcErrorKind::NotAProgram);
+	bytes[4..8].copy_from_slice(&(pvc::VERSION + 1).to_le_bytes());
+	assert_eq!(
+		kind(&bytes),
+		PvcErrorKind::UnsupportedVersion(pvc::VERSION + 1)
+	);
+}
+
+#[test]
+fn trailing_bytes() {
+	let (program, types) = compile("pvc-trailing", PROGRAM);
+	let mut bytes = pvc::write(&program, &types);
+	bytes.extend([0, 0]);
+	assert_eq!(
+		pvc::read(&bytes).map(|_| ()).unwrap_err().kind,
+		PvcErrorKind::TrailingBytes(2)
+	);
+}
+
+#[test]
+fn native_library_paths() {
+	// Where a library was found when compiling is kept, so running doesn't need the project
+	let library = std::env::temp_dir().join(format!("preval-pvc-native-{}.so", std::process::id()));
+	std::fs::write(&library, []).expect("temporary directory is writable");
+	let mut native = NativeFunction::new("t".to_string(), "f".to_string());
+	native.path = Some(library.clone());
+	let types = vec![
+		Type::Concrete(ConcreteType::Integer {
+			size: IntegerSize::Size,
+			signed: false,
+		}),
+		Type::Concrete(ConcreteType::Function(
+			Vec::new(),
+			0,
+			Implementation::Native(native),
+		)),
+	];
+	let program = RunResult::Concrete(Value::new(0usize, 0));
+	let read = pvc::read(&pvc::write(&program, &types));
+	let expected = library.canonicalize().ok();
+	let _ = std::fs::remove_file(&library);
+
+	let (_, types) = read.expect("program reads back");
+	let Type::Concrete(ConcreteType::Function(_, _, Implementation::Native(native))) = &types[1]
+	else {
+		panic!("expected a native function, got {:?}", types[1]);
+	};
+	assert_eq!(native.path, expected);
+}
+
+/// A program that branches `depth` times, each branch in the `then` side of the last
+fn nested_branches(depth: usize) -> Vec<u8> {
+	// Writing and dropping the program recurses as deeply as it nests
+	thread::Builder::new()
+		.stack_size(1 << 30)
+		.spawn(move || {
+			let types = vec![
+				Type::Concrete(ConcreteType::Integer {
+					size: IntegerSize::Size,
+					signed: false,
+				}),
+				Type::Concrete(ConcreteType::Bool),
+			];
+			let mut program = RunResult::Concrete(Value::new(0usize, 0));
+			for _ in 0..depth {
+				program = RunResult::Partial(Partial {
+					blocks: vec![Block {
+						statements: vec![Statement {
+							store: Some(0),
+							operation: Operation::LoadLiteral(Value::new(true, 1)),
+						}],
+						terminal: Terminal::Branch {
+							cond: 0,
+							then: program,
+							els: RunResult::Concrete(Value::new(0usize, 0)),
+						},
+					}],
+					start_block: 0,
+					generics: Vec::new(),
+				});
+			}
+			pvc::write(&program, &types)
+		})
+		.unwrap()
+		.join()
+		.unwrap()
+}
+
+#[test]
+fn deeply_nested() {
+	// Reading gets the stack `preval` runs on, rather than a test thread's smaller one
+	thread::Builder::new()
+		.stack_size(8 << 20)
+		.spawn(|| {
+			assert!(pvc::read(&nested_branches(200)).is_ok());
+			assert_eq!(
+				pvc::read(&nested_branches(3000))
+					.map(|_| ())
+					.unwrap_err()
+					.kind,
+				PvcErrorKind::TooDeep
+			);
+		})
+		.unwrap()
+		.join()
+		.unwrap();
+}
@@ -3,14 +3,114 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Version of [`RawAPI`], raised whenever its layout changes
+ */
+#define PREVAL_API_VERSION 4
+
+/**
+ * Returned by `value_type` when it fails, as no type has this id
+ */
+#define PREVAL_NO_TYPE ~0
+
+/**
+ * What kind of value a `Value` is
+ */
+typedef enum ValueKind {
+  ValueKind_USize,
+  ValueKind_Bool,
+  ValueKind_String,
+  ValueKind_Tuple,
+  ValueKind_Struct,
+  ValueKind_List,
+  /**
+   * Functions, types and other values natives can only pass along
+   */
+  ValueKind_Other,
+} ValueKind;
+
 typedef struct Value Value;
 
+/**
+ * Functions given to native functions for working with values.
+ *
+ * Values returned as `*mut Value` are owned by the caller, and are given up by
+ * returning them or passing them to a function that takes ownership.
+ */
 typedef struct RawAPI_Value {
+  /**
+   * Always [`PREVAL_API_VERSION`]
+   */
+  uint32_t version;
   void (*drop_value)(struct Value*);
+  struct Value *(*clone_value)(const struct Value*);
+  enum ValueKind (*value_kind)(const struct Value*);
+  /**
+   * Type id of a value, which is always a concrete type rather than a union, or
+   * [`PREVAL_NO_TYPE`] if it fails
+   */
+  uintptr_t (*value_type)(const struct Value*);
+  /**
+   * Whether a type is the same as another, or one of the members of a union
+   */
+  bool (*type_is)(uintptr_t, uintptr_t);
+  uintptr_t (*usize_value)(const struct Value*);
+  struct Value *(*new_usize_value)(uintptr_t);
+  bool (*bool_value)(const struct Value*);
+  struct Value *(*new_bool_value)(bool);
   uintptr_t (*string_value_length)(const struct Value*);
   const uint8_t *(*string_value_start)(const struct Value*);
-  struct Value *(*new_tuple_value)(void);
   struct Value *(*new_string_value)(const uint8_t*, uintptr_t);
+  struct Value *(*new_tuple_value)(void);
+  /**
+   * Makes a struct of a type id with no fields, which are then added with `set_struct_field`
+   */
+  struct Value *(*new_struct_value)(uintptr_t);
+  /**
+   * Copies a field by name, or returns null if the struct doesn't have it or it isn't known
+   */
+  struct Value *(*struct_field)(const struct Value*, const uint8_t*, uintptr_t);
+  /**
+   * Sets a field by name, taking ownership of the new value
+   */
+  void (*set_struct_field)(struct Value*, const uint8_t*, uintptr_t, struct Value*);
+  /**
+   * Makes an empty list of a type id
+   */
+  struct Value *(*new_list_value)(uintptr_t);
+  uintptr_t (*list_length)(const struct Value*);
+  /**
+   * Copies an element, or returns null if the index is out of range
+   */
+  struct Value *(*list_get)(const struct Value*, uintptr_t);
+  /**
+   * Appends an element, taking ownership of it
+   */
+  void (*list_push)(struct Value*, struct Value*);
+  /**
+   * Makes the call fail with a message instead of returning, so natives can
+   * `return api->raise_error(message, length);`. Always returns null. If the call has
+   * already failed, like from misusing the API, the earlier failure is kept.
+   */
+  struct Value *(*raise_error)(const uint8_t*, uintptr_t);
+  /**
+   * Calls a function value with borrowed arguments, which are null when unknown.
+   * Returns null if the result is only known at run time, in which case the native
+   * should return null too, or if the function fails, which fails the native call.
+   */
+  struct Value *(*call)(const struct Value*, uintptr_t, const struct Value*const *);
 } RawAPI_Value;
 
 typedef struct RawAPI_Value API;
+
+/**
+ * Type of `preval_api_version`, which returns the [`PREVAL_API_VERSION`] the library
+ * was built with. Libraries without it, or built with another version, aren't loaded.
+ */
+typedef uint32_t (*ApiVersion)(void);
+
+/**
+ * Type of `preval_metadata`, which returns a null-terminated string declaring the
+ * library's functions in Preval, like `fn print(io: IO, message: String);`
+ */
+typedef const char *(*Metadata)(void);
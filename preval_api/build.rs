@@ -1,4 +1,4 @@
-use cbindgen::{Config, ExportConfig};
+use cbindgen::{Config, EnumConfig, ExportConfig};
 use std::env;
 
 fn main() {
@@ -10,13 +10,23 @@ fn main() {
 		language: cbindgen::Language::C,
 		cpp_compat: true,
 		export: ExportConfig {
-			include: vec!["API".to_string()],
+			include: vec![
+				"API".to_string(),
+				"ApiVersion".to_string(),
+				"Metadata".to_string(),
+			],
+			..Default::default()
+		},
+		// Variants like `String` would clash with other C names
+		enumeration: EnumConfig {
+			prefix_with_name: true,
 			..Default::default()
 		},
 		..Default::default()
 	};
 
-	cbindgen::generate_with_config(&crate_dir, config)
-		.unwrap()
-		.write_to_file(&output_file);
+	let bindings = cbindgen::generate_with_config(&crate_dir, config).unwrap();
+	bindings.write_to_file(&output_file);
+	// The example native library builds against its own copy
+	bindings.write_to_file(crate_dir + "/../stdlib/preval.h");
 }
@@ -1,14 +1,92 @@
-use std::marker::PhantomData;
+use std::{ffi::c_char, marker::PhantomData};
 
+/// Version of [`RawAPI`], raised whenever its layout changes
+pub const PREVAL_API_VERSION: u32 = 4;
+
+/// Returned by `value_type` when it fails, as no type has this id
+pub const PREVAL_NO_TYPE: usize = !0;
+
+/// What kind of value a `Value` is
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+	USize,
+	Bool,
+	String,
+	Tuple,
+	Struct,
+	List,
+	/// Functions, types and other values natives can only pass along
+	Other,
+}
+
+/// Functions given to native functions for working with values.
+///
+/// Values returned as `*mut Value` are owned by the caller, and are given up by
+/// returning them or passing them to a function that takes ownership.
 #[repr(C)]
 pub struct RawAPI<Value> {
+	/// Always [`PREVAL_API_VERSION`]
+	pub version: u32,
+
 	pub drop_value: unsafe extern "C" fn(*mut Value),
+	pub clone_value: unsafe extern "C" fn(*const Value) -> *mut Value,
+	pub value_kind: unsafe extern "C" fn(*const Value) -> ValueKind,
+	/// Type id of a value, which is always a concrete type rather than a union, or
+	/// [`PREVAL_NO_TYPE`] if it fails
+	pub value_type: unsafe extern "C" fn(*const Value) -> usize,
+	/// Whether a type is the same as another, or one of the members of a union
+	pub type_is: unsafe extern "C" fn(usize, usize) -> bool,
+
+	pub usize_value: unsafe extern "C" fn(*const Value) -> usize,
+	pub new_usize_value: unsafe extern "C" fn(usize) -> *mut Value,
+	pub bool_value: unsafe extern "C" fn(*const Value) -> bool,
+	pub new_bool_value: unsafe extern "C" fn(bool) -> *mut Value,
 	pub string_value_length: unsafe extern "C" fn(*const Value) -> usize,
 	pub string_value_start: unsafe extern "C" fn(*const Value) -> *const u8,
-	pub new_tuple_value: unsafe extern "C" fn() -> *mut Value,
 	pub new_string_value: unsafe extern "C" fn(*const u8, usize) -> *mut Value,
+	pub new_tuple_value: unsafe extern "C" fn() -> *mut Value,
+
+	/// Makes a struct of a type id with no fields, which are then added with `set_struct_field`
+	pub new_struct_value: unsafe extern "C" fn(usize) -> *mut Value,
+	/// Copies a field by name, or returns null if the struct doesn't have it or it isn't known
+	pub struct_field: unsafe extern "C" fn(*const Value, *const u8, usize) -> *mut Value,
+	/// Sets a field by name, taking ownership of the new value
+	pub set_struct_field: unsafe extern "C" fn(*mut Value, *const u8, usize, *mut Value),
+
+	/// Makes an empty list of a type id
+	pub new_list_value: unsafe extern "C" fn(usize) -> *mut Value,
+	pub list_length: unsafe extern "C" fn(*const Value) -> usize,
+	/// Copies an element, or returns null if the index is out of range
+	pub list_get: unsafe extern "C" fn(*const Value, usize) -> *mut Value,
+	/// Appends an element, taking ownership of it
+	pub list_push: unsafe extern "C" fn(*mut Value, *mut Value),
+
+	/// Makes the call fail with a message instead of returning, so natives can
+	/// `return api->raise_error(message, length);`. Always returns null. If the call has
+	/// already failed, like from misusing the API, the earlier failure is kept.
+	pub raise_error: unsafe extern "C" fn(*const u8, usize) -> *mut Value,
+
+	/// Calls a function value with borrowed arguments, which are null when unknown.
+	/// Returns null if the result is only known at run time, in which case the native
+	/// should return null too, or if the function fails, which fails the native call.
+	pub call: unsafe extern "C" fn(*const Value, usize, *const *const Value) -> *mut Value,
 }
 
+/// Name of the function every library exports to say which API it was built against
+pub const VERSION_SYMBOL: &str = "preval_api_version";
+
+/// Type of `preval_api_version`, which returns the [`PREVAL_API_VERSION`] the library
+/// was built with. Libraries without it, or built with another version, aren't loaded.
+pub type ApiVersion = unsafe extern "C" fn() -> u32;
+
+/// Name of the optional function a library exports to describe its functions
+pub const METADATA_SYMBOL: &str = "preval_metadata";
+
+/// Type of `preval_metadata`, which returns a null-terminated string declaring the
+/// library's functions in Preval, like `fn print(io: IO, message: String);`
+pub type Metadata = unsafe extern "C" fn() -> *const c_char;
+
 pub struct Value {
 	p: PhantomData<()>,
 }
@@ -0,0 +1,20 @@
+//! `stdlib/print.c` written with the SDK, built with `cargo build --example print`
+
+use std::io::BufRead;
+
+use preval_sdk::IO;
+
+preval_sdk::functions! {
+	fn print(_io: IO, message: String) {
+		println!("{message}");
+	}
+
+	fn input(_io: IO) -> Result<String, String> {
+		let mut line = String::new();
+		std::io::stdin()
+			.lock()
+			.read_line(&mut line)
+			.map_err(|error| format!("couldn't read input: {error}"))?;
+		Ok(line.trim_end_matches(['\n', '\r']).to_string())
+	}
+}
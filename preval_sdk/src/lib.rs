@@ -0,0 +1,314 @@
+//! Writing Preval native libraries in Rust, without handling raw pointers.
+//!
+//! ```ignore
+//! preval_sdk::functions! {
+//!     fn shout(_io: IO, message: String) -> String {
+//!         message.to_uppercase()
+//!     }
+//! }
+//! ```
+//!
+//! Each function is exported with the signature natives are called with, and only
+//! runs once all its arguments are known, so functions taking an `IO` run at
+//! compile time when given `compile_io`. The library's metadata is exported too,
+//! letting `compile` check `dylib` declarations against it.
+
+use std::{
+	ffi::CString,
+	fmt::Display,
+	panic::{self, AssertUnwindSafe},
+	ptr::null_mut,
+};
+
+pub use preval_api::{API, PREVAL_API_VERSION, Value, ValueKind};
+
+/// The `IO` a function is given, which is only known when it's `compile_io` or the
+/// program is running
+pub struct IO;
+
+/// Types functions can take
+pub trait Arg: Sized {
+	/// How the type is written in Preval
+	const NAME: &'static str;
+
+	/// Converts a value, or returns `None` if it's a different kind of value
+	fn from_value(api: &Api, value: &Value) -> Option<Self>;
+}
+
+/// Types functions can return
+pub trait Return {
+	/// How the type is written in Preval, or `None` for `()`
+	const NAME: Option<&'static str>;
+
+	fn into_value(self, api: &Api) -> *mut Value;
+}
+
+/// The functions a native call is given for working with values
+pub struct Api<'a>(&'a API);
+
+impl Api<'_> {
+	/// Wraps the API a native was called with
+	///
+	/// # Safety
+	/// `api` must be the pointer given to the native function, and outlive the wrapper.
+	pub unsafe fn from_raw(api: *const API) -> Self {
+		Self(unsafe { &*api })
+	}
+
+	pub fn version(&self) -> u32 {
+		self.0.version
+	}
+
+	pub fn kind(&self, value: &Value) -> ValueKind {
+		unsafe { (self.0.value_kind)(value) }
+	}
+
+	/// Fails the native call with `message`, returning what the call should return
+	pub fn raise_error(&self, message: &str) -> *mut Value {
+		unsafe { (self.0.raise_error)(message.as_ptr(), message.len()) }
+	}
+
+	pub fn usize(&self, value: usize) -> *mut Value {
+		unsafe { (self.0.new_usize_value)(value) }
+	}
+
+	pub fn bool(&self, value: bool) -> *mut Value {
+		unsafe { (self.0.new_bool_value)(value) }
+	}
+
+	pub fn string(&self, value: &str) -> *mut Value {
+		unsafe { (self.0.new_string_value)(value.as_ptr(), value.len()) }
+	}
+
+	pub fn unit(&self) -> *mut Value {
+		unsafe { (self.0.new_tuple_value)() }
+	}
+}
+
+impl Arg for IO {
+	const NAME: &'static str = "IO";
+
+	fn from_value(_api: &Api, _value: &Value) -> Option<Self> {
+		Some(IO)
+	}
+}
+
+impl Arg for usize {
+	const NAME: &'static str = "usize";
+
+	fn from_value(api: &Api, value: &Value) -> Option<Self> {
+		(api.kind(value) == ValueKind::USize).then(|| unsafe { (api.0.usize_value)(value) })
+	}
+}
+
+impl Arg for bool {
+	const NAME: &'static str = "bool";
+
+	fn from_value(api: &Api, value: &Value) -> Option<Self> {
+		(api.kind(value) == ValueKind::Bool).then(|| unsafe { (api.0.bool_value)(value) })
+	}
+}
+
+impl Arg for String {
+	const NAME: &'static str = "String";
+
+	fn from_value(api: &Api, value: &Value) -> Option<Self> {
+		if api.kind(value) != ValueKind::String {
+			return None;
+		}
+		let bytes = unsafe {
+			let len = (api.0.string_value_length)(value);
+			match len {
+				0 => &[],
+				_ => std::slice::from_raw_parts((api.0.string_value_start)(value), len),
+			}
+		};
+		Some(String::from_utf8_lossy(bytes).into_owned())
+	}
+}
+
+impl Return for () {
+	const NAME: Option<&'static str> = None;
+
+	fn into_value(self, api: &Api) -> *mut Value {
+		api.unit()
+	}
+}
+
+impl Return for usize {
+	const NAME: Option<&'static str> = Some("usize");
+
+	fn into_value(self, api: &Api) -> *mut Value {
+		api.usize(self)
+	}
+}
+
+impl Return for bool {
+	const NAME: Option<&'static str> = Some("bool");
+
+	fn into_value(self, api: &Api) -> *mut Value {
+		api.bool(self)
+	}
+}
+
+impl Return for String {
+	const NAME: Option<&'static str> = Some("String");
+
+	fn into_value(self, api: &Api) -> *mut Value {
+		api.string(&self)
+	}
+}
+
+/// Errors fail the call with their message
+impl<T: Return, E: Display> Return for Result<T, E> {
+	const NAME: Option<&'static str> = T::NAME;
+
+	fn into_value(self, api: &Api) -> *mut Value {
+		match self {
+			Ok(value) => value.into_value(api),
+			Err(error) => api.raise_error(&error.to_string()),
+		}
+	}
+}
+
+/// Arguments a native was called with
+///
+/// # Safety
+/// `args` must point to `argc` values, as given to the native function.
+#[doc(hidden)]
+pub unsafe fn args<'a>(argc: usize, args: *const *const Value) -> &'a [*const Value] {
+	match argc {
+		0 => &[],
+		_ => unsafe { std::slice::from_raw_parts(args, argc) },
+	}
+}
+
+/// Converts an argument, failing with null if it isn't known yet so the call is left
+/// for run time, or with an error if it's the wrong kind of value
+///
+/// # Safety
+/// `value` must be null or one of the arguments given to the native function.
+#[doc(hidden)]
+pub unsafe fn arg<T: Arg>(api: &Api, name: &str, value: *const Value) -> Result<T, *mut Value> {
+	let Some(value) = (unsafe { value.as_ref() }) else {
+		return Err(null_mut());
+	};
+	T::from_value(api, value)
+		.ok_or_else(|| api.raise_error(&format!("`{name}` isn't a {}", T::NAME)))
+}
+
+/// Runs a function's body, turning a panic into an error rather than unwinding into
+/// the compiler
+#[doc(hidden)]
+pub fn run<T: Return>(api: &Api, body: impl FnOnce() -> T) -> *mut Value {
+	match panic::catch_unwind(AssertUnwindSafe(body)) {
+		Ok(value) => value.into_value(api),
+		Err(payload) => {
+			let message = payload
+				.downcast_ref::<&str>()
+				.map(|message| message.to_string())
+				.or_else(|| payload.downcast_ref::<String>().cloned())
+				.unwrap_or_else(|| "panicked".to_string());
+			api.raise_error(&message)
+		}
+	}
+}
+
+/// A function as it's declared in the library's metadata
+#[doc(hidden)]
+pub struct Declaration {
+	pub name: &'static str,
+	pub args: &'static [(&'static str, &'static str)],
+	pub returns: Option<&'static str>,
+}
+
+/// The text `preval_metadata` returns
+#[doc(hidden)]
+pub fn metadata(declarations: &[Declaration]) -> CString {
+	let mut text = String::new();
+	for declaration in declarations {
+		let args = declaration
+			.args
+			.iter()
+			.map(|(name, typ)| format!("{name}: {typ}"))
+			.collect::<Vec<_>>()
+			.join(", ");
+		text += &format!("fn {}({args})", declaration.name);
+		if let Some(returns) = declaration.returns {
+			text += &format!(": {returns}");
+		}
+		text += ";\n";
+	}
+	CString::new(text).expect("declarations don't contain nul")
+}
+
+/// Exports native functions along with the library's metadata. Can only be used
+/// once per library.
+#[macro_export]
+macro_rules! functions {
+	(@returns) => { () };
+	(@returns $returns:ty) => { $returns };
+
+	($(fn $name:ident($($arg:ident: $typ:ty),* $(,)?) $(-> $returns:ty)? $body:block)*) => {
+		$(
+			/// # Safety
+			/// Only called by Preval, with the arguments of a native call.
+			#[unsafe(no_mangle)]
+			pub unsafe extern "C" fn $name(
+				api: *const $crate::API,
+				argc: usize,
+				args: *const *const $crate::Value,
+			) -> *mut $crate::Value {
+				fn body($($arg: $typ),*) $(-> $returns)? $body
+
+				let api = unsafe { $crate::Api::from_raw(api) };
+				if api.version() != $crate::PREVAL_API_VERSION {
+					return api.raise_error(&format!(
+						"`{}` was built for version {} of the native API, but was given version {}",
+						stringify!($name),
+						$crate::PREVAL_API_VERSION,
+						api.version()
+					));
+				}
+				let args = unsafe { $crate::args(argc, args) };
+				let names: &[&str] = &[$(stringify!($arg)),*];
+				if args.len() != names.len() {
+					return api.raise_error(&format!(
+						"`{}` takes {} arguments, but was given {}",
+						stringify!($name),
+						names.len(),
+						args.len()
+					));
+				}
+
+				let mut args = args.iter();
+				$(
+					let $arg = match unsafe { $crate::arg::<$typ>(&api, stringify!($arg), *args.next().unwrap()) } {
+						Ok(value) => value,
+						Err(result) => return result,
+					};
+				)*
+				$crate::run(&api, || body($($arg),*))
+			}
+		)*
+
+		#[unsafe(no_mangle)]
+		pub extern "C" fn preval_api_version() -> u32 {
+			$crate::PREVAL_API_VERSION
+		}
+
+		#[unsafe(no_mangle)]
+		pub extern "C" fn preval_metadata() -> *const ::std::ffi::c_char {
+			static METADATA: ::std::sync::OnceLock<::std::ffi::CString> = ::std::sync::OnceLock::new();
+			METADATA
+				.get_or_init(|| {
+					$crate::metadata(&[$($crate::Declaration {
+						name: stringify!($name),
+						args: &[$((stringify!($arg), <$typ as $crate::Arg>::NAME)),*],
+						returns: <$crate::functions!(@returns $($returns)?) as $crate::Return>::NAME,
+					}),*])
+				})
+				.as_ptr()
+		}
+	};
+}
@@ -0,0 +1,285 @@
+//! Calls functions exported by `functions!` through an API backed by boxed values
+
+use std::{cell::RefCell, ffi::CStr, ptr::null_mut};
+
+use preval_sdk::{API, IO, PREVAL_API_VERSION, Value, ValueKind};
+
+preval_sdk::functions! {
+	fn test_add(a: usize, b: usize) -> usize {
+		a + b
+	}
+
+	fn test_shout(_io: IO, message: String) -> String {
+		message.to_uppercase()
+	}
+
+	fn test_fails(reason: String) -> Result<bool, String> {
+		Err(reason)
+	}
+
+	fn test_panics(n: usize) {
+		panic!("{n} is too many");
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Mock {
+	USize(usize),
+	Bool(bool),
+	String(String),
+	Unit,
+}
+
+thread_local! {
+	static ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn new(mock: Mock) -> *mut Value {
+	Box::into_raw(Box::new(mock)).cast()
+}
+
+fn mock<'a>(value: *const Value) -> &'a Mock {
+	unsafe { &*value.cast::<Mock>() }
+}
+
+unsafe extern "C" fn drop_value(value: *mut Value) {
+	drop(unsafe { Box::from_raw(value.cast::<Mock>()) });
+}
+
+unsafe extern "C" fn clone_value(value: *const Value) -> *mut Value {
+	new(mock(value).clone())
+}
+
+unsafe extern "C" fn value_kind(value: *const Value) -> ValueKind {
+	match mock(value) {
+		Mock::USize(_) => ValueKind::USize,
+		Mock::Bool(_) => ValueKind::Bool,
+		Mock::String(_) => ValueKind::String,
+		Mock::Unit => ValueKind::Tuple,
+	}
+}
+
+unsafe extern "C" fn usize_value(value: *const Value) -> usize {
+	match mock(value) {
+		Mock::USize(n) => *n,
+		_ => 0,
+	}
+}
+
+unsafe extern "C" fn new_usize_value(n: usize) -> *mut Value {
+	new(Mock::USize(n))
+}
+
+unsafe extern "C" fn bool_value(value: *const Value) -> bool {
+	matches!(mock(value), Mock::Bool(true))
+}
+
+unsafe extern "C" fn new_bool_value(b: bool) -> *mut Value {
+	new(Mock::Bool(b))
+}
+
+unsafe extern "C" fn string_value_length(value: *const Value) -> usize {
+	match mock(value) {
+		Mock::String(string) => string.len(),
+		_ => 0,
+	}
+}
+
+unsafe extern "C" fn string_value_start(value: *const Value) -> *const u8 {
+	match mock(value) {
+		Mock::String(string) => string.as_ptr(),
+		_ => std::ptr::null(),
+	}
+}
+
+unsafe extern "C" fn new_string_value(start: *const u8, len: usize) -> *mut Value {
+	let bytes = unsafe { std::slice::from_raw_parts(start, len) };
+	new(Mock::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+unsafe extern "C" fn new_tuple_value() -> *mut Value {
+	new(Mock::Unit)
+}
+
+unsafe extern "C" fn raise_error(start: *const u8, len: usize) -> *mut Value {
+	let bytes = unsafe { std::slice::from_raw_parts(start, len) };
+	let message = String::from_utf8_lossy(bytes).into_owned();
+	// Like the real API, the first failure is the one kept
+	ERROR.with_borrow_mut(|error| {
+		error.get_or_insert(message);
+	});
+	null_mut()
+}
+
+// The functions under test don't use types, structs, lists or callbacks
+unsafe extern "C" fn value_type(_: *const Value) -> usize {
+	unimplemented!()
+}
+
+unsafe extern "C" fn type_is(_: usize, _: usize) -> bool {
+	unimplemented!()
+}
+
+unsafe extern "C" fn new_with_type(_: usize) -> *mut Value {
+	unimplemented!()
+}
+
+unsafe extern "C" fn struct_field(_: *const Value, _: *const u8, _: usize) -> *mut Value {
+	unimplemented!()
+}
+
+unsafe extern "C" fn set_struct_field(_: *mut Value, _: *const u8, _: usize, _: *mut Value) {
+	unimplemented!()
+}
+
+unsafe extern "C" fn list_length(_: *const Value) -> usize {
+	unimplemented!()
+}
+
+unsafe extern "C" fn list_get(_: *const Value, _: usize) -> *mut Value {
+	unimplemented!()
+}
+
+unsafe extern "C" fn list_push(_: *mut Value, _: *mut Value) {
+	unimplemented!()
+}
+
+unsafe extern "C" fn call(_: *const Value, _: usize, _: *const *const Value) -> *mut Value {
+	unimplemented!()
+}
+
+const MOCK_API: API = API {
+	version: PREVAL_API_VERSION,
+	drop_value,
+	clone_value,
+	value_kind,
+	value_type,
+	type_is,
+	usize_value,
+	new_usize_value,
+	bool_value,
+	new_bool_value,
+	string_value_length,
+	string_value_start,
+	new_string_value,
+	new_tuple_value,
+	new_struct_value: new_with_type,
+	struct_field,
+	set_struct_field,
+	new_list_value: new_with_type,
+	list_length,
+	list_get,
+	list_push,
+	raise_error,
+	call,
+};
+
+type Native = unsafe extern "C" fn(*const API, usize, *const *const Value) -> *mut Value;
+
+/// Calls `function` with `args`, null for unknown ones, returning its result or the
+/// error it raised
+fn call_with(api: &API, function: Native, args: Vec<Option<Mock>>) -> Result<Option<Mock>, String> {
+	ERROR.set(None);
+	let args: Vec<*const Value> = args
+		.into_iter()
+		.map(|arg| arg.map_or(std::ptr::null(), |arg| new(arg).cast_const()))
+		.collect();
+	let result = unsafe { function(api, args.len(), args.as_ptr()) };
+	for arg in args.into_iter().filter(|arg| !arg.is_null()) {
+		unsafe { drop_value(arg.cast_mut()) };
+	}
+
+	if let Some(error) = ERROR.take() {
+		return Err(error);
+	}
+	Ok((!result.is_null()).then(|| *unsafe { Box::from_raw(result.cast::<Mock>()) }))
+}
+
+fn call_native(function: Native, args: Vec<Option<Mock>>) -> Result<Option<Mock>, String> {
+	call_with(&MOCK_API, function, args)
+}
+
+#[test]
+fn converts_arguments_and_results() {
+	assert_eq!(
+		call_native(test_add, vec![Some(Mock::USize(2)), Some(Mock::USize(3))]),
+		Ok(Some(Mock::USize(5)))
+	);
+	assert_eq!(
+		call_native(
+			test_shout,
+			vec![Some(Mock::Unit), Some(Mock::String("hi".to_string()))]
+		),
+		Ok(Some(Mock::String("HI".to_string())))
+	);
+}
+
+#[test]
+fn waits_for_unknown_arguments() {
+	assert_eq!(
+		call_native(test_add, vec![Some(Mock::USize(2)), None]),
+		Ok(None)
+	);
+	// `IO` is only known at compile time when it's `compile_io`
+	assert_eq!(
+		call_native(test_shout, vec![None, Some(Mock::String("hi".to_string()))]),
+		Ok(None)
+	);
+}
+
+#[test]
+fn rejects_wrong_arguments() {
+	assert_eq!(
+		call_native(test_add, vec![Some(Mock::USize(2)), Some(Mock::Bool(true))]),
+		Err("`b` isn't a usize".to_string())
+	);
+	assert_eq!(
+		call_native(test_add, vec![Some(Mock::USize(2))]),
+		Err("`test_add` takes 2 arguments, but was given 1".to_string())
+	);
+}
+
+#[test]
+fn errors_and_panics_fail_the_call() {
+	assert_eq!(
+		call_native(test_fails, vec![Some(Mock::String("no".to_string()))]),
+		Err("no".to_string())
+	);
+	assert_eq!(
+		call_native(test_panics, vec![Some(Mock::USize(3))]),
+		Err("3 is too many".to_string())
+	);
+}
+
+#[test]
+fn checks_the_api_version() {
+	let api = API {
+		version: PREVAL_API_VERSION + 1,
+		..MOCK_API
+	};
+	assert_eq!(
+		call_with(
+			&api,
+			test_add,
+			vec![Some(Mock::USize(2)), Some(Mock::USize(3))]
+		),
+		Err(format!(
+			"`test_add` was built for version {PREVAL_API_VERSION} of the native API, but was given version {}",
+			PREVAL_API_VERSION + 1
+		))
+	);
+}
+
+#[test]
+fn exports_metadata() {
+	assert_eq!(preval_api_version(), PREVAL_API_VERSION);
+	let metadata = unsafe { CStr::from_ptr(preval_metadata()) };
+	assert_eq!(
+		metadata.to_str(),
+		Ok("fn test_add(a: usize, b: usize): usize;
+fn test_shout(_io: IO, message: String): String;
+fn test_fails(reason: String): bool;
+fn test_panics(n: usize);
+")
+	);
+}
@@ -9,6 +9,16 @@
 #define EXPORT
 #endif
 
+EXPORT uint32_t preval_api_version(void) {
+    return PREVAL_API_VERSION;
+}
+
+EXPORT const char* preval_metadata(void) {
+    return
+        "fn print(io: IO, message: String);"
+        "fn input(io: IO): String;";
+}
+
 EXPORT Value* print(API* api, size_t argc, const Value * const * args) {
     if (!args[0] || !args[1]) {
         return NULL;
@@ -33,8 +43,8 @@ EXPORT Value* input(API* api, size_t argc, const Value * const * args) {
             uint8_t *tmp = realloc(buffer, size + 2); // +1 char +1 '\0'
             if (!tmp) {
                 free(buffer);
-                printf("ERROR! TODO: make a way for errors to be propagated to preval");
-                exit(1);
+                const char* message = "out of memory while reading input";
+                return api->raise_error((const uint8_t*)message, strlen(message));
             }
             buffer = tmp;
             buffer[size++] = c;